@@ -0,0 +1,389 @@
+// cli/src/args.rs
+//! Command-line arguments - Every `anttp` command and option
+//!
+//! For 1st Year CS Students:
+//! clap turns these structs into a parser: each field is an option or
+//! argument, each enum variant a subcommand, and the `///` comments
+//! become the `--help` text. `anttp kv put --help` is generated from
+//! `KvCommand::Put` below.
+
+use anttp_rust_backend::models::{BundleFormat, GraphExportFormat, PnrRecordType, StoreType};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+/// Command-line tool for the AntTP-compatible backend
+#[derive(Debug, Parser)]
+#[command(name = "anttp", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub connection: Connection,
+
+    /// Print results as JSON instead of text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Where requests go, and the headers sent with each one
+#[derive(Debug, Clone, Args)]
+pub struct Connection {
+    /// URL of a running anttp-backend
+    #[arg(
+        long,
+        env = "ANTTP_URL",
+        default_value = "http://127.0.0.1:18888",
+        global = true
+    )]
+    pub url: String,
+
+    /// Run the API inside this process instead of using a server
+    ///
+    /// Nothing is kept after the command ends - use `batch` to run several
+    /// commands against the same embedded store.
+    #[arg(long, global = true)]
+    pub embedded: bool,
+
+    /// Where to read and write: memory, disk or network
+    #[arg(long = "store", env = "ANTTP_STORE_TYPE", global = true)]
+    pub store_type: Option<StoreType>,
+
+    /// Key proving ownership of PNR names
+    #[arg(long, env = "ANTTP_OWNER_KEY", hide_env_values = true, global = true)]
+    pub owner_key: Option<String>,
+
+    /// API token, for servers with authentication enabled
+    #[arg(long, env = "ANTTP_TOKEN", hide_env_values = true, global = true)]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Check that the server answers
+    Health,
+
+    /// Upload a file as public data
+    Upload { file: PathBuf },
+
+    /// Download public data, or one file of an archive (ADDRESS/PATH)
+    Download {
+        /// Address, or archive address and file path ("<address>/css/site.css")
+        address: String,
+        /// Write to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Archives: publish directories, list and download them
+    #[command(subcommand)]
+    Archive(ArchiveCommand),
+
+    /// Registers: small values with a history
+    #[command(subcommand)]
+    Register(RegisterCommand),
+
+    /// Pointers: mutable references to other addresses
+    #[command(subcommand)]
+    Pointer(PointerCommand),
+
+    /// Scratchpads: public or private mutable data
+    #[command(subcommand)]
+    Scratchpad(ScratchpadCommand),
+
+    /// Key/value objects in buckets
+    #[command(subcommand)]
+    Kv(KvCommand),
+
+    /// PNR names (like DNS for addresses)
+    #[command(subcommand)]
+    Pnr(PnrCommand),
+
+    /// Inspect graph entries
+    #[command(subcommand)]
+    Graph(GraphCommand),
+
+    /// Run commands from a file (or standard input), one per line
+    ///
+    /// Lines are split like a shell would ("quoted words" stay together);
+    /// empty lines and lines starting with # are skipped. `$address` is
+    /// replaced by the address the previous command printed. Stops at the
+    /// first command that fails.
+    Batch {
+        /// Command file ("-" or nothing for standard input)
+        file: Option<PathBuf>,
+    },
+}
+
+/// A command inside `anttp batch` (the same commands, without options)
+#[derive(Debug, Parser)]
+#[command(no_binary_name = true)]
+pub struct BatchLine {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ArchiveCommand {
+    /// Upload every file in a directory as one archive
+    Publish {
+        directory: PathBuf,
+        /// Leave out paths matching this glob (repeatable, e.g. "*.tmp")
+        #[arg(long)]
+        ignore: Vec<String>,
+        /// Point this PNR name ("@" record) at the new archive
+        #[arg(long)]
+        pnr: Option<String>,
+    },
+
+    /// List the files in an archive
+    Ls { address: String },
+
+    /// Download a whole archive as a zip, tar or tar.gz file
+    Download {
+        address: String,
+        #[arg(long, value_enum, default_value_t = BundleArg::Zip)]
+        format: BundleArg,
+        /// Only files under this folder
+        #[arg(long)]
+        prefix: Option<String>,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Which files differ between two archives
+    Diff { from: String, to: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RegisterCommand {
+    /// Create a register (CONTENT is hex)
+    Create {
+        name: String,
+        content: String,
+    },
+    Get {
+        address: String,
+    },
+    /// Write a new value (CONTENT is hex)
+    Update {
+        address: String,
+        name: String,
+        content: String,
+    },
+    /// Every value the register has had
+    History {
+        address: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PointerCommand {
+    /// Create a pointer to TARGET
+    Create {
+        name: String,
+        target: String,
+    },
+    Get {
+        address: String,
+    },
+    /// Point at a new TARGET
+    Update {
+        address: String,
+        name: String,
+        target: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ScratchpadCommand {
+    /// Create a scratchpad (VALUE is text, @file or - for standard input)
+    Create {
+        name: String,
+        value: String,
+        #[arg(long)]
+        private: bool,
+    },
+    /// Print a scratchpad's content
+    Get {
+        address: String,
+        /// Name of a private scratchpad
+        #[arg(long, value_name = "NAME")]
+        private: Option<String>,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Replace the content (VALUE is text, @file or - for standard input)
+    Update {
+        address: String,
+        name: String,
+        value: String,
+        #[arg(long)]
+        private: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KvCommand {
+    /// List all buckets
+    Buckets,
+    /// Create a bucket
+    CreateBucket {
+        bucket: String,
+        /// Keep every version of every object
+        #[arg(long)]
+        versioning: bool,
+    },
+    /// Delete an empty bucket
+    DeleteBucket { bucket: String },
+    /// List the objects in a bucket
+    List {
+        bucket: String,
+        #[arg(long)]
+        prefix: Option<String>,
+    },
+    /// Store an object (VALUE is text, @file or - for standard input)
+    Put {
+        bucket: String,
+        object: String,
+        value: String,
+        #[arg(long, default_value = "application/octet-stream")]
+        content_type: String,
+    },
+    /// Print an object's content
+    Get {
+        bucket: String,
+        object: String,
+        #[arg(long)]
+        version: Option<String>,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Show an object's size, type and version without its content
+    Head {
+        bucket: String,
+        object: String,
+        #[arg(long)]
+        version: Option<String>,
+    },
+    /// Delete an object (or one version of it)
+    Delete {
+        bucket: String,
+        object: String,
+        #[arg(long)]
+        version: Option<String>,
+    },
+    /// List an object's versions
+    Versions { bucket: String, object: String },
+}
+
+/// Records for a PNR name
+#[derive(Debug, Args)]
+pub struct PnrRecords {
+    /// Records as SUB=ADDRESS ("www=<address>"); a bare ADDRESS is the "@" record
+    #[arg(required = true)]
+    pub records: Vec<String>,
+    #[arg(long = "type", value_enum, default_value_t = RecordTypeArg::A)]
+    pub record_type: RecordTypeArg,
+    #[arg(long, default_value_t = 60)]
+    pub ttl: u32,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PnrCommand {
+    /// Register a name (owned by --owner-key, if given)
+    Create {
+        name: String,
+        #[command(flatten)]
+        records: PnrRecords,
+    },
+    /// Show a name's records
+    Get { name: String },
+    /// Replace all of a name's records
+    Update {
+        name: String,
+        #[command(flatten)]
+        records: PnrRecords,
+    },
+    /// Add or replace some records, keeping the others
+    Append {
+        name: String,
+        #[command(flatten)]
+        records: PnrRecords,
+    },
+    /// Delete a name
+    Delete { name: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GraphCommand {
+    /// Show one entry (content and descendants)
+    Get { address: String },
+    /// Export an entry and everything reachable from it
+    Export {
+        address: String,
+        #[arg(long, value_enum, default_value_t = GraphFormatArg::Dot)]
+        format: GraphFormatArg,
+        /// Stop this many links away from the start
+        #[arg(long)]
+        depth: Option<usize>,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+// ============================================================================
+// VALUE ENUMS - The models' enums, spelled for the command line
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BundleArg {
+    Zip,
+    Tar,
+    #[value(name = "tar.gz")]
+    TarGz,
+}
+
+impl From<BundleArg> for BundleFormat {
+    fn from(format: BundleArg) -> Self {
+        match format {
+            BundleArg::Zip => Self::Zip,
+            BundleArg::Tar => Self::Tar,
+            BundleArg::TarGz => Self::TarGz,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GraphFormatArg {
+    Dot,
+    Json,
+    Graphml,
+}
+
+impl From<GraphFormatArg> for GraphExportFormat {
+    fn from(format: GraphFormatArg) -> Self {
+        match format {
+            GraphFormatArg::Dot => Self::Dot,
+            GraphFormatArg::Json => Self::Json,
+            GraphFormatArg::Graphml => Self::Graphml,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RecordTypeArg {
+    A,
+    X,
+    Txt,
+}
+
+impl From<RecordTypeArg> for PnrRecordType {
+    fn from(record_type: RecordTypeArg) -> Self {
+        match record_type {
+            RecordTypeArg::A => Self::A,
+            RecordTypeArg::X => Self::X,
+            RecordTypeArg::Txt => Self::Txt,
+        }
+    }
+}
@@ -0,0 +1,518 @@
+// cli/src/commands.rs
+//! Commands - What each `anttp` subcommand does
+//!
+//! For 1st Year CS Students:
+//! Each command is one or two client calls. The answer is turned into a
+//! `serde_json::Value` for `output.rs` to print. Commands that fetch
+//! content (download, kv get, ...) write the bytes themselves - to the
+//! `--output` file or to standard output - so they can be piped.
+
+use anttp_client::models::{
+    ArchiveDiffResponse, BucketRequest, GraphExportQuery, ListObjectsQuery, PnrRecord, PnrRequest,
+    PointerRequest, RegisterRequest, ScratchpadRequest, ScratchpadUpdateRequest, VersionQuery,
+};
+use anttp_client::{AntTpClient, ByteStream, UploadFile};
+use base64::Engine;
+use bytes::Bytes;
+use futures::TryStreamExt;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde_json::{json, Value};
+use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use walkdir::WalkDir;
+
+use crate::args::{
+    ArchiveCommand, Command, GraphCommand, KvCommand, PnrCommand, PnrRecords, PointerCommand,
+    RegisterCommand, ScratchpadCommand,
+};
+use crate::error::CliError;
+
+/// Run one command (except `batch`, which `main.rs` handles)
+pub async fn execute(client: &AntTpClient, command: Command) -> Result<Value, CliError> {
+    match command {
+        Command::Health => Ok(client.health().await?),
+        Command::Upload { file } => {
+            let content = read_file(&file).await?;
+            let size = content.len();
+            let mut created = client.create_public_data(content).await?;
+            created["size"] = size.into();
+            Ok(created)
+        }
+        Command::Download { address, output } => {
+            let stream = match address.split_once('/') {
+                Some((archive, path)) => client.get_archive_file_stream(archive, path).await?,
+                None => client.get_public_data_stream(&address).await?,
+            };
+            save(stream, output.as_deref()).await
+        }
+        Command::Archive(command) => archive(client, command).await,
+        Command::Register(command) => register(client, command).await,
+        Command::Pointer(command) => pointer(client, command).await,
+        Command::Scratchpad(command) => scratchpad(client, command).await,
+        Command::Kv(command) => key_value(client, command).await,
+        Command::Pnr(command) => pnr(client, command).await,
+        Command::Graph(command) => graph(client, command).await,
+        Command::Batch { .. } => Err(CliError::Usage(
+            "batch can't be used inside a batch".to_string(),
+        )),
+    }
+}
+
+// ============================================================================
+// ARCHIVES
+// ============================================================================
+
+async fn archive(client: &AntTpClient, command: ArchiveCommand) -> Result<Value, CliError> {
+    match command {
+        ArchiveCommand::Publish {
+            directory,
+            ignore,
+            pnr,
+        } => {
+            let files = collect_directory(&directory, &ignore)?;
+            log::info!(
+                "📤 Publishing {} files from {}",
+                files.len(),
+                directory.display()
+            );
+            let created = client.create_archive(files).await?;
+            let mut result = to_value(&created);
+            if let Some(name) = pnr {
+                point_name_at(client, &name, &created.address).await?;
+                result["pnr"] = name.into();
+            }
+            Ok(result)
+        }
+        ArchiveCommand::Ls { address } => {
+            // The listing carries every file's content - show just the paths
+            let mut listing = client.get_archive_root(&address).await?;
+            if let Some(files) = listing.get_mut("files").and_then(Value::as_array_mut) {
+                for file in files.iter_mut() {
+                    *file = file["path"].take();
+                }
+            }
+            Ok(listing)
+        }
+        ArchiveCommand::Download {
+            address,
+            format,
+            prefix,
+            output,
+        } => {
+            let stream = client
+                .download_archive_stream(&address, format.into(), prefix.as_deref())
+                .await?;
+            save(stream, output.as_deref()).await
+        }
+        ArchiveCommand::Diff { from, to } => {
+            let diff: ArchiveDiffResponse = client.get_archive_diff(&from, &to).await?;
+            Ok(to_value(&diff))
+        }
+    }
+}
+
+/// Helper: Read every file under `directory` (sorted, symlinks skipped)
+fn collect_directory(directory: &Path, ignore: &[String]) -> Result<Vec<UploadFile>, CliError> {
+    let ignore = ignore_set(ignore)?;
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(directory).sort_by_file_name() {
+        let entry = entry.map_err(|e| CliError::Usage(e.to_string()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(directory)
+            .unwrap_or(entry.path())
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if ignore.is_match(&relative) {
+            continue;
+        }
+        let content =
+            std::fs::read(entry.path()).map_err(|e| CliError::io(entry.path().display(), e))?;
+        files.push(UploadFile::new(relative, content));
+    }
+
+    if files.is_empty() {
+        return Err(CliError::Usage(format!(
+            "Nothing to publish in {}",
+            directory.display()
+        )));
+    }
+    Ok(files)
+}
+
+fn ignore_set(patterns: &[String]) -> Result<GlobSet, CliError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| CliError::Usage(format!("Invalid ignore pattern '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| CliError::Usage(e.to_string()))
+}
+
+/// Helper: Set the "@" record of `name` to `address` (creating the name if needed)
+async fn point_name_at(client: &AntTpClient, name: &str, address: &str) -> Result<(), CliError> {
+    let mut request = PnrRequest {
+        name: name.to_string(),
+        records: Default::default(),
+    };
+    request.records.insert(
+        "@".to_string(),
+        PnrRecord {
+            address: address.to_string(),
+            record_type: anttp_client::models::PnrRecordType::A,
+            ttl: 60,
+        },
+    );
+    match client.append_pnr(name, &request).await {
+        Err(error) if error.is_not_found() => {
+            client.create_pnr(&request).await?;
+        }
+        result => {
+            result?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// REGISTERS, POINTERS, SCRATCHPADS
+// ============================================================================
+
+async fn register(client: &AntTpClient, command: RegisterCommand) -> Result<Value, CliError> {
+    match command {
+        RegisterCommand::Create { name, content } => {
+            let created = client
+                .create_register(&RegisterRequest { name, content })
+                .await?;
+            Ok(to_value(&created))
+        }
+        RegisterCommand::Get { address } => Ok(to_value(&client.get_register(&address).await?)),
+        RegisterCommand::Update {
+            address,
+            name,
+            content,
+        } => Ok(client
+            .update_register(&address, &RegisterRequest { name, content })
+            .await?),
+        RegisterCommand::History { address } => {
+            Ok(to_value(&client.get_register_history(&address).await?))
+        }
+    }
+}
+
+async fn pointer(client: &AntTpClient, command: PointerCommand) -> Result<Value, CliError> {
+    match command {
+        PointerCommand::Create { name, target } => {
+            let request = PointerRequest {
+                name,
+                content: target,
+            };
+            Ok(to_value(&client.create_pointer(&request).await?))
+        }
+        PointerCommand::Get { address } => Ok(to_value(&client.get_pointer(&address).await?)),
+        PointerCommand::Update {
+            address,
+            name,
+            target,
+        } => {
+            let request = PointerRequest {
+                name,
+                content: target,
+            };
+            Ok(client.update_pointer(&address, &request).await?)
+        }
+    }
+}
+
+async fn scratchpad(client: &AntTpClient, command: ScratchpadCommand) -> Result<Value, CliError> {
+    match command {
+        ScratchpadCommand::Create {
+            name,
+            value,
+            private,
+        } => {
+            let request = ScratchpadRequest {
+                name,
+                content: base64(&read_value(&value).await?),
+            };
+            let created = if private {
+                client.create_private_scratchpad(&request).await?
+            } else {
+                client.create_public_scratchpad(&request).await?
+            };
+            Ok(to_value(&created))
+        }
+        ScratchpadCommand::Get {
+            address,
+            private,
+            output,
+        } => {
+            let data = match private {
+                Some(name) => client.get_private_scratchpad(&address, &name).await?,
+                None => client.get_public_scratchpad(&address).await?,
+            };
+            let content = base64::engine::general_purpose::STANDARD
+                .decode(&data.content)
+                .map_err(|e| CliError::Usage(format!("Scratchpad content isn't base64: {}", e)))?;
+            write_all(Bytes::from(content), output.as_deref()).await
+        }
+        ScratchpadCommand::Update {
+            address,
+            name,
+            value,
+            private,
+        } => {
+            let request = ScratchpadUpdateRequest {
+                content: base64(&read_value(&value).await?),
+            };
+            if private {
+                Ok(client
+                    .update_private_scratchpad(&address, &name, &request)
+                    .await?)
+            } else {
+                Ok(client
+                    .update_public_scratchpad(&address, &name, &request)
+                    .await?)
+            }
+        }
+    }
+}
+
+// ============================================================================
+// KEY/VALUE
+// ============================================================================
+
+async fn key_value(client: &AntTpClient, command: KvCommand) -> Result<Value, CliError> {
+    match command {
+        KvCommand::Buckets => Ok(to_value(&client.list_buckets().await?)),
+        KvCommand::CreateBucket { bucket, versioning } => {
+            let request = BucketRequest {
+                versioning: versioning.then_some(true),
+            };
+            Ok(to_value(&client.create_bucket(&bucket, &request).await?))
+        }
+        KvCommand::DeleteBucket { bucket } => Ok(client.delete_bucket(&bucket).await?),
+        KvCommand::List { bucket, prefix } => {
+            let query = ListObjectsQuery {
+                prefix,
+                ..Default::default()
+            };
+            Ok(to_value(&client.list_key_values(&bucket, &query).await?))
+        }
+        KvCommand::Put {
+            bucket,
+            object,
+            value,
+            content_type,
+        } => {
+            let content = read_value(&value).await?;
+            let stored = client
+                .create_key_value_binary(&bucket, &object, content, &content_type)
+                .await?;
+            Ok(to_value(&stored))
+        }
+        KvCommand::Get {
+            bucket,
+            object,
+            version,
+            output,
+        } => {
+            let query = VersionQuery {
+                version_id: version,
+            };
+            let stream = client
+                .get_key_value_stream(&bucket, &object, &query)
+                .await?;
+            save(stream, output.as_deref()).await
+        }
+        KvCommand::Head {
+            bucket,
+            object,
+            version,
+        } => {
+            let query = VersionQuery {
+                version_id: version,
+            };
+            Ok(to_value(
+                &client.head_key_value(&bucket, &object, &query).await?,
+            ))
+        }
+        KvCommand::Delete {
+            bucket,
+            object,
+            version,
+        } => {
+            let query = VersionQuery {
+                version_id: version,
+            };
+            Ok(client.delete_key_value(&bucket, &object, &query).await?)
+        }
+        KvCommand::Versions { bucket, object } => Ok(to_value(
+            &client.list_key_value_versions(&bucket, &object).await?,
+        )),
+    }
+}
+
+// ============================================================================
+// PNR AND GRAPH
+// ============================================================================
+
+async fn pnr(client: &AntTpClient, command: PnrCommand) -> Result<Value, CliError> {
+    match command {
+        PnrCommand::Create { name, records } => {
+            let request = pnr_request(&name, records)?;
+            Ok(client.create_pnr(&request).await?)
+        }
+        PnrCommand::Get { name } => Ok(client.get_pnr(&name).await?),
+        PnrCommand::Update { name, records } => {
+            let request = pnr_request(&name, records)?;
+            Ok(client.update_pnr(&name, &request).await?)
+        }
+        PnrCommand::Append { name, records } => {
+            let request = pnr_request(&name, records)?;
+            Ok(client.append_pnr(&name, &request).await?)
+        }
+        PnrCommand::Delete { name } => Ok(client.delete_pnr(&name).await?),
+    }
+}
+
+/// Helper: Turn "www=<address>" arguments into a PnrRequest
+fn pnr_request(name: &str, records: PnrRecords) -> Result<PnrRequest, CliError> {
+    let mut request = PnrRequest {
+        name: name.to_string(),
+        records: Default::default(),
+    };
+    for record in records.records {
+        let (sub_name, address) = record.split_once('=').unwrap_or(("@", &record));
+        if address.is_empty() {
+            return Err(CliError::Usage(format!(
+                "Record '{}' has no address",
+                record
+            )));
+        }
+        request.records.insert(
+            sub_name.to_string(),
+            PnrRecord {
+                address: address.to_string(),
+                record_type: records.record_type.into(),
+                ttl: records.ttl,
+            },
+        );
+    }
+    Ok(request)
+}
+
+async fn graph(client: &AntTpClient, command: GraphCommand) -> Result<Value, CliError> {
+    match command {
+        GraphCommand::Get { address } => Ok(to_value(&client.get_graph_entry(&address).await?)),
+        GraphCommand::Export {
+            address,
+            format,
+            depth,
+            output,
+        } => {
+            let query = GraphExportQuery {
+                format: format.into(),
+                depth,
+            };
+            let exported = client.export_graph(&address, &query).await?;
+            write_all(exported, output.as_deref()).await
+        }
+    }
+}
+
+// ============================================================================
+// HELPERS - Reading input, writing output
+// ============================================================================
+
+fn to_value(value: &impl serde::Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+fn base64(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+/// A VALUE argument: "-" is standard input, "@path" a file, anything else the text itself
+async fn read_value(value: &str) -> Result<Bytes, CliError> {
+    if value == "-" {
+        let mut content = Vec::new();
+        tokio::io::stdin()
+            .read_to_end(&mut content)
+            .await
+            .map_err(|e| CliError::io("standard input", e))?;
+        Ok(Bytes::from(content))
+    } else if let Some(path) = value.strip_prefix('@') {
+        read_file(Path::new(path)).await
+    } else {
+        Ok(Bytes::copy_from_slice(value.as_bytes()))
+    }
+}
+
+async fn read_file(path: &Path) -> Result<Bytes, CliError> {
+    tokio::fs::read(path)
+        .await
+        .map(Bytes::from)
+        .map_err(|e| CliError::io(path.display(), e))
+}
+
+/// Write a download to `output` (or standard output) as it arrives
+async fn save(mut stream: ByteStream, output: Option<&Path>) -> Result<Value, CliError> {
+    let name = output.map_or("standard output".to_string(), |path| {
+        path.display().to_string()
+    });
+    let mut writer = open_output(output).await?;
+    let mut written = 0u64;
+    while let Some(piece) = stream.try_next().await? {
+        writer
+            .write_all(&piece)
+            .await
+            .map_err(|e| CliError::io(&name, e))?;
+        written += piece.len() as u64;
+    }
+    writer.flush().await.map_err(|e| CliError::io(&name, e))?;
+    Ok(saved(output, written))
+}
+
+/// Write content that is already in memory to `output` (or standard output)
+async fn write_all(content: Bytes, output: Option<&Path>) -> Result<Value, CliError> {
+    let name = output.map_or("standard output".to_string(), |path| {
+        path.display().to_string()
+    });
+    let mut writer = open_output(output).await?;
+    writer
+        .write_all(&content)
+        .await
+        .map_err(|e| CliError::io(&name, e))?;
+    writer.flush().await.map_err(|e| CliError::io(&name, e))?;
+    Ok(saved(output, content.len() as u64))
+}
+
+async fn open_output(
+    output: Option<&Path>,
+) -> Result<Box<dyn AsyncWrite + Unpin + Send>, CliError> {
+    match output {
+        Some(path) => {
+            let file = tokio::fs::File::create(path)
+                .await
+                .map_err(|e| CliError::io(path.display(), e))?;
+            Ok(Box::new(file))
+        }
+        None => Ok(Box::new(tokio::io::stdout())),
+    }
+}
+
+/// What to print after a download: nothing when the content itself went to standard output
+fn saved(output: Option<&Path>, bytes: u64) -> Value {
+    match output {
+        Some(path) => json!({ "saved": path.display().to_string(), "bytes": bytes }),
+        None => Value::Null,
+    }
+}
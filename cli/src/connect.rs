@@ -0,0 +1,71 @@
+// cli/src/connect.rs
+//! Connecting - A client for a running server, or for an embedded one
+//!
+//! For 1st Year CS Students:
+//! With `--embedded` there is no server to talk to, so we start one: the
+//! same `api_server` the backend binary runs, with its own
+//! `NetworkService`, listening on a free port of 127.0.0.1 for as long as
+//! this process lives. Commands then go through the exact same handlers
+//! either way - an address computed offline is the address a server
+//! would give.
+
+use anttp_client::AntTpClient;
+use anttp_rust_backend::config::Config;
+use anttp_rust_backend::services::NetworkService;
+use anttp_rust_backend::{api_server, ApiState};
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use crate::args::Connection;
+use crate::error::CliError;
+
+/// Build the client every command uses
+pub async fn connect(connection: &Connection) -> Result<AntTpClient, CliError> {
+    let url = if connection.embedded {
+        start_embedded().await?
+    } else {
+        connection.url.clone()
+    };
+
+    let mut builder = AntTpClient::builder(url);
+    if let Some(store_type) = connection.store_type {
+        builder = builder.store_type(store_type);
+    }
+    if let Some(owner_key) = &connection.owner_key {
+        builder = builder.owner_key(owner_key.clone());
+    }
+    if let Some(token) = &connection.token {
+        builder = builder.token(token.clone());
+    }
+    Ok(builder.build()?)
+}
+
+/// Start the API in this process; returns its URL
+async fn start_embedded() -> Result<String, CliError> {
+    let listener =
+        TcpListener::bind("127.0.0.1:0").map_err(|e| CliError::Embedded(e.to_string()))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| CliError::Embedded(e.to_string()))?;
+
+    // The server's environment variables apply (DEFAULT_STORE_TYPE, ARCHIVE_PRECOMPRESS, ...)
+    let mut config = Config::from_env().map_err(|e| CliError::Embedded(e.to_string()))?;
+    // ...except those about its own listener: this one is private to the process,
+    // so it needs no TLS and no tokens
+    config.tls = Default::default();
+    config.publish = Default::default();
+    config.auth = Default::default();
+    config.logging.access_log = false;
+
+    let network = NetworkService::new()
+        .await
+        .map_err(|e| CliError::Embedded(e.to_string()))?
+        .with_precompress(config.storage.archive_precompress);
+    let state = ApiState::new(Arc::new(network), Arc::new(config))
+        .map_err(|e| CliError::Embedded(e.to_string()))?;
+    let server = api_server(listener, state).map_err(|e| CliError::Embedded(e.to_string()))?;
+    actix_rt::spawn(server);
+
+    log::debug!("Embedded API listening on {}", address);
+    Ok(format!("http://{}", address))
+}
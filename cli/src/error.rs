@@ -0,0 +1,47 @@
+// cli/src/error.rs
+//! CLI errors - Everything that ends a command with exit code 1
+
+use anttp_client::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    /// The server (or the client talking to it) failed
+    #[error(transparent)]
+    Client(#[from] ClientError),
+
+    /// A local file couldn't be read or written
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+
+    /// The command line doesn't make sense
+    #[error("{0}")]
+    Usage(String),
+
+    /// The embedded server couldn't start
+    #[error("Embedded server failed to start: {0}")]
+    Embedded(String),
+
+    /// A command in a batch file failed
+    #[error("line {line}: {source}")]
+    Batch {
+        line: usize,
+        #[source]
+        source: Box<CliError>,
+    },
+}
+
+impl CliError {
+    /// Helper: Attach the file name to an I/O error
+    pub fn io(path: impl std::fmt::Display, error: std::io::Error) -> Self {
+        Self::Io(path.to_string(), error)
+    }
+
+    /// The server's error, if this is one (looking inside batch errors)
+    pub fn client(&self) -> Option<&ClientError> {
+        match self {
+            Self::Client(error) => Some(error),
+            Self::Batch { source, .. } => source.client(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// cli/src/main.rs
+//! anttp - Command-line tool for the AntTP-compatible backend
+//!
+//! For 1st Year CS Students:
+//! This is a thin layer over the client SDK (`anttp-client`): parse the
+//! command line, make the client calls, print the answer. Try:
+//!
+//!   anttp upload photo.jpg
+//!   anttp archive publish ./site --pnr my-site --owner-key secret
+//!   anttp kv put notes today "Buy milk" --json
+//!   anttp --embedded batch script.txt
+//!
+//! Exit code 0 means success; on failure the error goes to standard error
+//! and the exit code is 1.
+
+mod args;
+mod commands;
+mod connect;
+mod error;
+mod output;
+
+use anttp_client::AntTpClient;
+use clap::Parser;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tokio::io::AsyncReadExt;
+
+use args::{BatchLine, Cli, Command};
+use error::CliError;
+
+#[actix_rt::main]
+async fn main() -> ExitCode {
+    // Only our own warnings unless RUST_LOG asks for more (errors are printed anyway,
+    // and the embedded server would log each one again)
+    let filter = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|filter| !filter.is_empty())
+        .unwrap_or_else(|| "anttp=warn,anttp_rust_backend=off".to_string());
+    env_logger::Builder::new().parse_filters(&filter).init();
+
+    let cli = Cli::parse();
+    let json = cli.json;
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            output::print_error(&error, json);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), CliError> {
+    let client = connect::connect(&cli.connection).await?;
+    match cli.command {
+        Command::Batch { file } => run_batch(&client, file, cli.json).await,
+        command => {
+            let result = commands::execute(&client, command).await?;
+            output::print(&result, cli.json);
+            Ok(())
+        }
+    }
+}
+
+// ============================================================================
+// BATCH - Several commands, one connection (and one embedded store)
+// ============================================================================
+
+async fn run_batch(
+    client: &AntTpClient,
+    file: Option<PathBuf>,
+    json: bool,
+) -> Result<(), CliError> {
+    let script = read_script(file).await?;
+    // The last address printed, for `$address` in the lines after it
+    let mut last_address: Option<String> = None;
+
+    for (index, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let in_line = |source: CliError| CliError::Batch {
+            line: index + 1,
+            source: Box::new(source),
+        };
+
+        let mut words =
+            shell_words::split(line).map_err(|e| in_line(CliError::Usage(e.to_string())))?;
+        if let Some(address) = &last_address {
+            for word in &mut words {
+                *word = word.replace("$address", address);
+            }
+        }
+        let parsed = BatchLine::try_parse_from(&words)
+            .map_err(|e| in_line(CliError::Usage(e.to_string())))?;
+
+        let result = commands::execute(client, parsed.command)
+            .await
+            .map_err(in_line)?;
+        if let Some(address) = result.get("address").and_then(Value::as_str) {
+            last_address = Some(address.to_string());
+        }
+        output::print(&result, json);
+    }
+    Ok(())
+}
+
+/// Helper: The whole batch file ("-" or `None` reads standard input)
+async fn read_script(file: Option<PathBuf>) -> Result<String, CliError> {
+    match file {
+        Some(path) if path.as_os_str() != "-" => tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| CliError::io(path.display(), e)),
+        _ => {
+            let mut script = String::new();
+            tokio::io::stdin()
+                .read_to_string(&mut script)
+                .await
+                .map_err(|e| CliError::io("standard input", e))?;
+            Ok(script)
+        }
+    }
+}
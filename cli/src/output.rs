@@ -0,0 +1,109 @@
+// cli/src/output.rs
+//! Output - Results as text for people or JSON for scripts
+//!
+//! For 1st Year CS Students:
+//! Every command produces a `serde_json::Value`. With `--json` it is
+//! printed as is; otherwise the same value is shown as indented
+//! `key: value` lines. One result type, two views - so both always
+//! contain the same information.
+
+use anttp_client::ClientError;
+use serde_json::Value;
+
+use crate::error::CliError;
+
+/// Print a command's result (`Null` prints nothing)
+pub fn print(value: &Value, json: bool) {
+    if value.is_null() {
+        return;
+    }
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    } else {
+        let mut text = String::new();
+        render(value, 0, &mut text);
+        print!("{}", text);
+    }
+}
+
+/// Print an error to standard error
+pub fn print_error(error: &CliError, json: bool) {
+    let api = match error.client() {
+        Some(ClientError::Api(api)) => Some(api),
+        _ => None,
+    };
+    if json {
+        let mut value = serde_json::json!({ "error": error.to_string() });
+        if let Some(api) = api {
+            value["error"] = api.error.clone().into();
+            value["status"] = api.status.as_u16().into();
+            value["code"] = serde_json::to_value(api.code).unwrap_or_default();
+            value["details"] = api.details.clone().into();
+            value["request_id"] = api.request_id.clone().into();
+        }
+        eprintln!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        );
+    } else {
+        eprintln!("❌ {}", error);
+        if let Some(request_id) = api.and_then(|api| api.request_id.as_deref()) {
+            eprintln!("   request id: {}", request_id);
+        }
+    }
+}
+
+/// Helper: `value` as indented text lines, appended to `out`
+fn render(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent);
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map {
+                if is_scalar(item) {
+                    out.push_str(&format!("{}{}: {}\n", pad, key, scalar(item)));
+                } else if is_empty(item) {
+                    out.push_str(&format!("{}{}: (none)\n", pad, key));
+                } else {
+                    out.push_str(&format!("{}{}:\n", pad, key));
+                    render(item, indent + 1, out);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                if is_scalar(item) {
+                    out.push_str(&format!("{}{}\n", pad, scalar(item)));
+                } else {
+                    // An object in a list: a "-" line, then its fields below it
+                    out.push_str(&format!("{}-\n", pad));
+                    render(item, indent + 1, out);
+                }
+            }
+        }
+        _ => out.push_str(&format!("{}{}\n", pad, scalar(value))),
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// Helper: Strings without their JSON quotes
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,42 @@
+// client/src/api/admin.rs
+//! Admin endpoints - Publish a server-side directory, manage API tokens
+
+use anttp_rust_backend::models::{
+    IssuedTokenResponse, PublishRequest, PublishResponse, TokenInfo, TokenListResponse,
+    TokenRequest,
+};
+use reqwest::Method;
+
+use crate::client::{json, segment, AntTpClient, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/admin/publish_directory - Publish a directory as an archive
+    pub async fn publish_directory(
+        &self,
+        request: &PublishRequest,
+    ) -> Result<PublishResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/admin/publish_directory").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/admin/token - Issue an API token (keep `token`: it isn't shown again)
+    pub async fn issue_token(
+        &self,
+        request: &TokenRequest,
+    ) -> Result<IssuedTokenResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/admin/token").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/admin/token - List API tokens (without secrets)
+    pub async fn list_tokens(&self) -> Result<TokenListResponse, ClientError> {
+        json(self.send(Call::get("/anttp-0/admin/token")).await?).await
+    }
+
+    /// DELETE /anttp-0/admin/token/{id} - Revoke an issued API token
+    pub async fn revoke_token(&self, id: &str) -> Result<TokenInfo, ClientError> {
+        let path = format!("/anttp-0/admin/token/{}", segment(id));
+        json(self.send(Call::new(Method::DELETE, path)).await?).await
+    }
+}
@@ -0,0 +1,203 @@
+// client/src/api/archives.rs
+//! Archive endpoints - File collections
+//!
+//! For Students:
+//! Archives never change: "updating" one makes a new archive and leaves
+//! the old one as it was, so every change answers with the new address.
+
+use anttp_rust_backend::models::{
+    ArchiveDiffResponse, ArchiveDownloadQuery, ArchiveManifestRequest, ArchivePatchRequest,
+    ArchiveSyncPlan, ArchiveUpdateQuery, ArchiveUpdateResponse, ArchiveUploadResponse,
+    BundleFormat, ZipUploadResponse,
+};
+use bytes::Bytes;
+use futures::Stream;
+use reqwest::Method;
+
+use crate::client::{
+    bytes, json, segment, segments, stream, AntTpClient, ByteStream, Call, UploadFile,
+};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/multipart/public_archive - Create archive
+    pub async fn create_archive(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<ArchiveUploadResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/multipart/public_archive").multipart(files);
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/multipart/public_archive/{path} - Create archive, every file under `path`
+    pub async fn create_archive_with_path(
+        &self,
+        path: &str,
+        files: Vec<UploadFile>,
+    ) -> Result<ArchiveUploadResponse, ClientError> {
+        let call = Call::new(
+            Method::POST,
+            format!("/anttp-0/multipart/public_archive/{}", segments(path)),
+        )
+        .multipart(files);
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/zip/public_archive - Create archive from a zip file
+    pub async fn create_archive_from_zip(
+        &self,
+        zip: impl Into<Bytes>,
+    ) -> Result<ZipUploadResponse, ClientError> {
+        let call = Call::new(Method::POST, ZIP_UPLOAD).bytes(zip.into(), "application/zip");
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/zip/public_archive, sending the zip as it is read
+    pub async fn create_archive_from_zip_stream<S, E>(
+        &self,
+        zip: S,
+    ) -> Result<ZipUploadResponse, ClientError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let call = Call::new(Method::POST, ZIP_UPLOAD);
+        json(self.send_stream(call, "application/zip", zip).await?).await
+    }
+
+    /// PUT /anttp-0/multipart/public_archive/{address} - Add or replace files
+    pub async fn update_archive_files(
+        &self,
+        address: &str,
+        query: &ArchiveUpdateQuery,
+        files: Vec<UploadFile>,
+    ) -> Result<ArchiveUpdateResponse, ClientError> {
+        let call = Call::new(
+            Method::PUT,
+            format!("/anttp-0/multipart/public_archive/{}", segment(address)),
+        )
+        .query(query)?
+        .multipart(files);
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/public_archive/{address} - List files
+    pub async fn get_archive_root(&self, address: &str) -> Result<serde_json::Value, ClientError> {
+        json(self.send(archive_root(address)).await?).await
+    }
+
+    /// GET /anttp-0/public_archive/{address}?format= - Whole archive as zip/tar/tar.gz
+    pub async fn download_archive(
+        &self,
+        address: &str,
+        format: BundleFormat,
+        prefix: Option<&str>,
+    ) -> Result<Bytes, ClientError> {
+        let call = archive_root(address).query(&download_query(format, prefix))?;
+        bytes(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/public_archive/{address}?format=, as it arrives
+    pub async fn download_archive_stream(
+        &self,
+        address: &str,
+        format: BundleFormat,
+        prefix: Option<&str>,
+    ) -> Result<ByteStream, ClientError> {
+        let call = archive_root(address).query(&download_query(format, prefix))?;
+        Ok(stream(self.send(call).await?))
+    }
+
+    /// PATCH /anttp-0/public_archive/{address} - Delete or rename paths
+    pub async fn patch_archive(
+        &self,
+        address: &str,
+        query: &ArchiveUpdateQuery,
+        request: &ArchivePatchRequest,
+    ) -> Result<ArchiveUpdateResponse, ClientError> {
+        let call = Call::new(
+            Method::PATCH,
+            format!("/anttp-0/public_archive/{}", segment(address)),
+        )
+        .query(query)?
+        .json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/public_archive_diff/{from}/{to} - Compare two archives
+    pub async fn get_archive_diff(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<ArchiveDiffResponse, ClientError> {
+        let call = Call::get(format!(
+            "/anttp-0/public_archive_diff/{}/{}",
+            segment(from),
+            segment(to)
+        ));
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/public_archive_diff/{address} - Compare with local file hashes
+    pub async fn diff_archive_with_manifest(
+        &self,
+        address: &str,
+        request: &ArchiveManifestRequest,
+    ) -> Result<ArchiveDiffResponse, ClientError> {
+        let call = Call::new(
+            Method::POST,
+            format!("/anttp-0/public_archive_diff/{}", segment(address)),
+        )
+        .json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/public_archive_sync_plan/{address} - What to change to match local files
+    pub async fn get_archive_sync_plan(
+        &self,
+        address: &str,
+        request: &ArchiveManifestRequest,
+    ) -> Result<ArchiveSyncPlan, ClientError> {
+        let call = Call::new(
+            Method::POST,
+            format!("/anttp-0/public_archive_sync_plan/{}", segment(address)),
+        )
+        .json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/public_archive/{address}/{path} - One file
+    pub async fn get_archive_file(&self, address: &str, path: &str) -> Result<Bytes, ClientError> {
+        bytes(self.send(archive_file(address, path)).await?).await
+    }
+
+    /// GET /anttp-0/public_archive/{address}/{path}, as it arrives
+    pub async fn get_archive_file_stream(
+        &self,
+        address: &str,
+        path: &str,
+    ) -> Result<ByteStream, ClientError> {
+        Ok(stream(self.send(archive_file(address, path)).await?))
+    }
+}
+
+const ZIP_UPLOAD: &str = "/anttp-0/zip/public_archive";
+
+fn archive_root(address: &str) -> Call {
+    Call::get(format!("/anttp-0/public_archive/{}", segment(address)))
+}
+
+fn archive_file(address: &str, path: &str) -> Call {
+    Call::get(format!(
+        "/anttp-0/public_archive/{}/{}",
+        segment(address),
+        segments(path)
+    ))
+}
+
+fn download_query(format: BundleFormat, prefix: Option<&str>) -> ArchiveDownloadQuery {
+    ArchiveDownloadQuery {
+        format: Some(format),
+        prefix: prefix.map(str::to_string),
+    }
+}
@@ -0,0 +1,47 @@
+// client/src/api/chunks.rs
+//! Chunk endpoints - Immutable data
+
+use anttp_rust_backend::models::{ChunkData, ChunkRequest, ChunkResponse};
+use bytes::Bytes;
+use reqwest::Method;
+
+use crate::client::{bytes, json, segment, stream, AntTpClient, ByteStream, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/chunk - Create chunk (Base64 JSON)
+    pub async fn create_chunk(&self, request: &ChunkRequest) -> Result<ChunkResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/chunk").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/chunk/{address} - Get chunk (Base64 JSON)
+    pub async fn get_chunk(&self, address: &str) -> Result<ChunkData, ClientError> {
+        let call = Call::get(format!("/anttp-0/chunk/{}", segment(address)));
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/binary/chunk - Create chunk (raw bytes)
+    pub async fn create_chunk_binary(
+        &self,
+        content: impl Into<Bytes>,
+    ) -> Result<ChunkResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/binary/chunk")
+            .bytes(content.into(), "application/octet-stream");
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/binary/chunk/{address} - Get chunk (raw bytes)
+    pub async fn get_chunk_binary(&self, address: &str) -> Result<Bytes, ClientError> {
+        bytes(self.send(binary_chunk(address)).await?).await
+    }
+
+    /// GET /anttp-0/binary/chunk/{address}, as it arrives
+    pub async fn get_chunk_stream(&self, address: &str) -> Result<ByteStream, ClientError> {
+        Ok(stream(self.send(binary_chunk(address)).await?))
+    }
+}
+
+fn binary_chunk(address: &str) -> Call {
+    Call::get(format!("/anttp-0/binary/chunk/{}", segment(address)))
+}
@@ -0,0 +1,48 @@
+// client/src/api/graph.rs
+//! Graph endpoints - Graph entries, import and export
+
+use anttp_rust_backend::models::{
+    GraphEntryData, GraphEntryRequest, GraphExportQuery, GraphImportResponse, JsonGraphDocument,
+};
+use bytes::Bytes;
+use reqwest::Method;
+
+use crate::client::{bytes, json, segment, AntTpClient, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/graph_entry - Create graph entry
+    pub async fn create_graph_entry(
+        &self,
+        request: &GraphEntryRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/graph_entry").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/graph_entry/{address} - Get graph entry
+    pub async fn get_graph_entry(&self, address: &str) -> Result<GraphEntryData, ClientError> {
+        let call = Call::get(format!("/anttp-0/graph_entry/{}", segment(address)));
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/graph_entry/import - Bulk import a JSON Graph Format document
+    pub async fn import_graph(
+        &self,
+        document: &JsonGraphDocument,
+    ) -> Result<GraphImportResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/graph_entry/import").json(document)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/graph_entry/{address}/export - DOT, JSON or GraphML (as `query.format` says)
+    pub async fn export_graph(
+        &self,
+        address: &str,
+        query: &GraphExportQuery,
+    ) -> Result<Bytes, ClientError> {
+        let call =
+            Call::get(format!("/anttp-0/graph_entry/{}/export", segment(address))).query(query)?;
+        bytes(self.send(call).await?).await
+    }
+}
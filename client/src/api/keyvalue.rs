@@ -0,0 +1,193 @@
+// client/src/api/keyvalue.rs
+//! Key/value endpoints - Buckets and objects
+
+use anttp_rust_backend::models::{
+    BucketRequest, KeyValueBucket, KeyValueData, KeyValueRequest, KeyValueResponse,
+    ListBucketsResponse, ListObjectsQuery, ListObjectsResponse, ObjectVersionsResponse,
+    VersionQuery,
+};
+use bytes::Bytes;
+use futures::Stream;
+use reqwest::{header, Method};
+
+use crate::client::{
+    bytes, header, json, segment, segments, stream, AntTpClient, ByteStream, Call,
+};
+use crate::error::ClientError;
+
+/// What HEAD says about an object
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ObjectHead {
+    pub content_type: Option<String>,
+    pub size: Option<u64>,
+    /// Without the quotes
+    pub etag: Option<String>,
+    /// HTTP date, e.g. "Tue, 15 Nov 1994 08:12:31 GMT"
+    pub last_modified: Option<String>,
+    /// Set for versioned buckets
+    pub version_id: Option<String>,
+}
+
+impl AntTpClient {
+    /// POST /anttp-0/key_value - Create object (Base64 JSON)
+    pub async fn create_key_value(
+        &self,
+        request: &KeyValueRequest,
+    ) -> Result<KeyValueResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/key_value").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/key_value - List buckets
+    pub async fn list_buckets(&self) -> Result<ListBucketsResponse, ClientError> {
+        json(self.send(Call::get("/anttp-0/key_value")).await?).await
+    }
+
+    /// GET /anttp-0/key_value/{bucket} - List objects (one page)
+    pub async fn list_key_values(
+        &self,
+        bucket: &str,
+        query: &ListObjectsQuery,
+    ) -> Result<ListObjectsResponse, ClientError> {
+        let call = Call::get(bucket_path(bucket)).query(query)?;
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/key_value/{bucket} - Create bucket (or change its versioning)
+    pub async fn create_bucket(
+        &self,
+        bucket: &str,
+        request: &BucketRequest,
+    ) -> Result<KeyValueBucket, ClientError> {
+        let call = Call::new(Method::PUT, bucket_path(bucket)).json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// DELETE /anttp-0/key_value/{bucket} - Delete an empty bucket
+    pub async fn delete_bucket(&self, bucket: &str) -> Result<serde_json::Value, ClientError> {
+        json(
+            self.send(Call::new(Method::DELETE, bucket_path(bucket)))
+                .await?,
+        )
+        .await
+    }
+
+    /// GET /anttp-0/key_value/{bucket}/{object} - Get object (Base64 JSON)
+    pub async fn get_key_value(
+        &self,
+        bucket: &str,
+        object: &str,
+        query: &VersionQuery,
+    ) -> Result<KeyValueData, ClientError> {
+        let call = Call::get(object_path("key_value", bucket, object)).query(query)?;
+        json(self.send(call).await?).await
+    }
+
+    /// HEAD /anttp-0/key_value/{bucket}/{object} - Object metadata
+    pub async fn head_key_value(
+        &self,
+        bucket: &str,
+        object: &str,
+        query: &VersionQuery,
+    ) -> Result<ObjectHead, ClientError> {
+        let call =
+            Call::new(Method::HEAD, object_path("key_value", bucket, object)).query(query)?;
+        let response = self.send(call).await?;
+        Ok(ObjectHead {
+            content_type: header(&response, header::CONTENT_TYPE.as_str()),
+            size: header(&response, header::CONTENT_LENGTH.as_str())
+                .and_then(|size| size.parse().ok()),
+            etag: header(&response, header::ETAG.as_str())
+                .map(|etag| etag.trim_matches('"').to_string()),
+            last_modified: header(&response, header::LAST_MODIFIED.as_str()),
+            version_id: header(&response, "x-version-id"),
+        })
+    }
+
+    /// DELETE /anttp-0/key_value/{bucket}/{object} - Delete object (or one version)
+    pub async fn delete_key_value(
+        &self,
+        bucket: &str,
+        object: &str,
+        query: &VersionQuery,
+    ) -> Result<serde_json::Value, ClientError> {
+        let call =
+            Call::new(Method::DELETE, object_path("key_value", bucket, object)).query(query)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/key_value_versions/{bucket}/{object} - Object versions, newest first
+    pub async fn list_key_value_versions(
+        &self,
+        bucket: &str,
+        object: &str,
+    ) -> Result<ObjectVersionsResponse, ClientError> {
+        let call = Call::get(object_path("key_value_versions", bucket, object));
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/binary/key_value/{bucket}/{object} - Create object (raw bytes)
+    pub async fn create_key_value_binary(
+        &self,
+        bucket: &str,
+        object: &str,
+        content: impl Into<Bytes>,
+        content_type: &str,
+    ) -> Result<KeyValueResponse, ClientError> {
+        let call = Call::new(Method::PUT, object_path("binary/key_value", bucket, object))
+            .bytes(content.into(), content_type);
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/binary/key_value/{bucket}/{object}, sending the content as it is read
+    pub async fn create_key_value_stream<S, E>(
+        &self,
+        bucket: &str,
+        object: &str,
+        content: S,
+        content_type: &str,
+    ) -> Result<KeyValueResponse, ClientError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let call = Call::new(Method::PUT, object_path("binary/key_value", bucket, object));
+        json(self.send_stream(call, content_type, content).await?).await
+    }
+
+    /// GET /anttp-0/binary/key_value/{bucket}/{object} - Get object (raw bytes)
+    pub async fn get_key_value_binary(
+        &self,
+        bucket: &str,
+        object: &str,
+        query: &VersionQuery,
+    ) -> Result<Bytes, ClientError> {
+        let call = Call::get(object_path("binary/key_value", bucket, object)).query(query)?;
+        bytes(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/binary/key_value/{bucket}/{object}, as it arrives
+    pub async fn get_key_value_stream(
+        &self,
+        bucket: &str,
+        object: &str,
+        query: &VersionQuery,
+    ) -> Result<ByteStream, ClientError> {
+        let call = Call::get(object_path("binary/key_value", bucket, object)).query(query)?;
+        Ok(stream(self.send(call).await?))
+    }
+}
+
+fn bucket_path(bucket: &str) -> String {
+    format!("/anttp-0/key_value/{}", segment(bucket))
+}
+
+/// Helper: `/anttp-0/{route}/{bucket}/{object}` (the object key keeps its "/")
+fn object_path(route: &str, bucket: &str, object: &str) -> String {
+    format!(
+        "/anttp-0/{}/{}/{}",
+        route,
+        segment(bucket),
+        segments(object)
+    )
+}
@@ -0,0 +1,23 @@
+// client/src/api/mod.rs
+//! Endpoint methods - One file per feature, like the server's handlers
+//!
+//! Every route in the server's route table has a method here, named after
+//! its handler (`POST /anttp-0/chunk` → `create_chunk`). Endpoints with raw
+//! bytes also have a `_stream` variant that hands the body over as it
+//! arrives instead of holding all of it in memory.
+
+mod admin;
+mod archives;
+mod chunks;
+mod graph;
+mod keyvalue;
+mod pnr;
+mod pointers;
+mod publicdata;
+mod registers;
+mod scratchpads;
+mod system;
+mod tarchive;
+mod webdav;
+
+pub use keyvalue::ObjectHead;
@@ -0,0 +1,54 @@
+// client/src/api/pnr.rs
+//! PNR endpoints - Pointer Name Registry
+//!
+//! For Students:
+//! Owned names need the owner key: build the client with `.owner_key(..)`
+//! or call `with_owner_key(..)` first.
+
+use anttp_rust_backend::models::PnrRequest;
+use reqwest::Method;
+
+use crate::client::{json, segment, AntTpClient, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/pnr - Create PNR
+    pub async fn create_pnr(&self, request: &PnrRequest) -> Result<serde_json::Value, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/pnr").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/pnr/{name} - Replace PNR records
+    pub async fn update_pnr(
+        &self,
+        name: &str,
+        request: &PnrRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let call = Call::new(Method::PUT, pnr_path(name)).json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/pnr/{name} - PNR records
+    pub async fn get_pnr(&self, name: &str) -> Result<serde_json::Value, ClientError> {
+        json(self.send(Call::get(pnr_path(name))).await?).await
+    }
+
+    /// PATCH /anttp-0/pnr/{name} - Append records
+    pub async fn append_pnr(
+        &self,
+        name: &str,
+        request: &PnrRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let call = Call::new(Method::PATCH, pnr_path(name)).json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// DELETE /anttp-0/pnr/{name} - Delete a name (or one record via sub.name)
+    pub async fn delete_pnr(&self, name: &str) -> Result<serde_json::Value, ClientError> {
+        json(self.send(Call::new(Method::DELETE, pnr_path(name))).await?).await
+    }
+}
+
+fn pnr_path(name: &str) -> String {
+    format!("/anttp-0/pnr/{}", segment(name))
+}
@@ -0,0 +1,39 @@
+// client/src/api/pointers.rs
+//! Pointer endpoints - Mutable address references
+
+use anttp_rust_backend::models::{PointerData, PointerRequest, PointerResponse};
+use reqwest::Method;
+
+use crate::client::{json, segment, AntTpClient, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/pointer - Create pointer
+    pub async fn create_pointer(
+        &self,
+        request: &PointerRequest,
+    ) -> Result<PointerResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/pointer").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/pointer/{address} - Pointer target
+    pub async fn get_pointer(&self, address: &str) -> Result<PointerData, ClientError> {
+        let call = Call::get(format!("/anttp-0/pointer/{}", segment(address)));
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/pointer/{address} - Update pointer target
+    pub async fn update_pointer(
+        &self,
+        address: &str,
+        request: &PointerRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let call = Call::new(
+            Method::PUT,
+            format!("/anttp-0/pointer/{}", segment(address)),
+        )
+        .json(request)?;
+        json(self.send(call).await?).await
+    }
+}
@@ -0,0 +1,34 @@
+// client/src/api/publicdata.rs
+//! Public data endpoints - Simple binary storage
+
+use bytes::Bytes;
+use reqwest::Method;
+
+use crate::client::{bytes, json, segment, stream, AntTpClient, ByteStream, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/binary/public_data - Store public data
+    pub async fn create_public_data(
+        &self,
+        content: impl Into<Bytes>,
+    ) -> Result<serde_json::Value, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/binary/public_data")
+            .bytes(content.into(), "application/octet-stream");
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/binary/public_data/{address} - Get public data
+    pub async fn get_public_data(&self, address: &str) -> Result<Bytes, ClientError> {
+        bytes(self.send(public_data(address)).await?).await
+    }
+
+    /// GET /anttp-0/binary/public_data/{address}, as it arrives
+    pub async fn get_public_data_stream(&self, address: &str) -> Result<ByteStream, ClientError> {
+        Ok(stream(self.send(public_data(address)).await?))
+    }
+}
+
+fn public_data(address: &str) -> Call {
+    Call::get(format!("/anttp-0/binary/public_data/{}", segment(address)))
+}
@@ -0,0 +1,50 @@
+// client/src/api/registers.rs
+//! Register endpoints - Mutable values with history
+
+use anttp_rust_backend::models::{
+    RegisterData, RegisterHistoryEntry, RegisterRequest, RegisterResponse,
+};
+use reqwest::Method;
+
+use crate::client::{json, segment, AntTpClient, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/register - Create register
+    pub async fn create_register(
+        &self,
+        request: &RegisterRequest,
+    ) -> Result<RegisterResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/register").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/register/{address} - Current value
+    pub async fn get_register(&self, address: &str) -> Result<RegisterData, ClientError> {
+        let call = Call::get(format!("/anttp-0/register/{}", segment(address)));
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/register/{address} - Update register
+    pub async fn update_register(
+        &self,
+        address: &str,
+        request: &RegisterRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let call = Call::new(
+            Method::PUT,
+            format!("/anttp-0/register/{}", segment(address)),
+        )
+        .json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// GET /anttp-0/register_history/{address} - All values, oldest first
+    pub async fn get_register_history(
+        &self,
+        address: &str,
+    ) -> Result<Vec<RegisterHistoryEntry>, ClientError> {
+        let call = Call::get(format!("/anttp-0/register_history/{}", segment(address)));
+        json(self.send(call).await?).await
+    }
+}
@@ -0,0 +1,91 @@
+// client/src/api/scratchpads.rs
+//! Scratchpad endpoints - Public and private mutable data
+
+use anttp_rust_backend::models::{
+    ScratchpadData, ScratchpadRequest, ScratchpadResponse, ScratchpadUpdateRequest,
+};
+use reqwest::Method;
+
+use crate::client::{json, segment, AntTpClient, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/public_scratchpad - Create public scratchpad
+    pub async fn create_public_scratchpad(
+        &self,
+        request: &ScratchpadRequest,
+    ) -> Result<ScratchpadResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/public_scratchpad").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/public_scratchpad/{address}/{name} - Update public scratchpad
+    pub async fn update_public_scratchpad(
+        &self,
+        address: &str,
+        name: &str,
+        request: &ScratchpadUpdateRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let path = format!(
+            "/anttp-0/public_scratchpad/{}/{}",
+            segment(address),
+            segment(name)
+        );
+        json(
+            self.send(Call::new(Method::PUT, path).json(request)?)
+                .await?,
+        )
+        .await
+    }
+
+    /// GET /anttp-0/public_scratchpad/{address} - Get public scratchpad
+    pub async fn get_public_scratchpad(
+        &self,
+        address: &str,
+    ) -> Result<ScratchpadData, ClientError> {
+        let call = Call::get(format!("/anttp-0/public_scratchpad/{}", segment(address)));
+        json(self.send(call).await?).await
+    }
+
+    /// POST /anttp-0/private_scratchpad - Create private scratchpad
+    pub async fn create_private_scratchpad(
+        &self,
+        request: &ScratchpadRequest,
+    ) -> Result<ScratchpadResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/private_scratchpad").json(request)?;
+        json(self.send(call).await?).await
+    }
+
+    /// PUT /anttp-0/private_scratchpad/{address}/{name} - Update private scratchpad
+    pub async fn update_private_scratchpad(
+        &self,
+        address: &str,
+        name: &str,
+        request: &ScratchpadUpdateRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let path = format!(
+            "/anttp-0/private_scratchpad/{}/{}",
+            segment(address),
+            segment(name)
+        );
+        json(
+            self.send(Call::new(Method::PUT, path).json(request)?)
+                .await?,
+        )
+        .await
+    }
+
+    /// GET /anttp-0/private_scratchpad/{address}/{name} - Get private scratchpad
+    pub async fn get_private_scratchpad(
+        &self,
+        address: &str,
+        name: &str,
+    ) -> Result<ScratchpadData, ClientError> {
+        let path = format!(
+            "/anttp-0/private_scratchpad/{}/{}",
+            segment(address),
+            segment(name)
+        );
+        json(self.send(Call::get(path)).await?).await
+    }
+}
@@ -0,0 +1,22 @@
+// client/src/api/system.rs
+//! System endpoints - Health, command list, OpenAPI document
+
+use crate::client::{json, AntTpClient, Call};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// GET /health
+    pub async fn health(&self) -> Result<serde_json::Value, ClientError> {
+        json(self.send(Call::get("/health")).await?).await
+    }
+
+    /// GET /anttp-0/command - Every endpoint, grouped by feature
+    pub async fn get_commands(&self) -> Result<serde_json::Value, ClientError> {
+        json(self.send(Call::get("/anttp-0/command")).await?).await
+    }
+
+    /// GET /openapi.json - OpenAPI 3 description of the server's API
+    pub async fn get_openapi(&self) -> Result<serde_json::Value, ClientError> {
+        json(self.send(Call::get("/openapi.json")).await?).await
+    }
+}
@@ -0,0 +1,19 @@
+// client/src/api/tarchive.rs
+//! Tarchive endpoint - Tar-based archives
+
+use anttp_rust_backend::models::ArchiveResponse;
+use reqwest::Method;
+
+use crate::client::{json, AntTpClient, Call, UploadFile};
+use crate::error::ClientError;
+
+impl AntTpClient {
+    /// POST /anttp-0/multipart/tarchive - Create tarchive
+    pub async fn create_tarchive(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<ArchiveResponse, ClientError> {
+        let call = Call::new(Method::POST, "/anttp-0/multipart/tarchive").multipart(files);
+        json(self.send(call).await?).await
+    }
+}
@@ -0,0 +1,94 @@
+// client/src/api/webdav.rs
+//! WebDAV endpoints - The /webdav drive (archive/ and kv/)
+//!
+//! For Students:
+//! Paths are relative to /webdav, e.g. "kv/photos/2024/cat.jpg" or
+//! "archive/{address}/index.html". Writing into an archive makes a new
+//! one; those calls return its address (the `X-Archive-Address` header).
+
+use bytes::Bytes;
+use reqwest::Method;
+
+use crate::client::{bytes, header, segments, AntTpClient, Call};
+use crate::error::ClientError;
+
+const ARCHIVE_ADDRESS_HEADER: &str = "X-Archive-Address";
+
+impl AntTpClient {
+    /// PROPFIND /webdav/{path} - List a folder (`depth` 0 = just the folder itself)
+    pub async fn webdav_propfind(&self, path: &str, depth: u8) -> Result<String, ClientError> {
+        let call =
+            Call::new(dav_method("PROPFIND"), dav_path(path)).header("Depth", depth.to_string());
+        let body = bytes(self.send(call).await?).await?;
+        String::from_utf8(body.to_vec()).map_err(|e| ClientError::Decode(e.to_string()))
+    }
+
+    /// GET /webdav/{path} - Download a file
+    pub async fn webdav_get(&self, path: &str) -> Result<Bytes, ClientError> {
+        bytes(self.send(Call::get(dav_path(path))).await?).await
+    }
+
+    /// PUT /webdav/{path} - Upload a file (returns the new archive, if any)
+    pub async fn webdav_put(
+        &self,
+        path: &str,
+        content: impl Into<Bytes>,
+        content_type: &str,
+    ) -> Result<Option<String>, ClientError> {
+        let call = Call::new(Method::PUT, dav_path(path)).bytes(content.into(), content_type);
+        self.dav_change(call).await
+    }
+
+    /// MKCOL /webdav/{path} - Create a folder (or, at kv/{bucket}, a bucket)
+    pub async fn webdav_mkcol(&self, path: &str) -> Result<(), ClientError> {
+        self.send(Call::new(dav_method("MKCOL"), dav_path(path)))
+            .await?;
+        Ok(())
+    }
+
+    /// DELETE /webdav/{path} - Delete a file or folder (returns the new archive, if any)
+    pub async fn webdav_delete(&self, path: &str) -> Result<Option<String>, ClientError> {
+        self.dav_change(Call::new(Method::DELETE, dav_path(path)))
+            .await
+    }
+
+    /// MOVE /webdav/{from} to /webdav/{to} (returns the new archive, if any)
+    pub async fn webdav_move(
+        &self,
+        from: &str,
+        to: &str,
+        overwrite: bool,
+    ) -> Result<Option<String>, ClientError> {
+        let call = Call::new(dav_method("MOVE"), dav_path(from))
+            .header("Destination", dav_path(to))
+            .header("Overwrite", if overwrite { "T" } else { "F" });
+        self.dav_change(call).await
+    }
+
+    /// OPTIONS /webdav/{path} - The methods the server allows
+    pub async fn webdav_options(&self, path: &str) -> Result<Vec<String>, ClientError> {
+        let response = self
+            .send(Call::new(Method::OPTIONS, dav_path(path)))
+            .await?;
+        Ok(header(&response, "Allow")
+            .unwrap_or_default()
+            .split(',')
+            .map(|method| method.trim().to_string())
+            .filter(|method| !method.is_empty())
+            .collect())
+    }
+
+    /// Helper: Send a change, return the new archive address (archive paths only)
+    async fn dav_change(&self, call: Call) -> Result<Option<String>, ClientError> {
+        let response = self.send(call).await?;
+        Ok(header(&response, ARCHIVE_ADDRESS_HEADER))
+    }
+}
+
+fn dav_path(path: &str) -> String {
+    format!("/webdav/{}", segments(path.trim_start_matches('/')))
+}
+
+fn dav_method(name: &str) -> Method {
+    Method::from_bytes(name.as_bytes()).expect("WebDAV method names are valid tokens")
+}
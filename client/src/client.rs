@@ -0,0 +1,474 @@
+// client/src/client.rs
+//! The client - Connection settings and the request plumbing
+//!
+//! For 1st Year CS Students:
+//! `AntTpClient` remembers where the server is and which common headers
+//! to send (x-store-type, x-consistency, x-owner-key, the API token). Every endpoint
+//! method (in `api/`) describes its request as a `Call`; `send` adds the
+//! headers, sends it, retries when the `RetryPolicy` says so, and turns
+//! error answers into `ClientError::Api`.
+//!
+//! The client is cheap to clone (clones share one connection pool), so
+//! `with_store_type` etc. make a variant for a few calls:
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), anttp_client::ClientError> {
+//! use anttp_client::{models::StoreType, AntTpClient};
+//!
+//! let client = AntTpClient::new("http://127.0.0.1:18888")?;
+//! let data = client.with_store_type(StoreType::Disk).get_chunk_binary("abc...").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anttp_rust_backend::handlers::{
+    CONSISTENCY_HEADER, OWNER_KEY_HEADER, REQUEST_ID_HEADER, STORE_TYPE_HEADER,
+};
+use anttp_rust_backend::models::{Consistency, StoreType};
+use bytes::Bytes;
+use futures::{Stream, StreamExt, TryStreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::error::{ApiError, ClientError};
+use crate::retry::RetryPolicy;
+
+/// A response body arriving piece by piece
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, ClientError>> + Send>>;
+
+/// Everything in a path segment except unreserved characters is escaped
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Client for the AntTP-compatible backend
+#[derive(Debug, Clone)]
+pub struct AntTpClient {
+    http: reqwest::Client,
+    /// e.g. "http://127.0.0.1:18888" (no trailing slash)
+    base_url: String,
+    /// x-store-type (`None` = the server's default)
+    store_type: Option<StoreType>,
+    /// x-consistency (`None` = cached)
+    consistency: Option<Consistency>,
+    /// x-owner-key, for owned PNR names
+    owner_key: Option<String>,
+    /// Sent as `Authorization: Bearer` (servers with `[auth]` enabled)
+    token: Option<String>,
+    retry: RetryPolicy,
+}
+
+/// Settings for a new client
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    store_type: Option<StoreType>,
+    consistency: Option<Consistency>,
+    owner_key: Option<String>,
+    token: Option<String>,
+    retry: RetryPolicy,
+    timeout: Option<Duration>,
+    http: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    /// Store every call uses (unless changed with `with_store_type`)
+    pub fn store_type(mut self, store_type: StoreType) -> Self {
+        self.store_type = Some(store_type);
+        self
+    }
+
+    pub fn consistency(mut self, consistency: Consistency) -> Self {
+        self.consistency = Some(consistency);
+        self
+    }
+
+    pub fn owner_key(mut self, owner_key: impl Into<String>) -> Self {
+        self.owner_key = Some(owner_key.into());
+        self
+    }
+
+    /// API token for servers that require one
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Give up on a single attempt after this long
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Use your own reqwest client (proxies, TLS settings, ...)
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn build(self) -> Result<AntTpClient, ClientError> {
+        let url = Url::parse(&self.base_url)
+            .map_err(|e| ClientError::InvalidUrl(format!("{}: {}", self.base_url, e)))?;
+        if !matches!(url.scheme(), "http" | "https") || url.query().is_some() {
+            return Err(ClientError::InvalidUrl(format!(
+                "{}: use http(s)://host[:port][/prefix]",
+                self.base_url
+            )));
+        }
+
+        let http = match self.http {
+            Some(http) => http,
+            None => {
+                let mut builder = reqwest::Client::builder();
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(AntTpClient {
+            http,
+            base_url: url.as_str().trim_end_matches('/').to_string(),
+            store_type: self.store_type,
+            consistency: self.consistency,
+            owner_key: self.owner_key,
+            token: self.token,
+            retry: self.retry,
+        })
+    }
+}
+
+impl AntTpClient {
+    /// Client with the default settings (server's store type, default retries)
+    pub fn new(base_url: impl Into<String>) -> Result<Self, ClientError> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.into(),
+            store_type: None,
+            consistency: None,
+            owner_key: None,
+            token: None,
+            retry: RetryPolicy::default(),
+            timeout: None,
+            http: None,
+        }
+    }
+
+    /// The same client, using another store
+    pub fn with_store_type(&self, store_type: StoreType) -> Self {
+        Self {
+            store_type: Some(store_type),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_consistency(&self, consistency: Consistency) -> Self {
+        Self {
+            consistency: Some(consistency),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_owner_key(&self, owner_key: impl Into<String>) -> Self {
+        Self {
+            owner_key: Some(owner_key.into()),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_token(&self, token: impl Into<String>) -> Self {
+        Self {
+            token: Some(token.into()),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_retry_policy(&self, retry: RetryPolicy) -> Self {
+        Self {
+            retry,
+            ..self.clone()
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The store sent as x-store-type (`None` = the server's default)
+    pub fn store_type(&self) -> Option<StoreType> {
+        self.store_type
+    }
+
+    // ========================================================================
+    // SENDING
+    // ========================================================================
+
+    /// Send a call (retrying as the policy allows) and check the answer
+    pub(crate) async fn send(&self, call: Call) -> Result<Response, ClientError> {
+        let mut retry = 0;
+        loop {
+            let error = match self.send_once(&call, None).await {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+            if !self.retry.should_retry(retry, &call.method, &error) {
+                return Err(error);
+            }
+            tokio::time::sleep(self.retry.backoff(retry)).await;
+            retry += 1;
+        }
+    }
+
+    /// Send a call whose body is a stream (sent once - a stream can't be replayed)
+    pub(crate) async fn send_stream<S, E>(
+        &self,
+        call: Call,
+        content_type: &str,
+        body: S,
+    ) -> Result<Response, ClientError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let body = (content_type.to_string(), reqwest::Body::wrap_stream(body));
+        self.send_once(&call, Some(body)).await
+    }
+
+    async fn send_once(
+        &self,
+        call: &Call,
+        stream: Option<(String, reqwest::Body)>,
+    ) -> Result<Response, ClientError> {
+        let mut url = format!("{}{}", self.base_url, call.path);
+        if !call.query.is_empty() {
+            url.push('?');
+            url.push_str(&call.query);
+        }
+        let mut request = self.headers(self.http.request(call.method.clone(), url));
+        for (name, value) in &call.headers {
+            request = request.header(*name, value);
+        }
+
+        request = match (&call.body, stream) {
+            (_, Some((content_type, body))) => request
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .body(body),
+            (Payload::Empty, None) => request,
+            (Payload::Json(json), None) => request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(json.clone()),
+            (Payload::Bytes { data, content_type }, None) => request
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .body(data.clone()),
+            (Payload::Multipart(files), None) => request.multipart(multipart_form(files)?),
+        };
+
+        check(request.send().await?).await
+    }
+
+    /// Helper: The common headers this client sends
+    fn headers(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(store_type) = self.store_type {
+            request = request.header(STORE_TYPE_HEADER, store_type.as_str());
+        }
+        if let Some(consistency) = self.consistency {
+            request = request.header(CONSISTENCY_HEADER, consistency.as_str());
+        }
+        if let Some(owner_key) = &self.owner_key {
+            request = request.header(OWNER_KEY_HEADER, owner_key);
+        }
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        request
+    }
+}
+
+// ============================================================================
+// CALLS
+// ============================================================================
+
+/// One request, described so it can be sent again
+pub(crate) struct Call {
+    method: Method,
+    /// Already percent-encoded, starting with "/"
+    path: String,
+    /// Already encoded (empty = no query string)
+    query: String,
+    headers: Vec<(&'static str, String)>,
+    body: Payload,
+}
+
+/// A request body that can be sent more than once
+enum Payload {
+    Empty,
+    Json(Vec<u8>),
+    Bytes { data: Bytes, content_type: String },
+    Multipart(Vec<UploadFile>),
+}
+
+impl Call {
+    pub(crate) fn new(method: Method, path: impl Into<String>) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            query: String::new(),
+            headers: Vec::new(),
+            body: Payload::Empty,
+        }
+    }
+
+    pub(crate) fn get(path: impl Into<String>) -> Self {
+        Self::new(Method::GET, path)
+    }
+
+    pub(crate) fn query<T: Serialize>(mut self, query: &T) -> Result<Self, ClientError> {
+        self.query = serde_urlencoded::to_string(query)
+            .map_err(|e| ClientError::Decode(format!("Can't encode query string: {}", e)))?;
+        Ok(self)
+    }
+
+    pub(crate) fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub(crate) fn json<T: Serialize>(mut self, body: &T) -> Result<Self, ClientError> {
+        let json = serde_json::to_vec(body)
+            .map_err(|e| ClientError::Decode(format!("Can't encode request body: {}", e)))?;
+        self.body = Payload::Json(json);
+        Ok(self)
+    }
+
+    pub(crate) fn bytes(mut self, data: Bytes, content_type: impl Into<String>) -> Self {
+        self.body = Payload::Bytes {
+            data,
+            content_type: content_type.into(),
+        };
+        self
+    }
+
+    pub(crate) fn multipart(mut self, files: Vec<UploadFile>) -> Self {
+        self.body = Payload::Multipart(files);
+        self
+    }
+}
+
+/// A file for a multipart archive upload
+#[derive(Debug, Clone)]
+pub struct UploadFile {
+    /// Path inside the archive (e.g. "css/site.css")
+    pub path: String,
+    pub content: Bytes,
+    /// Sent with the part (the server detects types itself)
+    pub content_type: Option<String>,
+}
+
+impl UploadFile {
+    pub fn new(path: impl Into<String>, content: impl Into<Bytes>) -> Self {
+        Self {
+            path: path.into(),
+            content: content.into(),
+            content_type: None,
+        }
+    }
+
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+}
+
+/// Helper: The multipart form for some files (the path is both field and file name)
+fn multipart_form(files: &[UploadFile]) -> Result<Form, ClientError> {
+    // File names are sent as they are: "css/site.css" must keep its "/"
+    let mut form = Form::new().percent_encode_noop();
+    for file in files {
+        let mut part = Part::bytes(file.content.to_vec()).file_name(file.path.clone());
+        if let Some(content_type) = &file.content_type {
+            part = part.mime_str(content_type).map_err(|_| {
+                ClientError::Decode(format!(
+                    "Invalid content type '{}' for {}",
+                    content_type, file.path
+                ))
+            })?;
+        }
+        form = form.part(file.path.clone(), part);
+    }
+    Ok(form)
+}
+
+// ============================================================================
+// ANSWERS
+// ============================================================================
+
+/// Helper: Turn an error answer into `ClientError::Api`
+async fn check(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let request_id = request_id(&response);
+    let body = response.bytes().await.unwrap_or_default();
+    Err(ApiError::from_parts(status, request_id, &body).into())
+}
+
+/// The x-request-id the server answered with
+pub(crate) fn request_id(response: &Response) -> Option<String> {
+    header(response, REQUEST_ID_HEADER)
+}
+
+/// A response header as text
+pub(crate) fn header(response: &Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Read a JSON answer
+pub(crate) async fn json<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    let body = response.bytes().await?;
+    serde_json::from_slice(&body).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Read a whole binary answer
+pub(crate) async fn bytes(response: Response) -> Result<Bytes, ClientError> {
+    Ok(response.bytes().await?)
+}
+
+/// Hand out a binary answer as it arrives
+pub(crate) fn stream(response: Response) -> ByteStream {
+    response.bytes_stream().map_err(ClientError::from).boxed()
+}
+
+// ============================================================================
+// PATHS
+// ============================================================================
+
+/// One path segment (an address, name or bucket), escaped
+pub(crate) fn segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+/// A path that may contain "/" (an object key or a file in an archive)
+pub(crate) fn segments(value: &str) -> String {
+    value.split('/').map(segment).collect::<Vec<_>>().join("/")
+}
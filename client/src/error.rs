@@ -0,0 +1,158 @@
+// client/src/error.rs
+//! Client errors - The server's ErrorResponse, as a Rust type
+//!
+//! For 1st Year CS Students:
+//! Every error the server sends has the same JSON shape: a readable
+//! `error`, optional `details` and a stable `code`. `ApiError` is that
+//! shape plus the HTTP status, so your code can `match` on
+//! `ErrorCode::NotFound` instead of comparing strings. Problems that
+//! never reached the server (no connection, timeout) are `Http`.
+
+use anttp_rust_backend::models::{ErrorCode, ErrorResponse};
+use reqwest::StatusCode;
+use std::fmt;
+
+/// Anything that can go wrong in a client call
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The server answered with an error
+    #[error(transparent)]
+    Api(#[from] ApiError),
+
+    /// The request didn't get an answer (connection, timeout, ...)
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The answer wasn't what this endpoint sends
+    #[error("Unexpected response: {0}")]
+    Decode(String),
+
+    /// The base URL can't be used
+    #[error("Invalid base URL: {0}")]
+    InvalidUrl(String),
+}
+
+impl ClientError {
+    /// The server's error code (`None` if the server never answered)
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::Api(error) => Some(error.code),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status the server answered with
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Api(error) => Some(error.status),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.code() == Some(ErrorCode::NotFound)
+    }
+
+    /// Could the same request work if sent again a little later?
+    ///
+    /// For Students:
+    /// A dropped connection or a busy gateway (502/503/504, 429) often is
+    /// gone a moment later. `backend_unavailable` is also a 503, but means
+    /// "not possible on this backend" - retrying won't change that.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Api(error) => {
+                error.code != ErrorCode::BackendUnavailable
+                    && matches!(
+                        error.status,
+                        StatusCode::TOO_MANY_REQUESTS
+                            | StatusCode::BAD_GATEWAY
+                            | StatusCode::SERVICE_UNAVAILABLE
+                            | StatusCode::GATEWAY_TIMEOUT
+                    )
+            }
+            Self::Http(error) => error.is_connect() || error.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+/// An error answer from the server
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    /// Readable headline (may change between versions - match on `code`)
+    pub error: String,
+    /// What exactly went wrong, if the server said
+    pub details: Option<String>,
+    /// The x-request-id of the failed request, to find it in the server log
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    /// Build from an error answer (`body` should be an ErrorResponse)
+    ///
+    /// For Students:
+    /// Some errors have no JSON body: HEAD answers never do, and a proxy
+    /// in between may send its own page. Then the status picks the code.
+    pub(crate) fn from_parts(status: StatusCode, request_id: Option<String>, body: &[u8]) -> Self {
+        match serde_json::from_slice::<ErrorResponse>(body) {
+            Ok(response) => Self {
+                status,
+                code: response.code,
+                error: response.error,
+                details: response.details,
+                request_id,
+            },
+            Err(_) => {
+                let text = String::from_utf8_lossy(body).trim().to_string();
+                Self {
+                    status,
+                    code: code_for_status(status),
+                    error: status
+                        .canonical_reason()
+                        .unwrap_or("Request failed")
+                        .to_string(),
+                    details: Some(text).filter(|text| !text.is_empty()),
+                    request_id,
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The code as the server writes it, e.g. "not_found"
+        let code = serde_json::to_value(self.code)
+            .ok()
+            .and_then(|code| code.as_str().map(str::to_string))
+            .unwrap_or_default();
+        write!(f, "{} ({}, {})", self.error, self.status.as_u16(), code)?;
+        if let Some(details) = &self.details {
+            write!(f, ": {}", details)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Helper: The code the server would have used for this status
+fn code_for_status(status: StatusCode) -> ErrorCode {
+    match status {
+        StatusCode::BAD_REQUEST => ErrorCode::InvalidInput,
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::METHOD_NOT_ALLOWED => ErrorCode::MethodNotAllowed,
+        StatusCode::CONFLICT => ErrorCode::Conflict,
+        StatusCode::PRECONDITION_FAILED => ErrorCode::PreconditionFailed,
+        StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+        StatusCode::UNPROCESSABLE_ENTITY => ErrorCode::TypeMismatch,
+        // Not backend_unavailable: a bare 503 (from a proxy) is worth a retry
+        _ => ErrorCode::Internal,
+    }
+}
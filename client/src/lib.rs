@@ -0,0 +1,35 @@
+// client/src/lib.rs
+//! AntTP client - Call the AntTP-compatible backend from Rust
+//!
+//! For 1st Year CS Students:
+//! Instead of building URLs and JSON by hand, call a method: every
+//! endpoint of the server has one, taking and returning the same types
+//! the server uses (`models`, shared with the server crate). Errors come
+//! back as `ClientError`, with the server's error `code` to match on.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), anttp_client::ClientError> {
+//! use anttp_client::models::{ChunkRequest, StoreType};
+//! use anttp_client::{AntTpClient, RetryPolicy};
+//!
+//! let client = AntTpClient::builder("http://127.0.0.1:18888")
+//!     .store_type(StoreType::Memory)
+//!     .retry_policy(RetryPolicy::retries(5))
+//!     .build()?;
+//!
+//! let chunk = client.create_chunk_binary(&b"Hello World!"[..]).await?;
+//! let content = client.get_chunk_binary(&chunk.address).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod api;
+mod client;
+mod error;
+mod retry;
+
+pub use anttp_rust_backend::models;
+pub use api::ObjectHead;
+pub use client::{AntTpClient, ByteStream, ClientBuilder, UploadFile};
+pub use error::{ApiError, ClientError};
+pub use retry::RetryPolicy;
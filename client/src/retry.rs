@@ -0,0 +1,80 @@
+// client/src/retry.rs
+//! Retry policy - When and how often to send a request again
+//!
+//! For 1st Year CS Students:
+//! Networks hiccup. Instead of failing at the first dropped connection,
+//! the client waits a little and tries again, waiting twice as long each
+//! time ("exponential backoff") so a struggling server isn't hammered.
+//! Only requests that are safe to repeat are retried: sending the same
+//! GET, PUT or DELETE twice ends the same way as sending it once.
+
+use reqwest::Method;
+use std::time::Duration;
+
+use crate::error::ClientError;
+
+/// How the client retries failed requests
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first one (0 = never retry)
+    pub max_retries: u32,
+    /// Wait before the first retry (doubled for each one after)
+    pub initial_backoff: Duration,
+    /// Longest wait between two attempts
+    pub max_backoff: Duration,
+    /// Also retry POST and PATCH (only if repeating them is harmless to you)
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    /// 3 retries after 100ms, 200ms and 400ms
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Every request is sent exactly once
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Retry up to `max_retries` times (other settings as the default)
+    pub fn retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// How long to wait before retry number `retry` (starting at 0)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Should attempt number `retry` (starting at 0) be made after `error`?
+    pub fn should_retry(&self, retry: u32, method: &Method, error: &ClientError) -> bool {
+        retry < self.max_retries
+            && (self.retry_non_idempotent || is_idempotent(method))
+            && error.is_transient()
+    }
+}
+
+/// Helper: Does sending this method twice do the same as sending it once?
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
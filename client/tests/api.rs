@@ -0,0 +1,1178 @@
+// client/tests/api.rs
+//! Integration tests - The client against a real server in this process
+//!
+//! For 1st Year CS Students:
+//! Each test starts the API (the same `api_server` the binary runs) on a
+//! free port with a fresh in-memory store, then talks to it over HTTP
+//! through the client - nothing is mocked between the two.
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use anttp_client::models::{
+    ArchiveManifestRequest, ArchivePatchRequest, ArchivePathChange, ArchiveUpdateQuery,
+    BucketRequest, BundleFormat, ChunkRequest, ErrorCode, GraphEntryRequest, GraphExportFormat,
+    GraphExportQuery, JsonGraph, JsonGraphDocument, JsonGraphEdge, JsonGraphNode,
+    JsonGraphNodeMetadata, KeyValueRequest, ListObjectsQuery, ManifestFile, PnrRecord,
+    PnrRecordType, PnrRequest, PointerRequest, RegisterRequest, ScratchpadRequest,
+    ScratchpadUpdateRequest, StoreType, TokenRequest, VersionQuery,
+};
+use anttp_client::{AntTpClient, ClientError, RetryPolicy, UploadFile};
+use anttp_rust_backend::config::Config;
+use anttp_rust_backend::routes::{api_groups, endpoint_count};
+use anttp_rust_backend::services::NetworkService;
+use anttp_rust_backend::{api_server, ApiState};
+use base64::Engine;
+use bytes::Bytes;
+use futures::{stream, TryStreamExt};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Start the API on a free port and return a client for it
+async fn start_server() -> AntTpClient {
+    start_server_with(Config::default()).await
+}
+
+async fn start_server_with(config: Config) -> AntTpClient {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind a free port");
+    let address = listener.local_addr().unwrap();
+    let network = Arc::new(NetworkService::new().await.unwrap());
+    let state = ApiState::new(network, Arc::new(config)).unwrap();
+    actix_rt::spawn(api_server(listener, state).unwrap());
+
+    AntTpClient::builder(format!("http://{}", address))
+        .store_type(StoreType::Memory)
+        .build()
+        .unwrap()
+}
+
+fn base64(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+fn hex_address(c: char) -> String {
+    c.to_string().repeat(64)
+}
+
+async fn collect(stream: anttp_client::ByteStream) -> Vec<u8> {
+    stream
+        .try_fold(Vec::new(), |mut all, piece| async move {
+            all.extend_from_slice(&piece);
+            Ok(all)
+        })
+        .await
+        .unwrap()
+}
+
+fn pnr_request(name: &str, address: &str) -> PnrRequest {
+    let record = PnrRecord {
+        address: address.to_string(),
+        record_type: PnrRecordType::A,
+        ttl: 60,
+    };
+    PnrRequest {
+        name: name.to_string(),
+        records: [("www".to_string(), record)].into_iter().collect(),
+    }
+}
+
+// ============================================================================
+// SYSTEM
+// ============================================================================
+
+#[actix_rt::test]
+async fn system_endpoints_describe_every_route() {
+    let client = start_server().await;
+
+    let health = client.health().await.unwrap();
+    assert_eq!(health["status"], "healthy");
+
+    let commands = client.get_commands().await.unwrap();
+    assert_eq!(
+        commands["total_endpoints"].as_u64().unwrap() as usize,
+        endpoint_count(&api_groups())
+    );
+    assert_eq!(commands["storage_type"], "Memory");
+
+    let openapi = client.get_openapi().await.unwrap();
+    assert_eq!(openapi["openapi"], "3.0.3");
+    assert!(openapi["paths"]["/anttp-0/chunk"]["post"].is_object());
+}
+
+// ============================================================================
+// CHUNKS, REGISTERS, POINTERS, SCRATCHPADS
+// ============================================================================
+
+#[actix_rt::test]
+async fn chunks_round_trip_as_json_binary_and_stream() {
+    let client = start_server().await;
+
+    let created = client
+        .create_chunk(&ChunkRequest {
+            content: base64(b"Hello World!"),
+        })
+        .await
+        .unwrap();
+    let chunk = client.get_chunk(&created.address).await.unwrap();
+    assert_eq!(chunk.content, base64(b"Hello World!"));
+
+    let binary = client.create_chunk_binary(&b"raw bytes"[..]).await.unwrap();
+    let content = client.get_chunk_binary(&binary.address).await.unwrap();
+    assert_eq!(content, Bytes::from_static(b"raw bytes"));
+
+    let streamed = client.get_chunk_stream(&binary.address).await.unwrap();
+    assert_eq!(collect(streamed).await, b"raw bytes");
+}
+
+#[actix_rt::test]
+async fn registers_and_pointers_can_be_updated() {
+    let client = start_server().await;
+
+    let register = client
+        .create_register(&RegisterRequest {
+            name: "counter".to_string(),
+            content: "01".to_string(),
+        })
+        .await
+        .unwrap();
+    client
+        .update_register(
+            &register.address,
+            &RegisterRequest {
+                name: "counter".to_string(),
+                content: "02".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        client
+            .get_register(&register.address)
+            .await
+            .unwrap()
+            .content,
+        "02"
+    );
+    let history = client
+        .get_register_history(&register.address)
+        .await
+        .unwrap();
+    let values: Vec<_> = history.iter().map(|entry| entry.content.as_str()).collect();
+    assert_eq!(values, ["01", "02"]);
+
+    // A PNR name works in place of the address
+    client
+        .with_owner_key("owner-secret")
+        .create_pnr(&pnr_request("counter", &register.address))
+        .await
+        .unwrap();
+    assert_eq!(client.get_register("counter").await.unwrap().content, "02");
+
+    let pointer = client
+        .create_pointer(&PointerRequest {
+            name: "latest".to_string(),
+            content: hex_address('a'),
+        })
+        .await
+        .unwrap();
+    client
+        .update_pointer(
+            &pointer.address,
+            &PointerRequest {
+                name: "latest".to_string(),
+                content: hex_address('b'),
+            },
+        )
+        .await
+        .unwrap();
+    let target = client.get_pointer(&pointer.address).await.unwrap();
+    assert_eq!(target.content, hex_address('b'));
+}
+
+#[actix_rt::test]
+async fn public_and_private_scratchpads() {
+    let client = start_server().await;
+
+    let public = client
+        .create_public_scratchpad(&ScratchpadRequest {
+            name: "notes".to_string(),
+            content: base64(b"v1"),
+        })
+        .await
+        .unwrap();
+    client
+        .update_public_scratchpad(
+            &public.address,
+            "notes",
+            &ScratchpadUpdateRequest {
+                content: base64(b"v2"),
+            },
+        )
+        .await
+        .unwrap();
+    let data = client.get_public_scratchpad(&public.address).await.unwrap();
+    assert_eq!(data.content, base64(b"v2"));
+
+    let private = client
+        .create_private_scratchpad(&ScratchpadRequest {
+            name: "secret".to_string(),
+            content: base64(b"hidden"),
+        })
+        .await
+        .unwrap();
+    client
+        .update_private_scratchpad(
+            &private.address,
+            "secret",
+            &ScratchpadUpdateRequest {
+                content: base64(b"still hidden"),
+            },
+        )
+        .await
+        .unwrap();
+    let data = client
+        .get_private_scratchpad(&private.address, "secret")
+        .await
+        .unwrap();
+    assert_eq!(data.content, base64(b"still hidden"));
+}
+
+// ============================================================================
+// ARCHIVES
+// ============================================================================
+
+#[actix_rt::test]
+async fn archives_upload_change_compare_and_download() {
+    let client = start_server().await;
+
+    let created = client
+        .create_archive(vec![
+            UploadFile::new("index.html", &b"<h1>Hi</h1>"[..]).with_content_type("text/html"),
+            UploadFile::new("css/site.css", &b"h1 { color: red }"[..]),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(created.files.len(), 2);
+    let css = client
+        .get_archive_file(&created.address, "css/site.css")
+        .await
+        .unwrap();
+    assert_eq!(css, Bytes::from_static(b"h1 { color: red }"));
+    let listing = client.get_archive_root(&created.address).await.unwrap();
+    assert!(listing.to_string().contains("css/site.css"));
+
+    let updated = client
+        .update_archive_files(
+            &created.address,
+            &ArchiveUpdateQuery::default(),
+            vec![UploadFile::new("about.html", &b"About"[..])],
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.previous, created.address);
+
+    let patched = client
+        .patch_archive(
+            &updated.address,
+            &ArchiveUpdateQuery::default(),
+            &ArchivePatchRequest {
+                changes: vec![ArchivePathChange::Delete {
+                    path: "css/site.css".to_string(),
+                }],
+            },
+        )
+        .await
+        .unwrap();
+
+    let diff = client
+        .get_archive_diff(&created.address, &patched.address)
+        .await
+        .unwrap();
+    assert_eq!(diff.added, ["about.html"]);
+    assert_eq!(diff.removed, ["css/site.css"]);
+
+    let manifest = ArchiveManifestRequest {
+        files: vec![ManifestFile {
+            path: "new.txt".to_string(),
+            sha256: "00".repeat(32),
+        }],
+    };
+    let diff = client
+        .diff_archive_with_manifest(&patched.address, &manifest)
+        .await
+        .unwrap();
+    assert!(diff.added.contains(&"new.txt".to_string()));
+    let plan = client
+        .get_archive_sync_plan(&patched.address, &manifest)
+        .await
+        .unwrap();
+    assert_eq!(plan.upload, ["new.txt"]);
+
+    let with_path = client
+        .create_archive_with_path("site", vec![UploadFile::new("a.txt", &b"A"[..])])
+        .await
+        .unwrap();
+    let file = client
+        .get_archive_file(&with_path.address, "site/a.txt")
+        .await
+        .unwrap();
+    assert_eq!(file, Bytes::from_static(b"A"));
+
+    let tarchive = client
+        .create_tarchive(vec![UploadFile::new("t.txt", &b"T"[..])])
+        .await
+        .unwrap();
+    assert!(!tarchive.address.is_empty());
+}
+
+#[actix_rt::test]
+async fn archives_download_as_zip_and_upload_the_zip_again() {
+    let client = start_server().await;
+    let created = client
+        .create_archive(vec![UploadFile::new("docs/readme.txt", &b"Read me"[..])])
+        .await
+        .unwrap();
+
+    let zip = client
+        .download_archive(&created.address, BundleFormat::Zip, None)
+        .await
+        .unwrap();
+    assert!(zip.starts_with(b"PK"));
+    let streamed = client
+        .download_archive_stream(&created.address, BundleFormat::Zip, None)
+        .await
+        .unwrap();
+    assert_eq!(collect(streamed).await, zip.to_vec());
+
+    // The same zip back in, once whole and once as a stream of small pieces
+    let uploaded = client.create_archive_from_zip(zip.clone()).await.unwrap();
+    let pieces: Vec<Result<Bytes, std::io::Error>> = zip
+        .chunks(16)
+        .map(|piece| Ok(Bytes::copy_from_slice(piece)))
+        .collect();
+    let streamed = client
+        .create_archive_from_zip_stream(stream::iter(pieces))
+        .await
+        .unwrap();
+    assert_eq!(uploaded.address, streamed.address);
+
+    let address = uploaded.address.unwrap();
+    let file = client
+        .get_archive_file_stream(&address, "docs/readme.txt")
+        .await
+        .unwrap();
+    assert_eq!(collect(file).await, b"Read me");
+}
+
+#[actix_rt::test]
+async fn multipart_uploads_stop_at_the_size_limit() {
+    let mut config = Config::default();
+    config.limits.multipart_body_bytes = 1024;
+    let client = start_server_with(config).await;
+
+    let too_large = |error: ClientError| match error {
+        ClientError::Api(api) => {
+            assert_eq!(api.status.as_u16(), 413);
+            assert_eq!(api.code, ErrorCode::PayloadTooLarge);
+        }
+        other => panic!("expected an API error, got {:?}", other),
+    };
+
+    // Two files under the limit on their own, over it together
+    let halves = || {
+        vec![
+            UploadFile::new("a.bin", vec![b'a'; 600]),
+            UploadFile::new("b.bin", vec![b'b'; 600]),
+        ]
+    };
+    too_large(client.create_archive(halves()).await.unwrap_err());
+    too_large(client.create_tarchive(halves()).await.unwrap_err());
+
+    let created = client
+        .create_archive(vec![UploadFile::new("a.bin", vec![b'a'; 600])])
+        .await
+        .unwrap();
+    too_large(
+        client
+            .update_archive_files(&created.address, &ArchiveUpdateQuery::default(), halves())
+            .await
+            .unwrap_err(),
+    );
+}
+
+// ============================================================================
+// GRAPH, PNR, PUBLIC DATA
+// ============================================================================
+
+#[actix_rt::test]
+async fn graph_entries_import_and_export() {
+    let client = start_server().await;
+
+    let created = client
+        .create_graph_entry(&GraphEntryRequest {
+            name: "root".to_string(),
+            content: "cafe".to_string(),
+            descendants: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let address = created["address"].as_str().unwrap();
+    assert_eq!(
+        client.get_graph_entry(address).await.unwrap().content,
+        "cafe"
+    );
+
+    let node = |label: &str| JsonGraphNode {
+        label: label.to_string(),
+        metadata: JsonGraphNodeMetadata {
+            content: "00".to_string(),
+        },
+    };
+    let document = JsonGraphDocument {
+        graph: JsonGraph {
+            directed: true,
+            nodes: [("a".to_string(), node("a")), ("b".to_string(), node("b"))]
+                .into_iter()
+                .collect(),
+            edges: vec![JsonGraphEdge {
+                source: "a".to_string(),
+                target: "b".to_string(),
+            }],
+        },
+    };
+    let imported = client.import_graph(&document).await.unwrap();
+    assert_eq!(imported.roots.len(), 1);
+
+    let query = GraphExportQuery {
+        format: GraphExportFormat::Json,
+        depth: None,
+    };
+    let exported = client
+        .export_graph(&imported.roots[0], &query)
+        .await
+        .unwrap();
+    let exported: JsonGraphDocument = serde_json::from_slice(&exported).unwrap();
+    assert_eq!(exported.graph.nodes.len(), 2);
+}
+
+#[actix_rt::test]
+async fn owned_pnr_names_need_the_owner_key() {
+    let client = start_server().await;
+    let owner = client.with_owner_key("owner-secret");
+
+    owner
+        .create_pnr(&pnr_request("my-site", &hex_address('a')))
+        .await
+        .unwrap();
+    let records = client.get_pnr("my-site").await.unwrap();
+    assert_eq!(records["records"]["www"]["address"], hex_address('a'));
+
+    let error = client
+        .update_pnr("my-site", &pnr_request("my-site", &hex_address('b')))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Unauthorized));
+    let error = client
+        .with_owner_key("wrong")
+        .delete_pnr("my-site")
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Forbidden));
+
+    owner
+        .append_pnr("my-site", &pnr_request("my-site", &hex_address('c')))
+        .await
+        .unwrap();
+    // A taken name can't be registered again, not even by its owner
+    let error = client
+        .with_owner_key("thief")
+        .create_pnr(&pnr_request("my-site", &hex_address('d')))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Conflict));
+    let error = owner
+        .create_pnr(&pnr_request("my-site", &hex_address('d')))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Conflict));
+
+    owner.delete_pnr("my-site").await.unwrap();
+    assert!(client.get_pnr("my-site").await.unwrap_err().is_not_found());
+    // Only POST registers names
+    let error = client
+        .with_owner_key("thief")
+        .update_pnr("my-site", &pnr_request("my-site", &hex_address('d')))
+        .await
+        .unwrap_err();
+    assert!(error.is_not_found());
+}
+
+#[actix_rt::test]
+async fn pnr_names_registered_without_a_key_get_one() {
+    let client = start_server().await;
+
+    let created = client
+        .create_pnr(&pnr_request("open-site", &hex_address('a')))
+        .await
+        .unwrap();
+    let owner_key = created["owner_key"]
+        .as_str()
+        .expect("a generated owner key");
+    assert_eq!(owner_key.len(), 64);
+
+    // Nobody else can change it, with or without a key of their own
+    let error = client
+        .append_pnr("open-site", &pnr_request("open-site", &hex_address('b')))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Unauthorized));
+    let error = client
+        .with_owner_key("first-writer")
+        .update_pnr("open-site", &pnr_request("open-site", &hex_address('b')))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Forbidden));
+
+    client
+        .with_owner_key(owner_key)
+        .update_pnr("open-site", &pnr_request("open-site", &hex_address('c')))
+        .await
+        .unwrap();
+    let records = client.get_pnr("open-site").await.unwrap();
+    assert_eq!(records["records"]["www"]["address"], hex_address('c'));
+
+    // A key sent at registration is not echoed back
+    let created = client
+        .with_owner_key("mine")
+        .create_pnr(&pnr_request("keyed-site", &hex_address('a')))
+        .await
+        .unwrap();
+    assert!(created.get("owner_key").is_none());
+}
+
+#[actix_rt::test]
+async fn public_data_round_trip() {
+    let client = start_server().await;
+
+    let created = client.create_public_data(&b"public"[..]).await.unwrap();
+    let address = created["address"].as_str().unwrap();
+    assert_eq!(
+        client.get_public_data(address).await.unwrap(),
+        Bytes::from_static(b"public")
+    );
+    let streamed = client.get_public_data_stream(address).await.unwrap();
+    assert_eq!(collect(streamed).await, b"public");
+}
+
+// ============================================================================
+// KEY/VALUE AND WEBDAV
+// ============================================================================
+
+#[actix_rt::test]
+async fn key_value_objects_and_versions() {
+    let client = start_server().await;
+
+    let bucket = client
+        .create_bucket(
+            "photos",
+            &BucketRequest {
+                versioning: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+    assert!(bucket.versioning);
+
+    client
+        .create_key_value(&KeyValueRequest {
+            bucket: "photos".to_string(),
+            object: "2024/cat.txt".to_string(),
+            content: base64(b"meow"),
+            content_type: Some("text/plain".to_string()),
+        })
+        .await
+        .unwrap();
+    let pieces = stream::iter(vec![
+        Ok::<_, std::io::Error>(Bytes::from_static(b"meow ")),
+        Ok(Bytes::from_static(b"meow")),
+    ]);
+    let second = client
+        .create_key_value_stream("photos", "2024/cat.txt", pieces, "text/plain")
+        .await
+        .unwrap();
+
+    let latest = VersionQuery::default();
+    let data = client
+        .get_key_value("photos", "2024/cat.txt", &latest)
+        .await
+        .unwrap();
+    assert_eq!(data.content, base64(b"meow meow"));
+    let head = client
+        .head_key_value("photos", "2024/cat.txt", &latest)
+        .await
+        .unwrap();
+    assert_eq!(head.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(head.version_id, second.version_id);
+
+    let versions = client
+        .list_key_value_versions("photos", "2024/cat.txt")
+        .await
+        .unwrap();
+    assert_eq!(versions.versions.len(), 2);
+    let first = VersionQuery {
+        version_id: Some(versions.versions[1].version_id.clone()),
+    };
+    let old = client
+        .get_key_value_binary("photos", "2024/cat.txt", &first)
+        .await
+        .unwrap();
+    assert_eq!(old, Bytes::from_static(b"meow"));
+
+    client
+        .create_key_value_binary("photos", "2024/dog.txt", &b"woof"[..], "text/plain")
+        .await
+        .unwrap();
+    let listing = client
+        .list_key_values(
+            "photos",
+            &ListObjectsQuery {
+                prefix: Some("2024/".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(listing.key_count, 2);
+    let streamed = client
+        .get_key_value_stream("photos", "2024/dog.txt", &latest)
+        .await
+        .unwrap();
+    assert_eq!(collect(streamed).await, b"woof");
+
+    let buckets = client.list_buckets().await.unwrap();
+    assert_eq!(buckets.buckets.len(), 1);
+    client
+        .delete_key_value("photos", "2024/dog.txt", &latest)
+        .await
+        .unwrap();
+    let error = client.delete_bucket("photos").await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Conflict));
+}
+
+#[actix_rt::test]
+async fn objects_from_before_versioning_survive_a_delete() {
+    let client = start_server().await;
+
+    client
+        .create_key_value_binary("docs", "old.txt", &b"before"[..], "text/plain")
+        .await
+        .unwrap();
+    client
+        .create_bucket(
+            "docs",
+            &BucketRequest {
+                versioning: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+    let deleted = client
+        .delete_key_value("docs", "old.txt", &VersionQuery::default())
+        .await
+        .unwrap();
+    let marker = deleted["delete_marker_version_id"].as_str().unwrap();
+
+    let latest = VersionQuery::default();
+    let error = client.get_key_value("docs", "old.txt", &latest).await;
+    assert!(error.unwrap_err().is_not_found());
+    let null_version = VersionQuery {
+        version_id: Some("null".to_string()),
+    };
+    let old = client
+        .get_key_value_binary("docs", "old.txt", &null_version)
+        .await
+        .unwrap();
+    assert_eq!(old, Bytes::from_static(b"before"));
+
+    // Deleting the marker brings it back
+    let marker = VersionQuery {
+        version_id: Some(marker.to_string()),
+    };
+    client
+        .delete_key_value("docs", "old.txt", &marker)
+        .await
+        .unwrap();
+    let restored = client
+        .get_key_value_binary("docs", "old.txt", &latest)
+        .await
+        .unwrap();
+    assert_eq!(restored, Bytes::from_static(b"before"));
+
+    // Suspended: a delete replaces the "null" version with a "null" marker, others stay
+    let kept = client
+        .create_key_value_binary("docs", "old.txt", &b"versioned"[..], "text/plain")
+        .await
+        .unwrap();
+    client
+        .create_bucket(
+            "docs",
+            &BucketRequest {
+                versioning: Some(false),
+            },
+        )
+        .await
+        .unwrap();
+    let deleted = client
+        .delete_key_value("docs", "old.txt", &VersionQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(deleted["delete_marker_version_id"], "null");
+    let error = client.get_key_value("docs", "old.txt", &latest).await;
+    assert!(error.unwrap_err().is_not_found());
+    let versions = client
+        .list_key_value_versions("docs", "old.txt")
+        .await
+        .unwrap()
+        .versions;
+    assert_eq!(versions.len(), 2);
+    assert!(versions[0].is_latest && versions[0].is_delete_marker);
+    assert_eq!(versions[0].version_id, "null");
+    assert_eq!(Some(&versions[1].version_id), kept.version_id.as_ref());
+
+    // Deleting the "null" marker brings the versioned object back
+    client
+        .delete_key_value("docs", "old.txt", &null_version)
+        .await
+        .unwrap();
+    let restored = client
+        .get_key_value_binary("docs", "old.txt", &latest)
+        .await
+        .unwrap();
+    assert_eq!(restored, Bytes::from_static(b"versioned"));
+}
+
+#[actix_rt::test]
+async fn webdav_drive() {
+    let client = start_server().await;
+
+    assert!(client
+        .webdav_options("")
+        .await
+        .unwrap()
+        .contains(&"PROPFIND".to_string()));
+    client.webdav_mkcol("kv/docs").await.unwrap();
+    let archive = client
+        .webdav_put("kv/docs/a.txt", &b"A"[..], "text/plain")
+        .await
+        .unwrap();
+    assert_eq!(archive, None);
+    assert!(client
+        .webdav_propfind("kv/docs/", 1)
+        .await
+        .unwrap()
+        .contains("a.txt"));
+    client
+        .webdav_move("kv/docs/a.txt", "kv/docs/b.txt", false)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.webdav_get("kv/docs/b.txt").await.unwrap(),
+        Bytes::from_static(b"A")
+    );
+    client.webdav_delete("kv/docs/b.txt").await.unwrap();
+
+    // A versioned bucket can't be emptied over WebDAV - its files stay visible
+    client
+        .create_bucket(
+            "kept",
+            &BucketRequest {
+                versioning: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+    client
+        .webdav_put("kv/kept/a.txt", &b"A"[..], "text/plain")
+        .await
+        .unwrap();
+    let error = client.webdav_delete("kv/kept").await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Conflict));
+    assert_eq!(
+        client.webdav_get("kv/kept/a.txt").await.unwrap(),
+        Bytes::from_static(b"A")
+    );
+    // Folders move as a whole
+    for name in ["x", "y"] {
+        client
+            .webdav_put(&format!("kv/docs/f/{}.txt", name), &b"F"[..], "text/plain")
+            .await
+            .unwrap();
+    }
+    client
+        .webdav_move("kv/docs/f", "kv/docs/g", false)
+        .await
+        .unwrap();
+    let listing = client.webdav_propfind("kv/docs/g/", 1).await.unwrap();
+    assert!(listing.contains("x.txt") && listing.contains("y.txt"));
+    assert!(client.webdav_get("kv/docs/f/x.txt").await.is_err());
+    client.webdav_delete("kv/docs").await.unwrap();
+
+    // Writing into an archive makes a new one
+    let created = client
+        .create_archive(vec![UploadFile::new("old.txt", &b"old"[..])])
+        .await
+        .unwrap();
+    let new_address = client
+        .webdav_put(
+            &format!("archive/{}/new.txt", created.address),
+            &b"new"[..],
+            "text/plain",
+        )
+        .await
+        .unwrap()
+        .expect("a new archive address");
+    let file = client
+        .get_archive_file(&new_address, "new.txt")
+        .await
+        .unwrap();
+    assert_eq!(file, Bytes::from_static(b"new"));
+}
+
+// ============================================================================
+// ERRORS, STORE TYPES AND RETRIES
+// ============================================================================
+
+#[actix_rt::test]
+async fn errors_carry_the_server_error_response() {
+    let client = start_server().await;
+
+    let error = client.get_chunk(&hex_address('f')).await.unwrap_err();
+    let ClientError::Api(api) = &error else {
+        panic!("expected an API error, got {:?}", error);
+    };
+    assert_eq!(api.status.as_u16(), 404);
+    assert_eq!(api.code, ErrorCode::NotFound);
+    assert!(api.request_id.is_some());
+
+    // A pointer is not an archive
+    let pointer = client
+        .create_pointer(&PointerRequest {
+            name: "p".to_string(),
+            content: hex_address('a'),
+        })
+        .await
+        .unwrap();
+    let error = client.get_archive_root(&pointer.address).await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::TypeMismatch));
+
+    let error = client
+        .create_register(&RegisterRequest {
+            name: "bad".to_string(),
+            content: "not hex".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::InvalidInput));
+}
+
+// ============================================================================
+// AUTHENTICATION
+// ============================================================================
+
+#[actix_rt::test]
+async fn tokens_and_scopes_guard_the_api() {
+    let mut config = Config::default();
+    config.auth.enabled = true;
+    config.auth.anonymous_scopes = vec!["read:public_data".to_string()];
+    config.auth.admin_token = Some("admin-token-0123456789".to_string());
+    let anonymous = start_server_with(config).await;
+    let admin = anonymous.with_token("admin-token-0123456789");
+
+    // Open without a token: health, and what anonymous_scopes allow
+    anonymous.health().await.unwrap();
+    let created = admin.create_public_data(&b"public"[..]).await.unwrap();
+    let address = created["address"].as_str().unwrap();
+    anonymous.get_public_data(address).await.unwrap();
+    let error = anonymous
+        .create_public_data(&b"anyone"[..])
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Unauthorized));
+    // A wrong token is refused, even where no token would do
+    let wrong = anonymous.with_token("not-a-token");
+    wrong.health().await.unwrap();
+    let error = wrong.get_public_data(address).await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Unauthorized));
+
+    // A token for one bucket
+    let issued = admin
+        .issue_token(&TokenRequest {
+            name: "notes-app".to_string(),
+            scopes: vec!["write:key_value/notes".to_string()],
+            expires_in_seconds: None,
+        })
+        .await
+        .unwrap();
+    let notes = anonymous.with_token(issued.token.clone());
+    notes
+        .create_bucket("notes", &BucketRequest::default())
+        .await
+        .unwrap();
+    notes
+        .create_key_value_binary("notes", "today.txt", &b"milk"[..], "text/plain")
+        .await
+        .unwrap();
+    let error = notes
+        .create_bucket("photos", &BucketRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Forbidden));
+    let error = notes.list_tokens().await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Forbidden));
+
+    // An archive token can't move a pointer along with its update
+    let issued_archive = admin
+        .issue_token(&TokenRequest {
+            name: "deploy-bot".to_string(),
+            scopes: vec!["write:archive".to_string()],
+            expires_in_seconds: None,
+        })
+        .await
+        .unwrap();
+    let deploy = anonymous.with_token(issued_archive.token);
+    let archive = deploy
+        .create_archive(vec![UploadFile::new("index.html", &b"v1"[..])])
+        .await
+        .unwrap();
+    let pointer = admin
+        .create_pointer(&PointerRequest {
+            name: "site".to_string(),
+            content: archive.address.clone(),
+        })
+        .await
+        .unwrap();
+    let rename = ArchivePatchRequest {
+        changes: vec![ArchivePathChange::Rename {
+            from: "index.html".to_string(),
+            to: "home.html".to_string(),
+        }],
+    };
+    let error = deploy
+        .patch_archive(
+            &archive.address,
+            &ArchiveUpdateQuery {
+                pointer: Some(pointer.address.clone()),
+            },
+            &rename,
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::Forbidden));
+    assert_eq!(
+        admin.get_pointer(&pointer.address).await.unwrap().content,
+        archive.address
+    );
+    deploy
+        .patch_archive(&archive.address, &ArchiveUpdateQuery::default(), &rename)
+        .await
+        .unwrap();
+
+    let tokens = admin.list_tokens().await.unwrap().tokens;
+    assert_eq!(tokens.len(), 3);
+    admin.revoke_token(&issued.info.id).await.unwrap();
+    let error = notes
+        .get_key_value("notes", "today.txt", &VersionQuery::default())
+        .await;
+    assert_eq!(error.unwrap_err().code(), Some(ErrorCode::Unauthorized));
+}
+
+#[actix_rt::test]
+async fn scoped_tokens_on_webdav_and_body_named_routes() {
+    let mut config = Config::default();
+    config.auth.enabled = true;
+    config.auth.admin_token = Some("admin-token-0123456789".to_string());
+    let anonymous = start_server_with(config).await;
+    let admin = anonymous.with_token("admin-token-0123456789");
+    let issue = |scopes: &[&str]| {
+        let admin = admin.clone();
+        let request = TokenRequest {
+            name: scopes.join(" "),
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            expires_in_seconds: None,
+        };
+        async move {
+            let issued = admin.issue_token(&request).await.unwrap();
+            admin.with_token(issued.token)
+        }
+    };
+    fn forbidden<T: std::fmt::Debug>(result: Result<T, ClientError>) {
+        assert_eq!(result.unwrap_err().code(), Some(ErrorCode::Forbidden));
+    }
+    admin
+        .create_bucket("notes", &BucketRequest::default())
+        .await
+        .unwrap();
+    admin
+        .create_bucket("photos", &BucketRequest::default())
+        .await
+        .unwrap();
+
+    // WebDAV paths are checked as the bucket they point into
+    let notes = issue(&["write:key_value/notes"]).await;
+    notes
+        .webdav_put("kv/notes/a.txt", &b"A"[..], "text/plain")
+        .await
+        .unwrap();
+    forbidden(
+        notes
+            .webdav_put("kv/photos/a.txt", &b"A"[..], "text/plain")
+            .await,
+    );
+    forbidden(
+        notes
+            .webdav_move("kv/notes/a.txt", "kv/photos/a.txt", false)
+            .await,
+    );
+    notes
+        .webdav_move("kv/notes/a.txt", "kv/notes/b.txt", false)
+        .await
+        .unwrap();
+    // ...and a webdav scope alone doesn't open every bucket
+    let drive = issue(&["write:webdav"]).await;
+    drive.webdav_propfind("", 1).await.unwrap();
+    forbidden(
+        drive
+            .webdav_put("kv/notes/c.txt", &b"C"[..], "text/plain")
+            .await,
+    );
+
+    // The bucket of POST /anttp-0/key_value is in the body
+    let object = |bucket: &str| KeyValueRequest {
+        bucket: bucket.to_string(),
+        object: "today.txt".to_string(),
+        content: base64(b"milk"),
+        content_type: None,
+    };
+    notes.create_key_value(&object("notes")).await.unwrap();
+    forbidden(notes.create_key_value(&object("photos")).await);
+    let reader = issue(&["read:key_value"]).await;
+    forbidden(reader.create_key_value(&object("photos")).await);
+
+    // ...and so is the name of POST /anttp-0/pnr
+    let site = issue(&["write:pnr/my-site"]).await;
+    site.create_pnr(&pnr_request("my-site", &hex_address('a')))
+        .await
+        .unwrap();
+    forbidden(
+        site.create_pnr(&pnr_request("other-site", &hex_address('a')))
+            .await,
+    );
+}
+
+#[actix_rt::test]
+async fn store_type_is_sent_with_every_call() {
+    let client = start_server().await;
+
+    // Registers aren't on the network yet: the server says so, and it isn't retried
+    let network = client.with_store_type(StoreType::Network);
+    assert_eq!(network.store_type(), Some(StoreType::Network));
+    let error = network.get_register(&hex_address('a')).await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::BackendUnavailable));
+    assert!(!error.is_transient());
+    // HEAD has no body to say why, but keeps the status GET would give
+    let error = network
+        .with_retry_policy(RetryPolicy::none())
+        .head_key_value("photos", "cat.txt", &VersionQuery::default())
+        .await
+        .unwrap_err();
+    assert_eq!(error.status().map(|status| status.as_u16()), Some(503));
+
+    let commands = client
+        .with_store_type(StoreType::Disk)
+        .get_commands()
+        .await
+        .unwrap();
+    assert_eq!(commands["storage_type"], "Disk");
+}
+
+/// A server that answers 503 (without a body) `failures` times, then 200
+async fn start_flaky_server(failures: usize) -> (AntTpClient, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = HttpServer::new(move || {
+        let counter = counter.clone();
+        App::new().default_service(web::to(move || {
+            let hit = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if hit < failures {
+                    HttpResponse::ServiceUnavailable().finish()
+                } else {
+                    HttpResponse::Ok().json(serde_json::json!({ "status": "healthy" }))
+                }
+            }
+        }))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_rt::spawn(server);
+
+    let retry = RetryPolicy {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(1),
+        ..RetryPolicy::default()
+    };
+    let client = AntTpClient::builder(format!("http://{}", address))
+        .retry_policy(retry)
+        .build()
+        .unwrap();
+    (client, hits)
+}
+
+#[actix_rt::test]
+async fn transient_errors_are_retried() {
+    let (client, hits) = start_flaky_server(2).await;
+    assert_eq!(client.health().await.unwrap()["status"], "healthy");
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    // Out of retries: the last error is returned
+    let (client, hits) = start_flaky_server(10).await;
+    let error = client.health().await.unwrap_err();
+    assert_eq!(error.status().map(|status| status.as_u16()), Some(503));
+    assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+    // POST isn't repeated unless the policy allows it
+    let (client, hits) = start_flaky_server(1).await;
+    client.create_chunk_binary(&b"x"[..]).await.unwrap_err();
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let (client, hits) = start_flaky_server(0).await;
+    let client = client.with_retry_policy(RetryPolicy::none());
+    client.health().await.unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[actix_rt::test]
+async fn unreachable_server_is_an_http_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let client = AntTpClient::builder(format!("http://{}", address))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    let error = client.health().await.unwrap_err();
+    assert!(matches!(error, ClientError::Http(_)));
+    assert!(error.is_transient());
+
+    let error = AntTpClient::new("ftp://example.com").unwrap_err();
+    assert!(matches!(error, ClientError::InvalidUrl(_)));
+}
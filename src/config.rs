@@ -0,0 +1,963 @@
+// src/config.rs
+//! Server configuration - TOML file, environment variables, command line
+//!
+//! For 1st Year CS Students:
+//! Every setting has a default. Four layers can change it, each one
+//! winning over the ones before:
+//!
+//!   1. the defaults below
+//!   2. a TOML file (`--config anttp.toml` or ANTTP_CONFIG)
+//!   3. environment variables (SERVER_PORT, S3_ENABLED, ...)
+//!   4. command-line flags (`--port 8080`)
+//!
+//! The result is checked once at startup. A typo in the file, a port that
+//! isn't a number or a missing certificate stops the server with a message
+//! naming the setting - instead of a panic, or a default used silently.
+//! `anttp.example.toml` lists every setting.
+
+use actix_web::http::header::HeaderName;
+use actix_web::http::Method;
+use clap::{Parser, ValueEnum};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::cors::OriginPattern;
+use crate::models::StoreType;
+use crate::services::tokens::{parse_scopes, Access};
+
+/// Shown instead of secrets (S3 secret keys, API tokens) in the effective config
+pub const REDACTED: &str = "********";
+
+/// Why the configuration can't be used
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Can't read config file {0}: {1}")]
+    Read(String, std::io::Error),
+    #[error("Invalid config file {0}: {1}")]
+    Parse(String, String),
+    #[error("Invalid environment variable {0}: {1}")]
+    Env(String, String),
+    #[error("Invalid setting {0}: {1}")]
+    Invalid(String, String),
+}
+
+impl ConfigError {
+    /// A setting that parsed but can't be used
+    pub fn invalid(setting: &str, message: impl Display) -> Self {
+        Self::Invalid(setting.to_string(), message.to_string())
+    }
+}
+
+// ============================================================================
+// SETTINGS - One struct per [section] of the TOML file
+// ============================================================================
+
+/// Every server setting
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub storage: StorageConfig,
+    pub limits: LimitsConfig,
+    pub cors: CorsConfig,
+    pub auth: AuthConfig,
+    pub tls: TlsConfig,
+    pub logging: LoggingConfig,
+    pub features: FeaturesConfig,
+    pub dns: DnsSettings,
+    pub s3: S3Settings,
+    pub publish: PublishSettings,
+}
+
+/// The API listener
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Worker threads (one per CPU core when not set)
+    pub workers: Option<usize>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 18888,
+            workers: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// "host:port", as given to bind
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Where data is kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Store used by requests without an x-store-type header
+    pub default_store_type: StoreType,
+    /// Directory for files the server keeps between restarts (created if missing)
+    pub data_dir: Option<PathBuf>,
+    /// Also store brotli/gzip copies of archive files, served as-is
+    pub archive_precompress: bool,
+}
+
+/// Largest request bodies, in bytes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// JSON request bodies
+    pub json_body_bytes: usize,
+    /// Raw-bytes bodies (binary chunks, public data, key/value objects)
+    pub binary_body_bytes: usize,
+    /// Zip files converted into an archive
+    pub zip_body_bytes: usize,
+    /// What one zip may unpack to (all entries together, held in memory)
+    pub zip_expanded_bytes: usize,
+    /// Multipart archive uploads (all files of one request together)
+    pub multipart_body_bytes: usize,
+    /// Files a WebDAV client PUTs
+    pub webdav_body_bytes: usize,
+    /// S3 listener (objects and multipart parts)
+    pub s3_body_bytes: usize,
+    /// Parts of all unfinished S3 multipart uploads together (kept in memory)
+    pub s3_multipart_bytes: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            json_body_bytes: 2 * 1024 * 1024,
+            binary_body_bytes: 256 * 1024,
+            zip_body_bytes: 256 * 1024 * 1024,
+            zip_expanded_bytes: 512 * 1024 * 1024,
+            multipart_body_bytes: 256 * 1024 * 1024,
+            webdav_body_bytes: 256 * 1024 * 1024,
+            s3_body_bytes: 256 * 1024 * 1024,
+            s3_multipart_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Which websites may call the API from a browser (see `cors.rs`)
+///
+/// Origins are "https://example.com", "https://*.example.com" (any
+/// subdomain) or "*" (every website).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// May read: GET, HEAD, WebDAV PROPFIND and the read-only POSTs (archive diffs)
+    pub read_origins: Vec<String>,
+    /// May also change data: POST, PUT, PATCH, DELETE, ...
+    pub write_origins: Vec<String>,
+    /// Methods a preflight may ask for ("*" = any)
+    pub allowed_methods: Vec<String>,
+    /// Request headers a preflight may ask for ("*" = any)
+    pub allowed_headers: Vec<String>,
+    /// Let browsers send cookies / HTTP auth (not with "*" origins)
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight answer
+    pub max_age_seconds: usize,
+}
+
+impl Default for CorsConfig {
+    /// Any website may read; only the frontend's dev server (`frontend/`,
+    /// port 5173) may write
+    fn default() -> Self {
+        Self {
+            read_origins: vec!["*".to_string()],
+            write_origins: vec![
+                "http://localhost:5173".to_string(),
+                "http://127.0.0.1:5173".to_string(),
+            ],
+            allowed_methods: vec!["*".to_string()],
+            allowed_headers: vec!["*".to_string()],
+            allow_credentials: false,
+            max_age_seconds: 3600,
+        }
+    }
+}
+
+/// Bearer-token authentication (see `services/tokens.rs` for scopes)
+///
+/// Off by default: then every client may do everything, as before.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    /// Scopes for requests without a token, e.g. ["read:archive"] (read-only)
+    pub anonymous_scopes: Vec<String>,
+    /// Token with the `admin` scope (can issue more tokens)
+    pub admin_token: Option<String>,
+    pub tokens: Vec<TokenConfig>,
+}
+
+/// A token defined in the config (`[[auth.tokens]]`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    pub name: String,
+    /// The secret clients send (at least 16 characters)
+    pub token: String,
+    /// e.g. ["read:*", "write:key_value/notes"]
+    pub scopes: Vec<String>,
+}
+
+/// HTTPS for the API and S3 listeners (plain HTTP when not set)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain
+    pub cert_file: Option<PathBuf>,
+    /// PEM private key
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One readable line per message
+    #[default]
+    Text,
+    /// One JSON object per line (for log collectors)
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("expected text or json, got '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Level ("info") or RUST_LOG-style filter ("info,actix_web=warn")
+    pub level: String,
+    pub format: LogFormat,
+    /// Log every request (method, path, status, time, request ID)
+    pub access_log: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+            access_log: true,
+        }
+    }
+}
+
+/// Parts of the API that can be switched off
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// The /webdav drive
+    pub webdav: bool,
+    /// /openapi.json and the /docs viewer
+    pub api_docs: bool,
+    /// gzip/brotli/zstd responses for clients that accept them
+    pub compression: bool,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            webdav: true,
+            api_docs: true,
+            compression: true,
+        }
+    }
+}
+
+impl FeaturesConfig {
+    /// Is this route group (see `routes.rs`) switched on?
+    pub fn enables(&self, group: &str) -> bool {
+        match group {
+            "webdav" => self.webdav,
+            "docs" => self.api_docs,
+            _ => true,
+        }
+    }
+}
+
+/// DNS listener answering from PNR records
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DnsSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// Pseudo top-level domain served from PNR (e.g. "ant")
+    pub tld: String,
+    pub store_type: StoreType,
+    /// This gateway's IPs, the answer to A/AAAA queries (none = NODATA)
+    pub gateway_addresses: Vec<IpAddr>,
+}
+
+impl Default for DnsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            // (not 5353, which mDNS already uses on most networks)
+            port: 18853,
+            tld: "ant".to_string(),
+            store_type: StoreType::default(),
+            gateway_addresses: Vec::new(),
+        }
+    }
+}
+
+/// S3-compatible listener over the key/value store
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Settings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// Region clients must sign for
+    pub region: String,
+    pub store_type: StoreType,
+    /// Access key id → secret access key
+    pub access_keys: BTreeMap<String, String>,
+}
+
+impl Default for S3Settings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 18889,
+            region: "us-east-1".to_string(),
+            store_type: StoreType::default(),
+            access_keys: BTreeMap::new(),
+        }
+    }
+}
+
+/// Server-side directory publishing
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct PublishSettings {
+    /// Only directories inside this one can be published (disabled when not set)
+    pub root: Option<PathBuf>,
+}
+
+// ============================================================================
+// COMMAND LINE - The last layer
+// ============================================================================
+
+/// anttp-backend - AntTP-compatible server
+///
+/// Flags win over environment variables, which win over the config file.
+#[derive(Debug, Default, Parser)]
+#[command(name = "anttp-backend", version)]
+pub struct ServerArgs {
+    /// TOML config file
+    #[arg(short, long, env = "ANTTP_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Print the effective configuration (secrets hidden) and exit
+    #[arg(long)]
+    pub print_config: bool,
+
+    #[arg(long)]
+    pub host: Option<String>,
+    #[arg(long)]
+    pub port: Option<u16>,
+    #[arg(long)]
+    pub workers: Option<usize>,
+    /// Store for requests without x-store-type: memory, disk or network
+    #[arg(long)]
+    pub default_store_type: Option<StoreType>,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Largest multipart archive upload, in bytes (limits.multipart_body_bytes)
+    #[arg(long)]
+    pub max_multipart_body_bytes: Option<usize>,
+    /// Website allowed to read (repeatable; replaces cors.read_origins)
+    #[arg(long = "cors-read-origin")]
+    pub cors_read_origins: Vec<String>,
+    /// Website allowed to write (repeatable; replaces cors.write_origins)
+    #[arg(long = "cors-write-origin")]
+    pub cors_write_origins: Vec<String>,
+    /// Require bearer tokens (see [auth])
+    #[arg(long)]
+    pub auth: bool,
+    #[arg(long)]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(long)]
+    pub tls_key: Option<PathBuf>,
+    /// Level or RUST_LOG-style filter
+    #[arg(long)]
+    pub log_level: Option<String>,
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+    #[arg(long)]
+    pub publish_root: Option<PathBuf>,
+    /// Start the DNS listener
+    #[arg(long)]
+    pub dns: bool,
+    /// Start the S3 listener
+    #[arg(long)]
+    pub s3: bool,
+    #[arg(long)]
+    pub no_webdav: bool,
+    #[arg(long)]
+    pub no_api_docs: bool,
+}
+
+// ============================================================================
+// LOADING - Defaults, file, environment, flags, then checks
+// ============================================================================
+
+impl Config {
+    /// All four layers, checked
+    pub fn load(args: &ServerArgs) -> Result<Self, ConfigError> {
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        config.apply_args(args);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Defaults and environment variables, checked (no file, no flags)
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Defaults plus a TOML file (not checked yet)
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let name = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Read(name.clone(), e))?;
+        // toml's message names the line and the unknown or mistyped key
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(name, e.to_string()))
+    }
+
+    /// Override settings from environment variables
+    ///
+    /// For Students:
+    /// The names are the ones the server has always read (SERVER_PORT,
+    /// DNS_ENABLED, ...), so existing setups keep working.
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        env("SERVER_HOST", &mut self.server.host)?;
+        env("SERVER_PORT", &mut self.server.port)?;
+        env_some("SERVER_WORKERS", &mut self.server.workers)?;
+
+        env("DEFAULT_STORE_TYPE", &mut self.storage.default_store_type)?;
+        env_some("DATA_DIR", &mut self.storage.data_dir)?;
+        env_bool("ARCHIVE_PRECOMPRESS", &mut self.storage.archive_precompress)?;
+
+        env("MAX_JSON_BODY_BYTES", &mut self.limits.json_body_bytes)?;
+        env("MAX_BINARY_BODY_BYTES", &mut self.limits.binary_body_bytes)?;
+        env("MAX_ZIP_BODY_BYTES", &mut self.limits.zip_body_bytes)?;
+        env(
+            "MAX_ZIP_EXPANDED_BYTES",
+            &mut self.limits.zip_expanded_bytes,
+        )?;
+        env(
+            "MAX_MULTIPART_BODY_BYTES",
+            &mut self.limits.multipart_body_bytes,
+        )?;
+        env("MAX_WEBDAV_BODY_BYTES", &mut self.limits.webdav_body_bytes)?;
+        env("MAX_S3_BODY_BYTES", &mut self.limits.s3_body_bytes)?;
+        env(
+            "MAX_S3_MULTIPART_BYTES",
+            &mut self.limits.s3_multipart_bytes,
+        )?;
+
+        env_list("CORS_READ_ORIGINS", &mut self.cors.read_origins);
+        env_list("CORS_WRITE_ORIGINS", &mut self.cors.write_origins);
+        env_list("CORS_ALLOWED_METHODS", &mut self.cors.allowed_methods);
+        env_list("CORS_ALLOWED_HEADERS", &mut self.cors.allowed_headers);
+        env_bool("CORS_ALLOW_CREDENTIALS", &mut self.cors.allow_credentials)?;
+        env("CORS_MAX_AGE_SECONDS", &mut self.cors.max_age_seconds)?;
+
+        env_bool("AUTH_ENABLED", &mut self.auth.enabled)?;
+        env_list("AUTH_ANONYMOUS_SCOPES", &mut self.auth.anonymous_scopes);
+        env_some("AUTH_ADMIN_TOKEN", &mut self.auth.admin_token)?;
+
+        env_some("TLS_CERT_FILE", &mut self.tls.cert_file)?;
+        env_some("TLS_KEY_FILE", &mut self.tls.key_file)?;
+
+        // RUST_LOG is what Rust programs usually read; LOG_LEVEL is ours
+        env("RUST_LOG", &mut self.logging.level)?;
+        env("LOG_LEVEL", &mut self.logging.level)?;
+        env("LOG_FORMAT", &mut self.logging.format)?;
+        env_bool("ACCESS_LOG", &mut self.logging.access_log)?;
+
+        env_bool("WEBDAV_ENABLED", &mut self.features.webdav)?;
+        env_bool("API_DOCS_ENABLED", &mut self.features.api_docs)?;
+        env_bool("COMPRESSION_ENABLED", &mut self.features.compression)?;
+
+        env_bool("DNS_ENABLED", &mut self.dns.enabled)?;
+        env("DNS_HOST", &mut self.dns.host)?;
+        env("DNS_PORT", &mut self.dns.port)?;
+        env("DNS_TLD", &mut self.dns.tld)?;
+        env("DNS_STORE_TYPE", &mut self.dns.store_type)?;
+        if let Some(addresses) = env_value("DNS_GATEWAY_ADDRESSES") {
+            self.dns.gateway_addresses = split_list(&addresses)
+                .iter()
+                .map(|address| address.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e: std::net::AddrParseError| {
+                    ConfigError::Env("DNS_GATEWAY_ADDRESSES".to_string(), e.to_string())
+                })?;
+        }
+
+        env_bool("S3_ENABLED", &mut self.s3.enabled)?;
+        env("S3_HOST", &mut self.s3.host)?;
+        env("S3_PORT", &mut self.s3.port)?;
+        env("S3_REGION", &mut self.s3.region)?;
+        env("S3_STORE_TYPE", &mut self.s3.store_type)?;
+        if let Some(keys) = env_value("S3_ACCESS_KEYS") {
+            self.s3.access_keys = parse_access_keys(&keys)
+                .map_err(|message| ConfigError::Env("S3_ACCESS_KEYS".to_string(), message))?;
+        }
+
+        env_some("PUBLISH_ROOT", &mut self.publish.root)?;
+        Ok(())
+    }
+
+    /// Override settings from command-line flags
+    fn apply_args(&mut self, args: &ServerArgs) {
+        fn set<T: Clone>(flag: &Option<T>, target: &mut T) {
+            if let Some(value) = flag {
+                *target = value.clone();
+            }
+        }
+        fn set_some<T: Clone>(flag: &Option<T>, target: &mut Option<T>) {
+            if flag.is_some() {
+                *target = flag.clone();
+            }
+        }
+
+        set(&args.host, &mut self.server.host);
+        set(&args.port, &mut self.server.port);
+        set_some(&args.workers, &mut self.server.workers);
+        set(
+            &args.default_store_type,
+            &mut self.storage.default_store_type,
+        );
+        set_some(&args.data_dir, &mut self.storage.data_dir);
+        set(
+            &args.max_multipart_body_bytes,
+            &mut self.limits.multipart_body_bytes,
+        );
+        if !args.cors_read_origins.is_empty() {
+            self.cors.read_origins = args.cors_read_origins.clone();
+        }
+        if !args.cors_write_origins.is_empty() {
+            self.cors.write_origins = args.cors_write_origins.clone();
+        }
+        self.auth.enabled |= args.auth;
+        set_some(&args.tls_cert, &mut self.tls.cert_file);
+        set_some(&args.tls_key, &mut self.tls.key_file);
+        set(&args.log_level, &mut self.logging.level);
+        set(&args.log_format, &mut self.logging.format);
+        set_some(&args.publish_root, &mut self.publish.root);
+        self.dns.enabled |= args.dns;
+        self.s3.enabled |= args.s3;
+        self.features.webdav &= !args.no_webdav;
+        self.features.api_docs &= !args.no_api_docs;
+    }
+
+    /// Check settings that parsed but can't work
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        // Server
+        if self.server.host.is_empty() {
+            return Err(ConfigError::invalid("server.host", "must not be empty"));
+        }
+        check_resolves("server.host", &self.server.bind_address())?;
+        if self.server.workers == Some(0) {
+            return Err(ConfigError::invalid("server.workers", "must be at least 1"));
+        }
+
+        // Storage
+        if let Some(dir) = &self.storage.data_dir {
+            if dir.exists() && !dir.is_dir() {
+                return Err(ConfigError::invalid(
+                    "storage.data_dir",
+                    format!("{} is not a directory", dir.display()),
+                ));
+            }
+        }
+
+        // Limits
+        let limits = [
+            ("limits.json_body_bytes", self.limits.json_body_bytes),
+            ("limits.binary_body_bytes", self.limits.binary_body_bytes),
+            ("limits.zip_body_bytes", self.limits.zip_body_bytes),
+            ("limits.zip_expanded_bytes", self.limits.zip_expanded_bytes),
+            (
+                "limits.multipart_body_bytes",
+                self.limits.multipart_body_bytes,
+            ),
+            ("limits.webdav_body_bytes", self.limits.webdav_body_bytes),
+            ("limits.s3_body_bytes", self.limits.s3_body_bytes),
+            ("limits.s3_multipart_bytes", self.limits.s3_multipart_bytes),
+        ];
+        for (setting, limit) in limits {
+            if limit == 0 {
+                return Err(ConfigError::invalid(setting, "must be more than 0"));
+            }
+        }
+
+        // CORS
+        let origins = [
+            ("cors.read_origins", &self.cors.read_origins),
+            ("cors.write_origins", &self.cors.write_origins),
+        ];
+        for (setting, list) in origins {
+            for origin in list {
+                OriginPattern::from_str(origin).map_err(|e| ConfigError::invalid(setting, e))?;
+                if origin == "*" && self.cors.allow_credentials {
+                    return Err(ConfigError::invalid(
+                        setting,
+                        "\"*\" can't be used with cors.allow_credentials - list the websites",
+                    ));
+                }
+            }
+        }
+        for method in self.cors.allowed_methods.iter().filter(|m| *m != "*") {
+            Method::from_bytes(method.as_bytes()).map_err(|_| {
+                ConfigError::invalid(
+                    "cors.allowed_methods",
+                    format!("'{}' is not an HTTP method", method),
+                )
+            })?;
+        }
+        for name in self.cors.allowed_headers.iter().filter(|h| *h != "*") {
+            HeaderName::from_str(name).map_err(|_| {
+                ConfigError::invalid(
+                    "cors.allowed_headers",
+                    format!("'{}' is not a header name", name),
+                )
+            })?;
+        }
+
+        // Auth
+        let anonymous = parse_scopes(&self.auth.anonymous_scopes)
+            .map_err(|e| ConfigError::invalid("auth.anonymous_scopes", e))?;
+        if anonymous.iter().any(|scope| scope.access != Access::Read) {
+            return Err(ConfigError::invalid(
+                "auth.anonymous_scopes",
+                "anonymous access can only be read:... scopes",
+            ));
+        }
+        if let Some(token) = &self.auth.admin_token {
+            check_token_secret("auth.admin_token", token)?;
+        }
+        let mut names = BTreeSet::from(["admin"]);
+        for token in &self.auth.tokens {
+            if !names.insert(token.name.as_str()) || token.name.trim().is_empty() {
+                return Err(ConfigError::invalid(
+                    "auth.tokens",
+                    format!("'{}' needs a unique name (\"admin\" is taken)", token.name),
+                ));
+            }
+            check_token_secret("auth.tokens", &token.token)?;
+            if token.scopes.is_empty() {
+                return Err(ConfigError::invalid(
+                    "auth.tokens",
+                    format!("token '{}' needs at least one scope", token.name),
+                ));
+            }
+            parse_scopes(&token.scopes).map_err(|e| ConfigError::invalid("auth.tokens", e))?;
+        }
+
+        // TLS: both files, or neither
+        match (&self.tls.cert_file, &self.tls.key_file) {
+            (Some(_), None) => {
+                return Err(ConfigError::invalid(
+                    "tls.key_file",
+                    "needed with tls.cert_file",
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(ConfigError::invalid(
+                    "tls.cert_file",
+                    "needed with tls.key_file",
+                ))
+            }
+            (Some(cert), Some(key)) => {
+                check_file("tls.cert_file", cert)?;
+                check_file("tls.key_file", key)?;
+            }
+            (None, None) => {}
+        }
+
+        // Logging
+        check_log_filter(&self.logging.level)
+            .map_err(|e| ConfigError::invalid("logging.level", e))?;
+
+        // DNS
+        if self.dns.enabled {
+            parse_socket_address("dns.host", &self.dns.host, self.dns.port)?;
+            let tld = self.dns.tld.trim_matches('.');
+            if tld.is_empty() || tld.contains('.') {
+                return Err(ConfigError::invalid(
+                    "dns.tld",
+                    format!("'{}' must be one label, like \"ant\"", self.dns.tld),
+                ));
+            }
+        }
+
+        // S3: every request is signed, so at least one key is needed
+        if self.s3.enabled {
+            parse_socket_address("s3.host", &self.s3.host, self.s3.port)?;
+            if self.s3.access_keys.is_empty() {
+                return Err(ConfigError::invalid(
+                    "s3.access_keys",
+                    "the S3 listener needs at least one access key",
+                ));
+            }
+            if let Some((id, _)) = self
+                .s3
+                .access_keys
+                .iter()
+                .find(|(id, secret)| id.is_empty() || secret.is_empty())
+            {
+                return Err(ConfigError::invalid(
+                    "s3.access_keys",
+                    format!("key '{}' needs an id and a secret", id),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The same settings with secrets replaced by `REDACTED`
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        for secret in config.s3.access_keys.values_mut() {
+            *secret = REDACTED.to_string();
+        }
+        if let Some(token) = &mut config.auth.admin_token {
+            *token = REDACTED.to_string();
+        }
+        for token in &mut config.auth.tokens {
+            token.token = REDACTED.to_string();
+        }
+        config
+    }
+
+    /// The DNS listener's address (call after `validate`)
+    pub fn dns_address(&self) -> Result<SocketAddr, ConfigError> {
+        parse_socket_address("dns.host", &self.dns.host, self.dns.port)
+    }
+
+    /// The S3 listener's address (call after `validate`)
+    pub fn s3_address(&self) -> Result<SocketAddr, ConfigError> {
+        parse_socket_address("s3.host", &self.s3.host, self.s3.port)
+    }
+}
+
+impl TlsConfig {
+    /// Read the certificate and key (`None` without TLS)
+    pub fn load(&self) -> Result<Option<rustls::ServerConfig>, ConfigError> {
+        let (Some(cert_file), Some(key_file)) = (&self.cert_file, &self.key_file) else {
+            return Ok(None);
+        };
+
+        let certs = read_pem(cert_file, "tls.cert_file", |reader| {
+            rustls_pemfile::certs(reader).collect::<Result<Vec<_>, _>>()
+        })?;
+        if certs.is_empty() {
+            return Err(ConfigError::invalid(
+                "tls.cert_file",
+                format!("no certificate in {}", cert_file.display()),
+            ));
+        }
+        let key =
+            read_pem(key_file, "tls.key_file", rustls_pemfile::private_key)?.ok_or_else(|| {
+                ConfigError::invalid(
+                    "tls.key_file",
+                    format!("no private key in {}", key_file.display()),
+                )
+            })?;
+
+        let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| ConfigError::invalid("tls.cert_file", e))?;
+        Ok(Some(config))
+    }
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Helper: An environment variable, if set and not empty
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Helper: Parse an environment variable into `target` (left alone when unset)
+fn env<T: FromStr>(name: &str, target: &mut T) -> Result<(), ConfigError>
+where
+    T::Err: Display,
+{
+    let mut value = None;
+    env_some(name, &mut value)?;
+    if let Some(value) = value {
+        *target = value;
+    }
+    Ok(())
+}
+
+fn env_some<T: FromStr>(name: &str, target: &mut Option<T>) -> Result<(), ConfigError>
+where
+    T::Err: Display,
+{
+    if let Some(value) = env_value(name) {
+        let parsed = value
+            .trim()
+            .parse()
+            .map_err(|e: T::Err| ConfigError::Env(name.to_string(), e.to_string()))?;
+        *target = Some(parsed);
+    }
+    Ok(())
+}
+
+fn env_bool(name: &str, target: &mut bool) -> Result<(), ConfigError> {
+    if let Some(value) = env_value(name) {
+        *target = match value.trim().to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            "0" | "false" | "no" | "off" => false,
+            _ => {
+                return Err(ConfigError::Env(
+                    name.to_string(),
+                    format!("expected true or false, got '{}'", value),
+                ))
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Helper: A comma-separated environment variable into `target`
+fn env_list(name: &str, target: &mut Vec<String>) {
+    if let Some(value) = env_value(name) {
+        *target = split_list(&value);
+    }
+}
+
+/// Helper: "a, b,c" → ["a", "b", "c"]
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse `AKID1:secret1,AKID2:secret2`
+fn parse_access_keys(value: &str) -> Result<BTreeMap<String, String>, String> {
+    split_list(value)
+        .iter()
+        .map(|pair| match pair.split_once(':') {
+            Some((id, secret)) if !id.is_empty() && !secret.is_empty() => {
+                Ok((id.to_string(), secret.to_string()))
+            }
+            _ => Err(format!(
+                "entry '{}' should be ACCESS_KEY_ID:SECRET",
+                pair.split(':').next().unwrap_or_default()
+            )),
+        })
+        .collect()
+}
+
+/// Helper: Configured tokens must be hard to guess
+fn check_token_secret(setting: &str, token: &str) -> Result<(), ConfigError> {
+    if token.chars().count() < 16 || token.chars().any(char::is_whitespace) {
+        return Err(ConfigError::invalid(
+            setting,
+            "tokens need at least 16 characters and no spaces",
+        ));
+    }
+    Ok(())
+}
+
+fn check_resolves(setting: &str, address: &str) -> Result<(), ConfigError> {
+    address
+        .to_socket_addrs()
+        .map(|_| ())
+        .map_err(|e| ConfigError::invalid(setting, format!("can't listen on {}: {}", address, e)))
+}
+
+fn parse_socket_address(setting: &str, host: &str, port: u16) -> Result<SocketAddr, ConfigError> {
+    format!("{}:{}", host, port).parse().map_err(|e| {
+        ConfigError::invalid(setting, format!("'{}' is not an IP address: {}", host, e))
+    })
+}
+
+fn check_file(setting: &str, path: &Path) -> Result<(), ConfigError> {
+    if path.is_file() {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(
+            setting,
+            format!("{} is not a readable file", path.display()),
+        ))
+    }
+}
+
+/// Helper: Every level in a RUST_LOG-style filter is a real level
+fn check_log_filter(filter: &str) -> Result<(), String> {
+    for directive in filter.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let level = directive
+            .rsplit_once('=')
+            .map_or(directive, |(_, level)| level);
+        let is_level = log::LevelFilter::from_str(level).is_ok();
+        // A bare word can also be a module name ("actix_web" = everything from it)
+        if !is_level && directive.contains('=') {
+            return Err(format!("unknown level '{}' in '{}'", level, filter));
+        }
+    }
+    Ok(())
+}
+
+fn read_pem<T, E: Display>(
+    path: &Path,
+    setting: &str,
+    parse: impl FnOnce(&mut dyn std::io::BufRead) -> Result<T, E>,
+) -> Result<T, ConfigError> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| ConfigError::invalid(setting, format!("{}: {}", path.display(), e)))?;
+    let mut reader = std::io::BufReader::new(file);
+    parse(&mut reader)
+        .map_err(|e| ConfigError::invalid(setting, format!("{}: {}", path.display(), e)))
+}
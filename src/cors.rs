@@ -0,0 +1,441 @@
+// src/cors.rs
+//! CORS policy - Which websites may call the API from a browser
+//!
+//! For 1st Year CS Students:
+//! A web page can `fetch()` any URL, including this server on your own
+//! machine. The browser protects you with CORS: before sending anything
+//! that could change data it asks the server ("preflight": may
+//! https://evil.example POST here?), and the server answers with the
+//! origins, methods and headers it allows.
+//!
+//! Reading and writing get separate lists. Archive sites can be embedded
+//! and fetched from anywhere (`read_origins = ["*"]`), while only the
+//! frontend may create or delete things (`write_origins`). Whether a
+//! request reads or writes comes from the route table (like auth), so a
+//! POST that only compares two archives counts as a read.
+//!
+//! Archive sites are served from this server too, so "same origin" is no
+//! proof a page is ours: only the public first-party routes (/docs,
+//! /openapi.json, /health) skip the lists for same-origin pages. To try
+//! writes from /docs, add the server's own origin to `write_origins`.
+
+use actix_cors::Cors;
+use actix_web::dev::{RequestHead, ResourceDef};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::Method;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::handlers::REQUEST_ID_HEADER;
+use crate::routes::{access_for, enabled_groups};
+use crate::services::Access;
+
+/// One entry of `read_origins` / `write_origins`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OriginPattern {
+    /// "*" - every website
+    Any,
+    /// "https://example.com" (or with a port)
+    Exact(String),
+    /// "https://*.example.com" - any subdomain, not example.com itself
+    Subdomain {
+        scheme: String,
+        /// ".example.com"
+        suffix: String,
+        port: Option<u16>,
+    },
+}
+
+impl FromStr for OriginPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern == "*" {
+            return Ok(Self::Any);
+        }
+        let usage = || {
+            format!(
+                "'{}' should look like https://example.com or https://*.example.com \
+                 (no path, no trailing /)",
+                pattern
+            )
+        };
+        let (scheme, host, port) = split_origin(pattern).ok_or_else(usage)?;
+        match host.strip_prefix('*') {
+            Some(suffix)
+                if suffix.starts_with('.') && suffix.len() > 1 && !suffix.contains('*') =>
+            {
+                Ok(Self::Subdomain {
+                    scheme,
+                    suffix: suffix.to_string(),
+                    port,
+                })
+            }
+            Some(_) => Err(usage()),
+            None if host.contains('*') => Err(usage()),
+            None => Ok(Self::Exact(pattern.to_lowercase())),
+        }
+    }
+}
+
+impl OriginPattern {
+    /// Does the Origin header value `origin` match?
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Exact(exact) => exact.eq_ignore_ascii_case(origin),
+            Self::Subdomain {
+                scheme,
+                suffix,
+                port,
+            } => match split_origin(origin) {
+                Some((origin_scheme, host, origin_port)) => {
+                    origin_scheme == *scheme
+                        && origin_port == *port
+                        && host.len() > suffix.len()
+                        && host.ends_with(suffix.as_str())
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// Helper: "https://Example.com:8443" → ("https", "example.com", Some(8443))
+fn split_origin(origin: &str) -> Option<(String, String, Option<u16>)> {
+    let (scheme, authority) = origin.split_once("://")?;
+    let scheme = scheme.to_lowercase();
+    if !matches!(scheme.as_str(), "http" | "https") || authority.contains(['/', '?', '#', '@']) {
+        return None;
+    }
+    let (host, port) = match authority.rsplit_once(':') {
+        // (an IPv6 host like [::1] has colons too - only a final ":digits" is a port)
+        Some((host, port)) if !port.contains(']') => (host, Some(port.parse().ok()?)),
+        _ => (authority, None),
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((scheme, host.to_lowercase(), port))
+}
+
+/// What one route (or a group's whole prefix) does, for CORS
+struct CorsRoute {
+    /// `None` = any method (access then follows the method)
+    method: Option<Method>,
+    resource: ResourceDef,
+    access: Access,
+    /// Public first-party routes (/docs, /health) trust same-origin pages
+    public: bool,
+}
+
+/// The origin lists and route table, built once and shared by every request
+struct OriginPolicy {
+    read: Vec<OriginPattern>,
+    write: Vec<OriginPattern>,
+    routes: Vec<CorsRoute>,
+    /// "http" or "https", to compare with a same-origin page
+    scheme: &'static str,
+}
+
+impl OriginPolicy {
+    fn from_config(config: &Config) -> Self {
+        let mut routes = Vec::new();
+        for group in enabled_groups(&config.features) {
+            for route in &group.routes {
+                routes.push(CorsRoute {
+                    method: Some(route.method.clone()),
+                    resource: ResourceDef::new(route.pattern),
+                    access: route.access,
+                    public: group.public,
+                });
+            }
+            if let Some(prefix) = group.prefix {
+                routes.push(CorsRoute {
+                    method: None,
+                    resource: ResourceDef::root_prefix(prefix),
+                    access: Access::Read,
+                    public: group.public,
+                });
+            }
+        }
+        Self {
+            read: parse_all(&config.cors.read_origins),
+            write: parse_all(&config.cors.write_origins),
+            routes,
+            scheme: if config.tls.cert_file.is_some() {
+                "https"
+            } else {
+                "http"
+            },
+        }
+    }
+
+    fn allows(&self, origin: &HeaderValue, req: &RequestHead) -> bool {
+        let Ok(origin) = origin.to_str() else {
+            return false;
+        };
+
+        // A preflight asks about the method that will follow
+        let method = match req.headers().get(header::ACCESS_CONTROL_REQUEST_METHOD) {
+            Some(requested) if req.method == Method::OPTIONS => {
+                match Method::from_bytes(requested.as_bytes()) {
+                    Ok(method) => method,
+                    Err(_) => return false,
+                }
+            }
+            _ => req.method.clone(),
+        };
+
+        // Same as actix's routing: the first route for this method and path
+        let route = self.routes.iter().find(|route| {
+            route.method.as_ref().is_none_or(|m| *m == method)
+                && route.resource.is_match(req.uri.path())
+        });
+        if route.is_some_and(|route| route.public) && self.is_same_origin(origin, req) {
+            return true;
+        }
+        let access = match route {
+            Some(CorsRoute {
+                method: Some(_),
+                access,
+                ..
+            }) => *access,
+            // Not in the table (answers 404/405), or a group's own service
+            _ => access_for(&method),
+        };
+
+        // Websites that may write may also read
+        let matches = |patterns: &[OriginPattern]| patterns.iter().any(|p| p.matches(origin));
+        matches(&self.write) || (access == Access::Read && matches(&self.read))
+    }
+
+    /// Helper: Is the page on this server itself? (scheme and Host match)
+    fn is_same_origin(&self, origin: &str, req: &RequestHead) -> bool {
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok());
+        match (origin.split_once("://"), host) {
+            (Some((scheme, authority)), Some(host)) => {
+                scheme.eq_ignore_ascii_case(self.scheme) && authority.eq_ignore_ascii_case(host)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// CORS middleware for the `[cors]` settings (checked by `Config::validate`)
+///
+/// For Students:
+/// Requests with an Origin that isn't allowed get 400 Bad Request before
+/// any handler runs. Browsers skip the preflight for "simple" requests
+/// (like a form POST), so only hiding the answer would still let any
+/// website write - this way the write never happens.
+pub fn cors(config: &Config) -> Cors {
+    let policy = OriginPolicy::from_config(config);
+    let config = &config.cors;
+
+    let mut cors = Cors::default()
+        .allowed_origin_fn(move |origin, req| policy.allows(origin, req))
+        .block_on_origin_mismatch(true)
+        .expose_headers([REQUEST_ID_HEADER])
+        .max_age(config.max_age_seconds);
+
+    cors = if config.allowed_methods.iter().any(|method| method == "*") {
+        cors.allow_any_method()
+    } else {
+        cors.allowed_methods(config.allowed_methods.iter().map(String::as_str))
+    };
+    cors = if config.allowed_headers.iter().any(|name| name == "*") {
+        cors.allow_any_header()
+    } else {
+        cors.allowed_headers(config.allowed_headers.iter().map(String::as_str))
+    };
+    if config.allow_credentials {
+        cors = cors.supports_credentials();
+    }
+    cors
+}
+
+/// Helper: Parse an origin list (`Config::validate` already refused bad entries)
+fn parse_all(origins: &[String]) -> Vec<OriginPattern> {
+    origins
+        .iter()
+        .filter_map(|origin| origin.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::{http::StatusCode, App};
+
+    /// Helper: Does `policy` let a page at `origin` send this request (Host: localhost:18888)?
+    fn allowed(policy: &OriginPolicy, origin: &str, request: TestRequest) -> bool {
+        let req = request
+            .insert_header((header::HOST, "localhost:18888"))
+            .to_srv_request();
+        policy.allows(&HeaderValue::from_str(origin).unwrap(), req.head())
+    }
+
+    fn policy(read: &[&str], write: &[&str]) -> OriginPolicy {
+        let mut config = Config::default();
+        config.cors.read_origins = read.iter().map(|o| o.to_string()).collect();
+        config.cors.write_origins = write.iter().map(|o| o.to_string()).collect();
+        OriginPolicy::from_config(&config)
+    }
+
+    #[test]
+    fn origin_patterns_parse() {
+        assert_eq!("*".parse(), Ok(OriginPattern::Any));
+        assert_eq!(
+            "HTTPS://App.example.com:8443".parse(),
+            Ok(OriginPattern::Exact(
+                "https://app.example.com:8443".to_string()
+            ))
+        );
+        assert_eq!(
+            "https://*.example.com".parse(),
+            Ok(OriginPattern::Subdomain {
+                scheme: "https".to_string(),
+                suffix: ".example.com".to_string(),
+                port: None,
+            })
+        );
+        for bad in [
+            "example.com",
+            "ftp://example.com",
+            "https://example.com/",
+            "https://example.com/app",
+            "https://user@example.com",
+            "https://*",
+            "https://*.",
+            "https://*example.com",
+            "https://a.*.example.com",
+            "https://*.*.example.com",
+            "https://example.com:port",
+            "https://",
+        ] {
+            assert!(
+                bad.parse::<OriginPattern>().is_err(),
+                "{} should be refused",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn wildcard_origins_match_subdomains_only() {
+        let pattern: OriginPattern = "https://*.example.com".parse().unwrap();
+        assert!(pattern.matches("https://app.example.com"));
+        assert!(pattern.matches("https://a.b.EXAMPLE.com"));
+        assert!(!pattern.matches("https://example.com"));
+        assert!(!pattern.matches("https://evilexample.com"));
+        assert!(!pattern.matches("https://example.com.evil.org"));
+        assert!(!pattern.matches("http://app.example.com"));
+        assert!(!pattern.matches("https://app.example.com:8443"));
+        assert!(!pattern.matches("null"));
+
+        let exact: OriginPattern = "http://localhost:5173".parse().unwrap();
+        assert!(exact.matches("http://LOCALHOST:5173"));
+        assert!(!exact.matches("http://localhost:5174"));
+    }
+
+    #[test]
+    fn access_comes_from_the_route_table() {
+        let policy = policy(&["*"], &["https://app.example.com"]);
+        let anyone = "https://elsewhere.example.org";
+
+        assert!(allowed(
+            &policy,
+            anyone,
+            TestRequest::get().uri("/anttp-0/chunk/ab")
+        ));
+        assert!(!allowed(
+            &policy,
+            anyone,
+            TestRequest::post().uri("/anttp-0/chunk")
+        ));
+        // POST, but only compares two archives
+        assert!(allowed(
+            &policy,
+            anyone,
+            TestRequest::post().uri("/anttp-0/public_archive_diff/ab")
+        ));
+        assert!(allowed(
+            &policy,
+            "https://app.example.com",
+            TestRequest::post().uri("/anttp-0/chunk")
+        ));
+    }
+
+    #[test]
+    fn preflights_are_judged_by_the_method_that_follows() {
+        let policy = policy(&["*"], &[]);
+        let preflight = |method: &str| {
+            TestRequest::default()
+                .method(Method::OPTIONS)
+                .uri("/anttp-0/key_value/notes/todo.txt")
+                .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+        };
+        assert!(allowed(&policy, "https://a.example", preflight("GET")));
+        assert!(!allowed(&policy, "https://a.example", preflight("DELETE")));
+    }
+
+    #[test]
+    fn archive_pages_on_this_server_cannot_write() {
+        // An archive site is served from http://localhost:18888 like everything else
+        let policy = policy(&[], &[]);
+        let ours = "http://localhost:18888";
+
+        assert!(!allowed(
+            &policy,
+            ours,
+            TestRequest::post().uri("/anttp-0/chunk")
+        ));
+        assert!(!allowed(
+            &policy,
+            ours,
+            TestRequest::delete().uri("/anttp-0/key_value/notes/todo.txt")
+        ));
+        assert!(!allowed(
+            &policy,
+            ours,
+            TestRequest::get().uri("/anttp-0/chunk/ab")
+        ));
+        // ...but the first-party pages still work
+        assert!(allowed(
+            &policy,
+            ours,
+            TestRequest::get().uri("/openapi.json")
+        ));
+        assert!(allowed(&policy, ours, TestRequest::get().uri("/docs")));
+        // (the scheme counts too)
+        assert!(!allowed(
+            &policy,
+            "https://localhost:18888",
+            TestRequest::get().uri("/docs")
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn refused_writes_never_reach_a_handler() {
+        let config = Config::default();
+        let app = test::init_service(
+            App::new()
+                .wrap(cors(&config))
+                .configure(|cfg| crate::routes::configure(cfg, &config)),
+        )
+        .await;
+        let req = TestRequest::post()
+            .uri("/anttp-0/chunk")
+            .insert_header((header::HOST, "localhost:18888"))
+            .insert_header((header::ORIGIN, "http://localhost:18888"))
+            .set_payload("data")
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
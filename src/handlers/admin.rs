@@ -0,0 +1,135 @@
+// src/handlers/admin.rs
+//! Admin handlers - Operations on the server itself
+//!
+//! For 1st Year CS Students:
+//! These endpoints act on the machine running the server (like reading
+//! a folder from its disk), so they are switched off unless configured.
+//! `/anttp-0/admin/config` shows what the server was started with, and
+//! `/anttp-0/admin/token` hands out API tokens. With `[auth]` enabled,
+//! all of them need a token with the `admin` scope.
+
+use actix_web::{web, HttpResponse};
+
+use super::context::RequestContext;
+use crate::config::Config;
+use crate::models::{ErrorCode, ErrorResponse, PublishRequest, TokenListResponse, TokenRequest};
+use crate::services::publisher::{collect_directory, publish_collected};
+use crate::services::{NetworkService, PublishConfig, ServiceError, TokenStore};
+
+/// POST /anttp-0/admin/publish_directory - Publish a server-side directory
+///
+/// For Students:
+/// Instead of uploading files one by one, name a folder on the server
+/// (inside PUBLISH_ROOT) and it becomes an archive:
+/// `{"path": "my-site", "ignore": [".git", "*.tmp"], "pnr": "my-site"}`
+pub async fn publish_directory(
+    ctx: RequestContext,
+    body: web::Json<PublishRequest>,
+    network: web::Data<NetworkService>,
+    config: web::Data<Option<PublishConfig>>,
+) -> HttpResponse {
+    let Some(walk_config) = config.get_ref().clone() else {
+        return HttpResponse::Forbidden().json(ErrorResponse::new(
+            ErrorCode::Forbidden,
+            "Directory publishing is disabled (set publish.root or PUBLISH_ROOT to enable)",
+        ));
+    };
+    let request = body.into_inner();
+    log::info!("📤 Publishing directory: {}", request.path);
+
+    let use_network = ctx.use_network();
+
+    // Walking the disk blocks, so keep it off the async workers
+    let walk_request = request.clone();
+    let collected = web::block(move || {
+        collect_directory(
+            &walk_config,
+            &walk_request.path,
+            &walk_request.ignore,
+            walk_request.symlinks,
+        )
+    })
+    .await;
+    let collected = match collected {
+        Ok(Ok(collected)) => collected,
+        Ok(Err(e)) => {
+            log::error!("❌ Failed to read directory: {}", e);
+            return ServiceError::from(e).response("Failed to read directory");
+        }
+        Err(e) => {
+            log::error!("❌ Directory walk failed: {}", e);
+            return ServiceError::Internal(e.to_string()).response("Failed to read directory");
+        }
+    };
+
+    match publish_collected(&network, &request, collected, ctx.owner_key(), use_network).await {
+        Ok(response) => {
+            log::info!("✅ Directory published: {}", response.address);
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            log::error!("❌ Failed to publish directory: {}", e);
+            ServiceError::from(e).response("Failed to publish directory")
+        }
+    }
+}
+
+/// GET /anttp-0/admin/config - The effective configuration
+///
+/// For Students:
+/// Defaults, config file, environment and command line merged - what the
+/// server is really running with. Secrets (S3 secret keys) are replaced
+/// by "********", so the answer is safe to paste into a bug report.
+pub async fn get_config(config: web::Data<Config>) -> HttpResponse {
+    HttpResponse::Ok().json(config.redacted())
+}
+
+/// POST /anttp-0/admin/token - Issue an API token
+///
+/// For Students:
+/// `{"name": "deploy-bot", "scopes": ["write:archive"], "expires_in_seconds": 86400}`
+/// The answer holds the token itself. Only its hash is kept, so save it:
+/// it can't be shown again.
+pub async fn issue_token(
+    body: web::Json<TokenRequest>,
+    tokens: web::Data<TokenStore>,
+) -> HttpResponse {
+    match tokens.issue(body.into_inner()) {
+        Ok(issued) => {
+            log::info!(
+                "🔑 Token issued: {} ({})",
+                issued.info.name,
+                issued.info.scopes.join(" ")
+            );
+            HttpResponse::Ok().json(issued)
+        }
+        Err(e) => {
+            log::error!("❌ Failed to issue token: {}", e);
+            ServiceError::from(e).response("Failed to issue token")
+        }
+    }
+}
+
+/// GET /anttp-0/admin/token - Every token the server accepts (without secrets)
+pub async fn list_tokens(tokens: web::Data<TokenStore>) -> HttpResponse {
+    HttpResponse::Ok().json(TokenListResponse {
+        tokens: tokens.list(),
+    })
+}
+
+/// DELETE /anttp-0/admin/token/{id} - Revoke an issued token
+///
+/// Tokens from the config can't be revoked here - change the config.
+pub async fn revoke_token(path: web::Path<String>, tokens: web::Data<TokenStore>) -> HttpResponse {
+    let id = path.into_inner();
+    match tokens.revoke(&id) {
+        Ok(revoked) => {
+            log::info!("🗑️ Token revoked: {} ({})", revoked.name, revoked.id);
+            HttpResponse::Ok().json(revoked)
+        }
+        Err(e) => {
+            log::error!("❌ Failed to revoke token: {}", e);
+            ServiceError::from(e).response("Failed to revoke token")
+        }
+    }
+}
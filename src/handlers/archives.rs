@@ -8,177 +8,361 @@
 //! - Like sending a folder through email
 
 use actix_multipart::Multipart;
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
 use bytes::Bytes;
 use futures::StreamExt;
 use std::path::PathBuf;
 
-use crate::models::{ArchiveFile, ArchiveResponse, ErrorResponse, StoreType};
-use crate::services::NetworkService;
+use super::auth::authorize;
+use super::context::RequestContext;
+use crate::config::Config;
+use crate::models::{
+    ArchiveDownloadQuery, ArchiveEncoding, ArchiveEntry, ArchiveFile, ArchiveManifestRequest,
+    ArchivePatchRequest, ArchivePathChange, ArchiveUpdateQuery, ArchiveUpdateResponse,
+    ArchiveUploadResponse, BundleFormat, ErrorCode, ErrorResponse, ZipEntryResult, ZipEntryStatus,
+    ZipUploadResponse,
+};
+use crate::services::app_config::{AppRoute, AppRoutes, APP_CONFIG_FILE};
+use crate::services::bundle::{read_zip, stream_bundle, ZipImportOutcome};
+use crate::services::{
+    diff_archive, plan_archive_sync, Access, ArchiveChange, NetworkService, ServiceError,
+};
 
 /// POST /anttp-0/multipart/public_archive - Create archive
 ///
 /// For Students:
 /// Upload multiple files as a single archive
 /// Uses multipart/form-data (like uploading files in a web form)
+///
+/// Folders are kept: a file sent as `css/site.css` (in its filename, as
+/// browsers do for folder uploads, or in its field name) lands at
+/// `css/site.css` in the archive.
 pub async fn create_archive(
-    req: HttpRequest,
-    mut payload: Multipart,
+    ctx: RequestContext,
+    payload: Multipart,
     network: web::Data<NetworkService>,
+    config: web::Data<Config>,
 ) -> HttpResponse {
     log::info!("📦 Creating archive from multipart upload");
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
-    // Parse multipart form data
-    let mut files = Vec::new();
+    let max_bytes = config.limits.multipart_body_bytes;
+    let files = match read_archive_upload(payload, None, max_bytes).await {
+        Ok(files) => files,
+        Err(response) => return response,
+    };
+    store_uploaded_archive(&network, files, use_network).await
+}
 
-    while let Some(item) = payload.next().await {
-        let mut field = match item {
-            Ok(field) => field,
-            Err(e) => {
-                log::error!("❌ Failed to read multipart field: {}", e);
-                return HttpResponse::BadRequest()
-                    .json(ErrorResponse::new(format!("Invalid multipart data: {}", e)));
-            }
-        };
+/// POST /anttp-0/multipart/public_archive/{path:.*} - Create archive with path
+///
+/// For Students:
+/// Create archive at a specific path (like creating a subfolder)
+pub async fn create_archive_with_path(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    payload: Multipart,
+    network: web::Data<NetworkService>,
+    config: web::Data<Config>,
+) -> HttpResponse {
+    let archive_path = path.into_inner();
+    log::info!("📦 Creating archive at path: {}", archive_path);
 
-        // Get filename from Content-Disposition
-        let filename = field
-            .content_disposition()
-            .get_filename()
-            .unwrap_or("unnamed")
-            .to_string();
+    let use_network = ctx.use_network();
 
-        log::info!("  📄 Reading file: {}", filename);
+    let base = match normalize_archive_path(&archive_path) {
+        Ok(base) => base,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::with_details(
+                ErrorCode::InvalidInput,
+                "Invalid archive path",
+                message,
+            ))
+        }
+    };
+    let max_bytes = config.limits.multipart_body_bytes;
+    let files = match read_archive_upload(payload, Some(&base), max_bytes).await {
+        Ok(files) => files,
+        Err(response) => return response,
+    };
+    store_uploaded_archive(&network, files, use_network).await
+}
 
-        // Read file content
-        let mut content = Vec::new();
-        while let Some(chunk) = field.next().await {
-            let chunk = match chunk {
-                Ok(data) => data,
-                Err(e) => {
-                    log::error!("❌ Failed to read chunk: {}", e);
-                    return HttpResponse::BadRequest().json(ErrorResponse::new(format!(
-                        "Failed to read file data: {}",
-                        e
-                    )));
-                }
-            };
-            content.extend_from_slice(&chunk);
+/// POST /anttp-0/zip/public_archive - Create archive from a zip file
+///
+/// For Students:
+/// Send a .zip as the request body:
+/// `curl --data-binary @site.zip -H "Content-Type: application/zip" ...`
+/// Every entry is checked first (zip bombs, "../" paths, duplicates); if
+/// any is rejected nothing is stored and the answer says which and why.
+pub async fn create_archive_from_zip(
+    ctx: RequestContext,
+    body: web::Bytes,
+    network: web::Data<NetworkService>,
+    config: web::Data<Config>,
+) -> HttpResponse {
+    log::info!("📦 Creating archive from zip ({} bytes)", body.len());
+
+    let use_network = ctx.use_network();
+
+    if body.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Empty body - send the zip file as the request body",
+        ));
+    }
+
+    // Decompressing is CPU work, so keep it off the async workers
+    let max_total = config.limits.zip_expanded_bytes as u64;
+    let entries = match web::block(move || read_zip(body, max_total)).await {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            log::error!("❌ Zip rejected: {}", e);
+            return ServiceError::from(e).response("Zip rejected");
+        }
+        Err(e) => {
+            log::error!("❌ Zip reading failed: {}", e);
+            return ServiceError::Internal(e.to_string()).response("Failed to read zip");
         }
+    };
 
-        files.push((PathBuf::from(filename), Bytes::from(content)));
+    let mut results = Vec::with_capacity(entries.len());
+    let mut files = Vec::new();
+    for entry in entries {
+        let (status, path, size, reason) = match entry.outcome {
+            ZipImportOutcome::File { content, modified } => {
+                match normalize_archive_path(&entry.name) {
+                    Ok(path) if !path.is_empty() => {
+                        let size = content.len() as u64;
+                        files.push((path.clone(), content, modified));
+                        (ZipEntryStatus::Stored, Some(path), size, None)
+                    }
+                    Ok(_) => (ZipEntryStatus::Rejected, None, 0, Some("Empty path".to_string())),
+                    Err(message) => (ZipEntryStatus::Rejected, None, 0, Some(message)),
+                }
+            }
+            ZipImportOutcome::Skipped(reason) => (ZipEntryStatus::Skipped, None, 0, Some(reason)),
+            ZipImportOutcome::Rejected(reason) => {
+                (ZipEntryStatus::Rejected, None, 0, Some(reason))
+            }
+        };
+        results.push(ZipEntryResult {
+            name: entry.name,
+            path,
+            status,
+            size,
+            reason,
+        });
     }
 
-    if files.is_empty() {
-        log::error!("❌ No files in archive");
-        return HttpResponse::BadRequest().json(ErrorResponse::new("No files provided"));
+    let clashes = conflicting_paths(files.iter().map(|(path, _, _)| path.as_str()).collect());
+    for result in &mut results {
+        let clashing = result.path.as_deref().is_some_and(|path| {
+            clashes
+                .iter()
+                .any(|clash| path == clash || path.starts_with(&format!("{}/", clash)))
+        });
+        if clashing {
+            result.status = ZipEntryStatus::Rejected;
+            result.reason = Some("Duplicate or conflicting path".to_string());
+        }
     }
 
-    log::info!("📦 Archive contains {} files", files.len());
+    let rejected = results
+        .iter()
+        .filter(|result| result.status == ZipEntryStatus::Rejected)
+        .count();
+    let error = if rejected > 0 {
+        Some(format!("{} entries rejected - nothing was stored", rejected))
+    } else if files.is_empty() {
+        Some("Zip contains no files".to_string())
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        log::error!("❌ {}", error);
+        for result in &mut results {
+            if result.status == ZipEntryStatus::Stored {
+                result.status = ZipEntryStatus::Skipped;
+                result.reason = Some("Not stored - other entries were rejected".to_string());
+            }
+        }
+        return HttpResponse::BadRequest().json(ZipUploadResponse {
+            address: None,
+            error: Some(error),
+            entries: results,
+        });
+    }
 
-    // Store archive
+    let files = files
+        .into_iter()
+        .map(|(path, content, modified)| (PathBuf::from(path), content, modified))
+        .collect();
     match network.store_archive(files, use_network).await {
         Ok(address) => {
-            log::info!("✅ Archive created: {}", address);
-            HttpResponse::Ok().json(ArchiveResponse { address })
+            log::info!("✅ Archive created from zip: {}", address);
+            HttpResponse::Ok().json(ZipUploadResponse {
+                address: Some(address),
+                error: None,
+                entries: results,
+            })
         }
         Err(e) => {
             log::error!("❌ Failed to create archive: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create archive", e.to_string()))
+            ServiceError::from(e).response("Failed to create archive")
         }
     }
 }
 
-/// POST /anttp-0/multipart/public_archive/{path:.*} - Create archive with path
+/// PUT /anttp-0/multipart/public_archive/{address} - Add or replace files
 ///
 /// For Students:
-/// Create archive at a specific path (like creating a subfolder)
-pub async fn create_archive_with_path(
+/// Archives never change, so this makes a *new* archive: the old files
+/// plus the uploaded ones (same path = replaced). Only the uploaded files
+/// are stored again - everything else reuses its existing chunk.
+/// Add `?pointer=<address>` to move a pointer to the new version (the
+/// token then needs `write:pointer` as well).
+pub async fn update_archive_files(
     req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
-    mut payload: Multipart,
+    query: web::Query<ArchiveUpdateQuery>,
+    payload: Multipart,
     network: web::Data<NetworkService>,
+    config: web::Data<Config>,
 ) -> HttpResponse {
-    let archive_path = path.into_inner();
-    log::info!("📦 Creating archive at path: {}", archive_path);
-
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
-
-    // Parse multipart form data
-    let mut files = Vec::new();
-
-    while let Some(item) = payload.next().await {
-        let mut field = match item {
-            Ok(field) => field,
-            Err(e) => {
-                return HttpResponse::BadRequest()
-                    .json(ErrorResponse::new(format!("Invalid multipart data: {}", e)));
-            }
-        };
-
-        let filename = field
-            .content_disposition()
-            .get_filename()
-            .unwrap_or("unnamed")
-            .to_string();
-
-        // Prepend the archive path to filename
-        let full_path = PathBuf::from(&archive_path).join(&filename);
+    let address = path.into_inner();
+    log::info!("📦 Updating files in archive: {}", address);
+    if let Err(e) = authorize_pointer(&req, &query) {
+        log::warn!("🔒 {}", e);
+        return e.error_response();
+    }
 
-        let mut content = Vec::new();
-        while let Some(chunk) = field.next().await {
-            let chunk = match chunk {
-                Ok(data) => data,
-                Err(e) => {
-                    return HttpResponse::BadRequest().json(ErrorResponse::new(format!(
-                        "Failed to read file data: {}",
-                        e
-                    )));
-                }
-            };
-            content.extend_from_slice(&chunk);
-        }
+    let max_bytes = config.limits.multipart_body_bytes;
+    let files = match read_archive_upload(payload, None, max_bytes).await {
+        Ok(files) => files,
+        Err(response) => return response,
+    };
+    let changes = files
+        .into_iter()
+        .map(|(path, content)| ArchiveChange::Put {
+            path,
+            content,
+            modified: None,
+        })
+        .collect();
+    apply_archive_changes(
+        &network,
+        &address,
+        changes,
+        query.into_inner().pointer,
+        &ctx,
+    )
+    .await
+}
 
-        files.push((full_path, Bytes::from(content)));
+/// PATCH /anttp-0/public_archive/{address} - Delete or rename paths
+///
+/// For Students:
+/// Like the PUT above, this makes a new archive. Send
+/// `{"changes": [{"op": "delete", "path": "old.html"},
+///               {"op": "rename", "from": "blog", "to": "posts"}]}`
+pub async fn patch_archive(
+    req: HttpRequest,
+    ctx: RequestContext,
+    path: web::Path<String>,
+    query: web::Query<ArchiveUpdateQuery>,
+    body: web::Json<ArchivePatchRequest>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let address = path.into_inner();
+    log::info!("📦 Patching archive: {} ({} changes)", address, body.changes.len());
+    if let Err(e) = authorize_pointer(&req, &query) {
+        log::warn!("🔒 {}", e);
+        return e.error_response();
     }
 
-    if files.is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse::new("No files provided"));
+    if body.changes.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "No changes provided",
+        ));
     }
-
-    // Store archive
-    match network.store_archive(files, use_network).await {
-        Ok(address) => {
-            log::info!("✅ Archive created at path: {}", address);
-            HttpResponse::Ok().json(ArchiveResponse { address })
-        }
-        Err(e) => {
-            log::error!("❌ Failed to create archive: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create archive", e.to_string()))
+    let mut changes = Vec::with_capacity(body.changes.len());
+    for change in &body.changes {
+        let change = match change {
+            ArchivePathChange::Delete { path } => {
+                normalize_change_path(path).map(|path| ArchiveChange::Delete { path })
+            }
+            ArchivePathChange::Rename { from, to } => normalize_change_path(from)
+                .and_then(|from| Ok((from, normalize_change_path(to)?)))
+                .map(|(from, to)| ArchiveChange::Rename { from, to }),
+        };
+        match change {
+            Ok(change) => changes.push(change),
+            Err(message) => {
+                return HttpResponse::BadRequest().json(ErrorResponse::with_details(
+                    ErrorCode::InvalidInput,
+                    "Invalid file path",
+                    message,
+                ))
+            }
         }
     }
+    apply_archive_changes(
+        &network,
+        &address,
+        changes,
+        query.into_inner().pointer,
+        &ctx,
+    )
+    .await
 }
 
 /// GET /anttp-0/public_archive/{address} - Get archive root
 ///
 /// For Students:
-/// Lists all files in the archive (like "ls" command)
+/// Lists all files in the archive (like "ls" command).
+/// Add `?format=zip` (or `tar`, `tar.gz`) to download everything as one
+/// file instead, and `?prefix=css` to only get one folder.
 pub async fn get_archive_root(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
+    query: web::Query<ArchiveDownloadQuery>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let address = path.into_inner();
     log::info!("📖 Getting archive: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let prefix = match normalize_archive_path(query.prefix.as_deref().unwrap_or("")) {
+        Ok(prefix) => prefix,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::with_details(
+                ErrorCode::InvalidInput,
+                "Invalid prefix",
+                message,
+            ))
+        }
+    };
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve archive address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
+
+    if let Some(format) = query.format {
+        return download_archive(network, address, prefix, format, use_network).await;
+    }
 
     match network.get_archive(&address, use_network).await {
         Ok(files) => {
@@ -191,6 +375,7 @@ pub async fn get_archive_root(
                     path: path.to_string_lossy().to_string(),
                     content: content.to_vec(),
                 })
+                .filter(|file| in_folder(&file.path, &prefix))
                 .collect();
 
             HttpResponse::Ok().json(serde_json::json!({
@@ -200,56 +385,759 @@ pub async fn get_archive_root(
         }
         Err(e) => {
             log::error!("❌ Archive not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Archive not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get archive")
+        }
+    }
+}
+
+/// Helper: Stream (part of) an archive as a zip/tar/tar.gz download
+async fn download_archive(
+    network: web::Data<NetworkService>,
+    address: String,
+    prefix: String,
+    format: BundleFormat,
+    use_network: bool,
+) -> HttpResponse {
+    let entries: Vec<ArchiveEntry> = match network.get_archive_manifest(&address, use_network).await
+    {
+        Ok(entries) => entries
+            .into_iter()
+            .filter(|entry| in_folder(&entry.path, &prefix))
+            .collect(),
+        Err(e) => {
+            log::error!("❌ Archive not found: {}", e);
+            return ServiceError::from(e).response("Failed to get archive");
         }
+    };
+    if entries.is_empty() {
+        return HttpResponse::NotFound().json(ErrorResponse::new(
+            ErrorCode::NotFound,
+            format!("No files under prefix: {}", prefix),
+        ));
     }
+
+    log::info!(
+        "📦 Streaming {} files from {} as {}",
+        entries.len(),
+        address,
+        format.extension()
+    );
+    let filename = match prefix.rsplit('/').next().filter(|name| !name.is_empty()) {
+        Some(folder) => format!("{}-{}.{}", address, folder, format.extension()),
+        None => format!("{}.{}", address, format.extension()),
+    };
+    let mut response = HttpResponse::Ok();
+    if format != BundleFormat::Tar {
+        // zip and tar.gz are already compressed - don't let Compress try again
+        response.insert_header(header::ContentEncoding::Identity);
+    }
+    response
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .streaming(stream_bundle(
+            network.into_inner(),
+            entries,
+            format,
+            use_network,
+        ))
+}
+
+/// Helper: Is `path` inside `folder`? (everything is inside "")
+fn in_folder(path: &str, folder: &str) -> bool {
+    folder.is_empty()
+        || path
+            .strip_prefix(folder)
+            .is_some_and(|rest| rest.starts_with('/'))
 }
 
 /// GET /anttp-0/public_archive/{address}/{path:.*} - Get specific file from archive
 ///
 /// For Students:
-/// Gets one specific file from the archive
+/// Gets one specific file from the archive. If the archive has an
+/// `app-conf.json` at its root, its redirects, rewrites, headers and
+/// error pages decide what is actually sent (see `AppConfig`). Files get
+/// the type of their extension, in a `sandbox` so a page can't act as
+/// this server's own.
 pub async fn get_archive_file(
     req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<(String, String)>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
-    let (address, file_path) = path.into_inner();
-    log::info!("📖 Getting file from archive: {}/{}", address, file_path);
+    let (requested, file_path) = path.into_inner();
+    log::info!("📖 Getting file from archive: {}/{}", requested, file_path);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
-    match network.get_archive(&address, use_network).await {
-        Ok(files) => {
-            // Find the requested file
-            for (path, content) in files {
-                if path.to_string_lossy() == file_path {
-                    log::info!("✅ File found ({} bytes)", content.len());
-                    return HttpResponse::Ok()
-                        .content_type("application/octet-stream")
-                        .body(content);
+    let address = match network
+        .resolve_address(&requested, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve archive address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
+
+    // The manifest says which chunk holds the file - only that one is fetched
+    let entries = match network.get_archive_manifest(&address, use_network).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!("❌ Archive not found: {}", e);
+            return ServiceError::from(e).response("Failed to get archive");
+        }
+    };
+    let routes = match load_app_routes(&network, &entries, use_network).await {
+        Ok(routes) => routes,
+        Err(e) => {
+            log::error!("❌ {}", e);
+            return ServiceError::from(e).response("Invalid app config");
+        }
+    };
+
+    let route = match &routes {
+        Some(routes) => routes.resolve(&file_path, |candidate| {
+            entries.iter().any(|entry| entry.path == candidate)
+        }),
+        None if entries.iter().any(|entry| entry.path == file_path) => AppRoute::File {
+            path: file_path.clone(),
+            status: 200,
+        },
+        None => AppRoute::NotFound,
+    };
+    // Header rules match the file that is sent (or the path, for redirects)
+    let headers_path = match &route {
+        AppRoute::File { path, .. } => path.as_str(),
+        _ => file_path.as_str(),
+    };
+    let extra_headers = routes
+        .as_ref()
+        .map(|routes| routes.headers_for(headers_path))
+        .unwrap_or_default();
+
+    let (served_path, status) = match route {
+        AppRoute::File { path, status } => (path.clone(), status),
+        AppRoute::Redirect { location, status } => {
+            // Archive paths redirect within the same archive (and name)
+            let location = match location.strip_prefix('/') {
+                Some(target) => format!("/anttp-0/public_archive/{}/{}", requested, target),
+                None => location,
+            };
+            log::info!("↪️ Redirecting {} → {} ({})", file_path, location, status);
+            let mut response = HttpResponse::build(http_status(status));
+            response.insert_header(("Location", location));
+            for header in extra_headers {
+                response.insert_header(header);
+            }
+            return response.finish();
+        }
+        AppRoute::NotFound => {
+            log::error!("❌ File not found in archive: {}", file_path);
+            return HttpResponse::NotFound().json(ErrorResponse::new(
+                ErrorCode::NotFound,
+                format!("File not found: {}", file_path),
+            ));
+        }
+    };
+    if served_path != file_path {
+        log::info!("🔀 Serving {} for {} ({})", served_path, file_path, status);
+    }
+
+    // A stored compressed copy is sent as-is (the Compress middleware skips it)
+    let entry = entries.iter().find(|entry| entry.path == served_path);
+    let encodings = entry.map_or(&[][..], |entry| entry.encodings.as_slice());
+    let variant = preferred_encoding(&req, encodings);
+    let content = match variant {
+        Some(variant) => network.get_chunk(&variant.address, use_network).await,
+        None => read_archive_entry(&network, &entries, &served_path, use_network).await,
+    };
+    let content = match content {
+        Ok(content) => content,
+        Err(e) => {
+            log::error!("❌ Archive file content missing: {}", e);
+            // The site's own 500 page, if it has one that can be read
+            let page = routes.as_ref().and_then(|routes| routes.error_page(500));
+            let content = match page {
+                Some(page) => read_archive_entry(&network, &entries, page, use_network)
+                    .await
+                    .ok()
+                    .map(|content| (page, content)),
+                None => None,
+            };
+            return match content {
+                Some((page, content)) => file_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    page,
+                    content,
+                    &extra_headers,
+                ),
+                None => ServiceError::from(e).response("Failed to read archive file"),
+            };
+        }
+    };
+    log::info!("✅ File found ({} bytes)", content.len());
+    let mut response = file_response(http_status(status), &served_path, content, &extra_headers);
+    if !encodings.is_empty() {
+        response.headers_mut().insert(
+            header::VARY,
+            header::HeaderValue::from_static("Accept-Encoding"),
+        );
+    }
+    if let Some(variant) = variant {
+        if let Ok(value) = header::HeaderValue::from_str(&variant.encoding) {
+            response.headers_mut().insert(header::CONTENT_ENCODING, value);
+        }
+    }
+    response
+}
+
+/// Helper: The best precompressed copy the client accepts (by Accept-Encoding)
+///
+/// `encodings` is ordered best first; "gzip;q=0" or "*;q=0" turn one off.
+fn preferred_encoding<'a>(
+    req: &HttpRequest,
+    encodings: &'a [ArchiveEncoding],
+) -> Option<&'a ArchiveEncoding> {
+    let accepted = req.headers().get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+    let quality = |name: &str| {
+        accepted.split(',').find_map(|item| {
+            let mut parts = item.split(';');
+            let coding = parts.next()?.trim();
+            if !coding.eq_ignore_ascii_case(name) {
+                return None;
+            }
+            let q = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some(q)
+        })
+    };
+    encodings.iter().find(|variant| {
+        quality(&variant.encoding)
+            .or_else(|| quality("*"))
+            .is_some_and(|q| q > 0.0)
+    })
+}
+
+/// Helper: The archive's app-conf.json rules, if it has one
+async fn load_app_routes(
+    network: &NetworkService,
+    entries: &[ArchiveEntry],
+    use_network: bool,
+) -> anyhow::Result<Option<AppRoutes>> {
+    if !entries.iter().any(|entry| entry.path == APP_CONFIG_FILE) {
+        return Ok(None);
+    }
+    let content = read_archive_entry(network, entries, APP_CONFIG_FILE, use_network).await?;
+    Ok(Some(AppRoutes::parse(&content)?))
+}
+
+/// Helper: Content of one file listed in a manifest
+async fn read_archive_entry(
+    network: &NetworkService,
+    entries: &[ArchiveEntry],
+    path: &str,
+    use_network: bool,
+) -> anyhow::Result<Bytes> {
+    let entry = entries
+        .iter()
+        .find(|entry| entry.path == path)
+        .ok_or_else(|| anyhow::anyhow!("File not found: {}", path))?;
+    network.get_chunk(&entry.address, use_network).await
+}
+
+/// Sandbox for every archive file: pages may run their own scripts, but in an
+/// opaque origin, so they can't reach this server's storage or act as its pages
+const ARCHIVE_SANDBOX: &str = "sandbox allow-scripts allow-forms allow-popups";
+
+/// Helper: A file's bytes plus any app config headers
+///
+/// The type comes from the file's extension. Anyone can publish an archive,
+/// so the `sandbox` policy is added on top of the site's own (browsers
+/// enforce every `Content-Security-Policy` header they get).
+fn file_response(
+    status: StatusCode,
+    path: &str,
+    content: Bytes,
+    headers: &[(&str, &str)],
+) -> HttpResponse {
+    let mut response = HttpResponse::build(status);
+    response.content_type(mime_guess::from_path(path).first_or_octet_stream().as_ref());
+    // Browsers must not guess a type of their own
+    response.insert_header(("X-Content-Type-Options", "nosniff"));
+    for header in headers {
+        response.insert_header(*header);
+    }
+    response.append_header((header::CONTENT_SECURITY_POLICY, ARCHIVE_SANDBOX));
+    response.body(content)
+}
+
+/// Helper: `?pointer=` moves a pointer, so the token needs pointer write access too
+fn authorize_pointer(req: &HttpRequest, query: &ArchiveUpdateQuery) -> Result<(), ServiceError> {
+    match query.pointer {
+        Some(_) => authorize(req, Access::Write, "pointer", None),
+        None => Ok(()),
+    }
+}
+
+/// Helper: Status codes in an app config were checked when it was parsed
+fn http_status(status: u16) -> StatusCode {
+    StatusCode::from_u16(status).unwrap_or(StatusCode::OK)
+}
+
+/// Helper: Read every file of a multipart upload, with checked relative paths
+///
+/// Each file goes under `base` (already normalised) if given. Duplicate
+/// paths, or a path used as both file and folder, reject the whole upload.
+async fn read_archive_upload(
+    mut payload: Multipart,
+    base: Option<&str>,
+    max_bytes: usize,
+) -> Result<Vec<(String, Bytes)>, HttpResponse> {
+    let mut files: Vec<(String, Bytes)> = Vec::new();
+    let mut total: usize = 0;
+
+    while let Some(item) = payload.next().await {
+        let mut field = match item {
+            Ok(field) => field,
+            Err(e) => {
+                log::error!("❌ Failed to read multipart field: {}", e);
+                return Err(HttpResponse::BadRequest().json(ErrorResponse::new(
+                    ErrorCode::InvalidInput,
+                    format!("Invalid multipart data: {}", e),
+                )));
+            }
+        };
+
+        let raw_path = upload_path(&field);
+        let path = match raw_path.as_deref().map(normalize_archive_path) {
+            Some(Ok(path)) if !path.is_empty() => path,
+            Some(Err(message)) => {
+                log::error!("❌ Rejected upload path: {}", message);
+                return Err(HttpResponse::BadRequest().json(ErrorResponse::with_details(
+                    ErrorCode::InvalidInput,
+                    "Invalid file path",
+                    message,
+                )));
+            }
+            _ => {
+                return Err(HttpResponse::BadRequest().json(ErrorResponse::new(
+                    ErrorCode::InvalidInput,
+                    format!("Field '{}' has no filename", field.name()),
+                )))
+            }
+        };
+        let path = match base {
+            Some(base) if !base.is_empty() => format!("{}/{}", base, path),
+            _ => path,
+        };
+
+        log::info!("  📄 Reading file: {}", path);
+
+        // Read file content
+        let mut content = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = match chunk {
+                Ok(data) => data,
+                Err(e) => {
+                    log::error!("❌ Failed to read chunk: {}", e);
+                    return Err(HttpResponse::BadRequest().json(ErrorResponse::new(
+                        ErrorCode::InvalidInput,
+                        format!("Failed to read file data: {}", e),
+                    )));
                 }
+            };
+            // (actix-multipart ignores PayloadConfig, so the limit is counted here)
+            total = total.saturating_add(chunk.len());
+            if total > max_bytes {
+                log::error!("❌ Upload is over {} bytes", max_bytes);
+                return Err(ServiceError::PayloadTooLarge(format!(
+                    "Upload is larger than {} bytes (limits.multipart_body_bytes)",
+                    max_bytes
+                ))
+                .error_response());
             }
+            content.extend_from_slice(&chunk);
+        }
 
-            log::error!("❌ File not found in archive: {}", file_path);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::new(format!("File not found: {}", file_path)))
+        files.push((path, Bytes::from(content)));
+    }
+
+    if files.is_empty() {
+        log::error!("❌ No files in archive");
+        return Err(HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "No files provided",
+        )));
+    }
+
+    let clashes = conflicting_paths(files.iter().map(|(path, _)| path.as_str()).collect());
+    if !clashes.is_empty() {
+        log::error!("❌ Duplicate paths in upload: {:?}", clashes);
+        return Err(HttpResponse::BadRequest().json(ErrorResponse::with_details(
+            ErrorCode::InvalidInput,
+            "Duplicate or conflicting file paths",
+            clashes.join(", "),
+        )));
+    }
+
+    log::info!("📦 Archive contains {} files", files.len());
+    Ok(files)
+}
+
+/// Helper: Paths that appear twice, or as a file and a folder
+///
+/// "a" twice, or "a" next to "a/b.txt", can't both be in one archive.
+fn conflicting_paths(mut paths: Vec<&str>) -> Vec<String> {
+    paths.sort_unstable();
+    let mut clashes: Vec<String> = paths
+        .windows(2)
+        .filter(|pair| pair[1] == pair[0] || pair[1].starts_with(&format!("{}/", pair[0])))
+        .map(|pair| pair[0].to_string())
+        .collect();
+    clashes.dedup();
+    clashes
+}
+
+/// Helper: Store uploaded files and answer with the address and manifest
+async fn store_uploaded_archive(
+    network: &NetworkService,
+    files: Vec<(String, Bytes)>,
+    use_network: bool,
+) -> HttpResponse {
+    let files = files
+        .into_iter()
+        .map(|(path, content)| (PathBuf::from(path), content, None))
+        .collect();
+    let stored = match network.store_archive(files, use_network).await {
+        Ok(address) => network
+            .get_archive_manifest(&address, use_network)
+            .await
+            .map(|files| (address, files)),
+        Err(e) => Err(e),
+    };
+
+    match stored {
+        Ok((address, files)) => {
+            log::info!("✅ Archive created: {}", address);
+            HttpResponse::Ok().json(ArchiveUploadResponse { address, files })
         }
+        Err(e) => {
+            log::error!("❌ Failed to create archive: {}", e);
+            ServiceError::from(e).response("Failed to create archive")
+        }
+    }
+}
+
+/// GET /anttp-0/public_archive_diff/{from}/{to} - Compare two archives
+///
+/// For Students:
+/// Files are compared by their SHA-256 from the manifest, so nothing is
+/// downloaded - like `git diff --stat` between two versions of a site.
+pub async fn get_archive_diff(
+    ctx: RequestContext,
+    path: web::Path<(String, String)>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let (from, to) = path.into_inner();
+    log::info!("🔍 Diffing archives: {} → {}", from, to);
+
+    let (from, current) = match load_manifest(&network, &from, &ctx).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+    let (to, target) = match load_manifest(&network, &to, &ctx).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+    let desired: Vec<(String, String)> = target
+        .into_iter()
+        .map(|entry| (entry.path, entry.sha256))
+        .collect();
+
+    let mut diff = diff_archive(&from, &current, &desired);
+    diff.to = Some(to);
+    HttpResponse::Ok().json(diff)
+}
+
+/// POST /anttp-0/public_archive_diff/{address} - Compare an archive with local files
+///
+/// For Students:
+/// Send the SHA-256 of each local file (`sha256sum` output, as JSON):
+/// `{"files": [{"path": "index.html", "sha256": "9f86d0..."}]}`
+pub async fn diff_archive_with_manifest(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    body: web::Json<ArchiveManifestRequest>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let address = path.into_inner();
+    log::info!("🔍 Diffing archive {} against {} local files", address, body.files.len());
+
+    let desired = match desired_state(&body) {
+        Ok(desired) => desired,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::with_details(
+                ErrorCode::InvalidInput,
+                "Invalid manifest",
+                message,
+            ))
+        }
+    };
+    match load_manifest(&network, &address, &ctx).await {
+        Ok((address, current)) => {
+            HttpResponse::Ok().json(diff_archive(&address, &current, &desired))
+        }
+        Err(response) => response,
+    }
+}
+
+/// POST /anttp-0/public_archive_sync_plan/{address} - What to upload to reach a state
+///
+/// For Students:
+/// Same body as the diff. The answer says which files must be uploaded
+/// (PUT) and which deletes/renames to send first (PATCH) - files that
+/// only moved are renamed instead of uploaded again.
+pub async fn get_archive_sync_plan(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    body: web::Json<ArchiveManifestRequest>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let address = path.into_inner();
+    log::info!("🔍 Planning sync of archive {} ({} files wanted)", address, body.files.len());
+
+    let desired = match desired_state(&body) {
+        Ok(desired) => desired,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::with_details(
+                ErrorCode::InvalidInput,
+                "Invalid manifest",
+                message,
+            ))
+        }
+    };
+    match load_manifest(&network, &address, &ctx).await {
+        Ok((address, current)) => {
+            let plan = plan_archive_sync(&address, &current, &desired);
+            log::info!(
+                "✅ Sync plan: {} uploads, {} changes, {} unchanged",
+                plan.upload.len(),
+                plan.changes.len(),
+                plan.unchanged
+            );
+            HttpResponse::Ok().json(plan)
+        }
+        Err(response) => response,
+    }
+}
+
+/// Helper: Resolve an archive address or PNR name and read its manifest
+async fn load_manifest(
+    network: &NetworkService,
+    address: &str,
+    ctx: &RequestContext,
+) -> Result<(String, Vec<ArchiveEntry>), HttpResponse> {
+    let use_network = ctx.use_network();
+    let address = match network
+        .resolve_address(address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve archive address: {}", e);
+            return Err(ServiceError::from(e).response("Failed to resolve name"));
+        }
+    };
+    match network.get_archive_manifest(&address, use_network).await {
+        Ok(entries) => Ok((address, entries)),
         Err(e) => {
             log::error!("❌ Archive not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Archive not found", e.to_string()))
+            Err(ServiceError::from(e).response("Failed to get archive"))
+        }
+    }
+}
+
+/// Helper: Check a supplied manifest - clean paths, real hashes, no duplicates
+///
+/// Returns the problem as a message for a 400 response.
+fn desired_state(body: &ArchiveManifestRequest) -> Result<Vec<(String, String)>, String> {
+    let mut desired = Vec::with_capacity(body.files.len());
+    let mut seen = std::collections::HashSet::new();
+    for file in &body.files {
+        let path = normalize_change_path(&file.path)?;
+        if file.sha256.len() != 64 || !file.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "Invalid sha256 for {}: expected 64 hex characters",
+                file.path
+            ));
+        }
+        if !seen.insert(path.clone()) {
+            return Err(format!("Duplicate path in manifest: {}", path));
+        }
+        desired.push((path, file.sha256.to_lowercase()));
+    }
+    Ok(desired)
+}
+
+/// Helper: Make the new archive version and move the pointer to it
+async fn apply_archive_changes(
+    network: &NetworkService,
+    address: &str,
+    changes: Vec<ArchiveChange>,
+    pointer: Option<String>,
+    ctx: &RequestContext,
+) -> HttpResponse {
+    let use_network = ctx.use_network();
+    let previous = match network
+        .resolve_address(address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve archive address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
+    // Check the pointer first, so a typo doesn't leave an unannounced version
+    if let Some(pointer) = &pointer {
+        if let Err(e) = network.get_pointer(pointer, use_network).await {
+            return ServiceError::from(e).response("Failed to get pointer");
+        }
+    }
+
+    let (address, files) = match network.update_archive(&previous, changes, use_network).await {
+        Ok(updated) => updated,
+        Err(e) => {
+            log::error!("❌ Failed to update archive: {}", e);
+            return ServiceError::from(e).response("Failed to update archive");
+        }
+    };
+
+    if let Some(pointer) = &pointer {
+        if let Err(e) = network.set_pointer_target(pointer, &address, use_network).await {
+            log::error!("❌ Failed to move pointer: {}", e);
+            return ServiceError::from(e).response("Failed to move pointer");
+        }
+    }
+
+    log::info!("✅ Archive updated: {} → {}", previous, address);
+    HttpResponse::Ok().json(ArchiveUpdateResponse {
+        address,
+        previous,
+        files,
+        pointer,
+    })
+}
+
+/// Helper: A path named in a change must be a real, non-empty archive path
+fn normalize_change_path(raw: &str) -> Result<String, String> {
+    match normalize_archive_path(raw)? {
+        path if path.is_empty() => Err("Path must not be empty".to_string()),
+        path => Ok(path),
+    }
+}
+
+/// Helper: Where a multipart field wants to go in the archive
+///
+/// The filename wins (`filename*=` too), unless only the field name
+/// carries folders - e.g. `-F "css/site.css=@site.css"` with curl.
+fn upload_path(field: &actix_multipart::Field) -> Option<String> {
+    let disposition = field.content_disposition();
+    let filename = disposition.get_filename().map(str::to_string).or_else(|| {
+        disposition
+            .get_filename_ext()
+            .map(|ext| String::from_utf8_lossy(&ext.value).into_owned())
+    });
+    let name = field.name();
+    let has_folders = |path: &str| path.contains(['/', '\\']);
+
+    match filename {
+        Some(filename) if has_folders(&filename) => Some(filename),
+        Some(filename) if has_folders(name) && name.ends_with(filename.as_str()) => {
+            Some(name.to_string())
         }
+        Some(filename) => Some(filename),
+        None if !name.is_empty() => Some(name.to_string()),
+        None => None,
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
+/// Helper: Clean up a path from a client, refusing ones that escape the archive
+///
+/// Backslashes become "/", empty and "." segments are dropped; "..",
+/// absolute paths and control characters are errors.
+fn normalize_archive_path(raw: &str) -> Result<String, String> {
+    let path = raw.replace('\\', "/");
+    if path.starts_with('/') || path.split_once(':').is_some_and(|(drive, _)| drive.len() == 1) {
+        return Err(format!("Absolute paths are not allowed: {}", raw));
+    }
+    if path.chars().any(char::is_control) {
+        return Err(format!("Path contains control characters: {}", raw.escape_debug()));
+    }
+
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return Err(format!("Path traversal ('..') is not allowed: {}", raw)),
+            segment => segments.push(segment),
+        }
+    }
+    Ok(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_paths_are_cleaned_up() {
+        assert_eq!(normalize_archive_path("css/site.css").unwrap(), "css/site.css");
+        assert_eq!(normalize_archive_path("css\\site.css").unwrap(), "css/site.css");
+        assert_eq!(normalize_archive_path("./a//b/./c.txt").unwrap(), "a/b/c.txt");
+        assert_eq!(normalize_archive_path("a/").unwrap(), "a");
+        // Only a whole ".." segment is traversal
+        assert_eq!(normalize_archive_path("a/..b/c..").unwrap(), "a/..b/c..");
+    }
+
+    #[test]
+    fn paths_leaving_the_archive_are_refused() {
+        for path in [
+            "../etc/passwd",
+            "a/../../b",
+            "a\\..\\b",
+            "..",
+            "/etc/passwd",
+            "\\server\\share",
+            "C:/Windows",
+            "c:\\boot.ini",
+            "a/b\u{0}.txt",
+            "line\nbreak",
+        ] {
+            assert!(normalize_archive_path(path).is_err(), "{:?} was accepted", path);
+        }
+    }
+
+    #[test]
+    fn files_get_their_type_and_always_the_sandbox() {
+        let site = [("Content-Security-Policy", "default-src 'self'")];
+        let response = file_response(StatusCode::OK, "docs/index.html", Bytes::new(), &site);
+        let headers = response.headers();
+        assert_eq!(headers.get(header::CONTENT_TYPE).unwrap(), "text/html");
+        assert_eq!(headers.get("X-Content-Type-Options").unwrap(), "nosniff");
+        let policies: Vec<_> = headers.get_all(header::CONTENT_SECURITY_POLICY).collect();
+        assert_eq!(policies, ["default-src 'self'", ARCHIVE_SANDBOX]);
+
+        let response = file_response(StatusCode::OK, "data.unknown", Bytes::new(), &[]);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+    }
 }
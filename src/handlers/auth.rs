@@ -0,0 +1,354 @@
+// src/handlers/auth.rs
+//! Authentication middleware - Checks the token before any handler runs
+//!
+//! For 1st Year CS Students:
+//! With `[auth]` enabled, every request goes through `authenticate`:
+//!
+//!   1. find the route in the route table (what does it need? read or
+//!      write on which data type, or admin)
+//!   2. read the token from `Authorization: Bearer <token>`
+//!   3. look up the token's scopes and check one of them allows it
+//!
+//! No token → 401 Unauthorized ("who are you?"), unless the anonymous
+//! scopes allow the request. A token without the right scope → 403
+//! Forbidden ("I know who you are, and you may not").
+//!
+//! WebDAV clients can't send bearer tokens, so HTTP Basic auth works too:
+//! any user name, the token as password. WebDAV paths are checked as the
+//! bucket or archive they touch (`/webdav/kv/notes/...` needs a
+//! `key_value/notes` scope), the Destination of a MOVE as well.
+//!
+//! Two routes take the bucket or name from the JSON body (POST
+//! /anttp-0/key_value and POST /anttp-0/pnr). The middleware can't read
+//! the body, so it only checks the scope could allow *some* name, and the
+//! handler checks the real one with `authorize_name`.
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ResourceDef, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, HttpMessage, HttpRequest, ResponseError};
+use base64::Engine as _;
+
+use super::webdav::{dav_scope_target, destination_path};
+use crate::config::{Config, ConfigError};
+use crate::routes::{access_for, enabled_groups};
+use crate::services::tokens::parse_scopes;
+use crate::services::{split_pnr_name, Access, Scope, ServiceError, TokenStore};
+
+/// What one route (or a group's whole prefix) needs
+struct ProtectedRoute {
+    /// `None` = any method (access then follows the method)
+    method: Option<Method>,
+    resource: ResourceDef,
+    group: &'static str,
+    access: Access,
+    name_param: Option<&'static str>,
+    /// The name is in the request body (checked by the handler)
+    body_named: bool,
+    public: bool,
+    /// WebDAV clients only know Basic auth
+    basic: bool,
+}
+
+/// The route table's auth needs, plus what anonymous requests may do
+pub struct AuthPolicy {
+    routes: Vec<ProtectedRoute>,
+    anonymous: Vec<Scope>,
+}
+
+/// What a request needs, found in the route table
+struct Needed<'a> {
+    route: &'a ProtectedRoute,
+    /// Usually one; a WebDAV MOVE also writes its destination
+    targets: Vec<Target>,
+}
+
+/// `access` on a data type (and one bucket or PNR name)
+struct Target {
+    access: Access,
+    group: &'static str,
+    name: Option<String>,
+}
+
+/// The scopes a request was let in with (for `authorize_name`)
+#[derive(Clone)]
+struct GrantedScopes(Vec<Scope>);
+
+impl AuthPolicy {
+    /// Built from the enabled route groups (in the table's order)
+    pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        let mut routes = Vec::new();
+        for group in enabled_groups(&config.features) {
+            for route in &group.routes {
+                routes.push(ProtectedRoute {
+                    method: Some(route.method.clone()),
+                    resource: ResourceDef::new(route.pattern),
+                    group: group.name,
+                    access: route.access,
+                    name_param: group.name_param,
+                    body_named: route.named_in_body,
+                    public: group.public,
+                    basic: group.prefix.is_some(),
+                });
+            }
+            // Methods and paths the table doesn't list still reach the group's service
+            if let Some(prefix) = group.prefix {
+                routes.push(ProtectedRoute {
+                    method: None,
+                    resource: ResourceDef::root_prefix(prefix),
+                    group: group.name,
+                    access: Access::Read,
+                    name_param: None,
+                    body_named: false,
+                    public: group.public,
+                    basic: true,
+                });
+            }
+        }
+        let anonymous = parse_scopes(&config.auth.anonymous_scopes)
+            .map_err(|e| ConfigError::invalid("auth.anonymous_scopes", e))?;
+        Ok(Self { routes, anonymous })
+    }
+
+    /// The first route matching the request (like actix's own routing)
+    fn find(&self, req: &ServiceRequest) -> Option<Needed<'_>> {
+        self.routes.iter().find_map(|route| {
+            if route
+                .method
+                .as_ref()
+                .is_some_and(|method| method != req.method())
+            {
+                return None;
+            }
+            let mut path = req.match_info().clone();
+            if !route.resource.capture_match_info(&mut path) {
+                return None;
+            }
+            let name = route
+                .name_param
+                .and_then(|param| path.get(param))
+                // "blog.my-site" belongs to the name "my-site"
+                .map(|name| match route.group {
+                    "pnr" => split_pnr_name(name).0.to_string(),
+                    _ => name.to_string(),
+                });
+            let access = match route.method {
+                Some(_) => route.access,
+                None => access_for(req.method()),
+            };
+            let targets = match route.group {
+                "webdav" => webdav_targets(req, access),
+                group => vec![Target {
+                    access,
+                    group,
+                    name,
+                }],
+            };
+            Some(Needed { route, targets })
+        })
+    }
+
+    /// Ok, or the 401/403 to answer with
+    fn check(&self, req: &ServiceRequest, tokens: &TokenStore) -> Result<(), ServiceError> {
+        let Some(needed) = self.find(req) else {
+            // Not an endpoint: actix answers 404/405 without touching data
+            return Ok(());
+        };
+        if needed.route.public {
+            return Ok(());
+        }
+        // The first target no scope allows (`None` = all allowed)
+        let refused = |scopes: &[Scope]| {
+            needed.targets.iter().find(|target| {
+                !scopes.iter().any(|scope| match &target.name {
+                    None if needed.route.body_named => {
+                        scope.allows_some_name(target.access, target.group)
+                    }
+                    name => scope.allows(target.access, target.group, name.as_deref()),
+                })
+            })
+        };
+
+        let scopes = match request_token(req)? {
+            Some(token) => {
+                let scopes = tokens.scopes_of(&token).ok_or_else(|| {
+                    ServiceError::Unauthorized("Invalid or expired token".to_string())
+                })?;
+                if let Some(target) = refused(&scopes) {
+                    return Err(ServiceError::Forbidden(format!(
+                        "This token has no scope allowing {}",
+                        describe(target)
+                    )));
+                }
+                scopes
+            }
+            None => match refused(&self.anonymous) {
+                None => self.anonymous.clone(),
+                Some(target) => {
+                    return Err(ServiceError::Unauthorized(format!(
+                        "A token is needed for {} (Authorization: Bearer <token>)",
+                        describe(target)
+                    )))
+                }
+            },
+        };
+        req.extensions_mut().insert(GrantedScopes(scopes));
+        Ok(())
+    }
+
+    /// Should a 401 for this request offer Basic auth (WebDAV) instead of Bearer?
+    fn wants_basic(&self, req: &ServiceRequest) -> bool {
+        self.find(req).is_some_and(|needed| needed.route.basic)
+    }
+}
+
+/// Middleware: Refuse requests the token (or anonymous access) doesn't allow
+///
+/// Registered inside the CORS middleware, so refusals still carry CORS
+/// headers and browsers can show them.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let (Some(policy), Some(tokens)) = (
+        req.app_data::<web::Data<AuthPolicy>>().cloned(),
+        req.app_data::<web::Data<TokenStore>>().cloned(),
+    ) else {
+        return Err(actix_web::error::ErrorInternalServerError(
+            "auth is not set up",
+        ));
+    };
+
+    match policy.check(&req, &tokens) {
+        Ok(()) => next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body),
+        Err(e) => {
+            log::warn!("🔒 {} {} refused: {}", req.method(), req.path(), e);
+            let mut response = e.error_response();
+            if matches!(e, ServiceError::Unauthorized(_)) {
+                let challenge = if policy.wants_basic(&req) {
+                    "Basic realm=\"anttp\""
+                } else {
+                    "Bearer realm=\"anttp\""
+                };
+                response.headers_mut().insert(
+                    header::WWW_AUTHENTICATE,
+                    HeaderValue::from_static(challenge),
+                );
+            }
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+/// Helper: The token from `Authorization: Bearer ...` (or Basic's password)
+fn request_token(req: &ServiceRequest) -> Result<Option<String>, ServiceError> {
+    let Some(value) = req.headers().get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let invalid = || {
+        ServiceError::Unauthorized(
+            "Invalid Authorization header (expected Bearer <token>)".to_string(),
+        )
+    };
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    let (scheme, credentials) = value.split_once(' ').ok_or_else(invalid)?;
+    let credentials = credentials.trim();
+
+    if scheme.eq_ignore_ascii_case("bearer") {
+        Ok(Some(credentials.to_string()))
+    } else if scheme.eq_ignore_ascii_case("basic") {
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(credentials)
+            .map_err(|_| invalid())?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (_, password) = decoded.split_once(':').ok_or_else(invalid)?;
+        Ok(Some(password.to_string()))
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Check a bucket or PNR name that came in the request body
+///
+/// For Students:
+/// Call it from handlers of body-named routes (see the module docs)
+/// before touching anything. Without `[auth]` every name is allowed.
+pub fn authorize_name(
+    req: &HttpRequest,
+    access: Access,
+    group: &'static str,
+    name: &str,
+) -> Result<(), ServiceError> {
+    let name = match group {
+        "pnr" => split_pnr_name(name).0,
+        _ => name,
+    };
+    authorize(req, access, group, Some(name))
+}
+
+/// Check access to another data type than the route's own
+///
+/// For handlers that also change something else on request, like an
+/// archive update moving a pointer (`?pointer=`): the middleware only
+/// checked the route's group.
+pub fn authorize(
+    req: &HttpRequest,
+    access: Access,
+    group: &'static str,
+    name: Option<&str>,
+) -> Result<(), ServiceError> {
+    let Some(GrantedScopes(scopes)) = req.extensions().get::<GrantedScopes>().cloned() else {
+        return Ok(());
+    };
+    if scopes.iter().any(|scope| scope.allows(access, group, name)) {
+        return Ok(());
+    }
+    Err(ServiceError::Forbidden(format!(
+        "This token has no scope allowing {}",
+        describe(&Target {
+            access,
+            group,
+            name: name.map(str::to_string),
+        })
+    )))
+}
+
+/// Helper: What a WebDAV request touches (its path, and a MOVE's destination)
+fn webdav_targets(req: &ServiceRequest, access: Access) -> Vec<Target> {
+    let (group, name) = dav_scope_target(req.path());
+    let mut targets = vec![Target {
+        access,
+        group,
+        name,
+    }];
+    let destination = req
+        .headers()
+        .get("Destination")
+        .and_then(|value| value.to_str().ok())
+        .and_then(destination_path);
+    if let Some(destination) = destination {
+        let (group, name) = dav_scope_target(destination);
+        targets.push(Target {
+            access: Access::Write,
+            group,
+            name,
+        });
+    }
+    targets
+}
+
+/// Helper: "write access to key_value/notes"
+fn describe(target: &Target) -> String {
+    match (target.access, &target.name) {
+        (Access::Admin, _) => "admin endpoints".to_string(),
+        (access, Some(name)) => {
+            format!("{} access to {}/{}", access.as_str(), target.group, name)
+        }
+        (access, None) => format!("{} access to {}", access.as_str(), target.group),
+    }
+}
@@ -5,37 +5,37 @@
 //! Handlers are like restaurant workers - they take orders (HTTP requests)
 //! and give you food (HTTP responses)!
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpResponse};
 use base64::Engine; // Need this to use encode/decode methods
 use bytes::Bytes;
 
-use crate::models::{ChunkData, ChunkRequest, ChunkResponse, ErrorResponse, StoreType};
-use crate::services::NetworkService;
+use super::context::RequestContext;
+use crate::models::{ChunkData, ChunkRequest, ChunkResponse, ErrorCode, ErrorResponse};
+use crate::services::{NetworkService, ServiceError};
 
 /// POST /anttp-0/chunk - Create chunk (JSON)
 ///
 /// For Students:
 /// This takes Base64-encoded content and stores it as a chunk
 pub async fn create_chunk(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<ChunkRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("📝 Creating chunk (JSON)");
 
     // Get storage type from header
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     // Decode Base64 content
     let content_bytes = match base64::engine::general_purpose::STANDARD.decode(&body.content) {
         Ok(bytes) => Bytes::from(bytes),
         Err(e) => {
             log::error!("❌ Invalid Base64: {}", e);
-            return HttpResponse::BadRequest().json(ErrorResponse::new(format!(
-                "Invalid Base64 encoding: {}",
-                e
-            )));
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::InvalidInput,
+                format!("Invalid Base64 encoding: {}", e),
+            ));
         }
     };
 
@@ -47,8 +47,7 @@ pub async fn create_chunk(
         }
         Err(e) => {
             log::error!("❌ Failed to store chunk: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to store chunk", e.to_string()))
+            ServiceError::from(e).response("Failed to store chunk")
         }
     }
 }
@@ -58,14 +57,13 @@ pub async fn create_chunk(
 /// For Students:
 /// This takes raw bytes (like an image) and stores it
 pub async fn create_chunk_binary(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: Bytes,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("📝 Creating chunk (Binary, {} bytes)", body.len());
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     match network.store_chunk(body, use_network).await {
         Ok(address) => {
@@ -74,8 +72,7 @@ pub async fn create_chunk_binary(
         }
         Err(e) => {
             log::error!("❌ Failed to store binary chunk: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to store chunk", e.to_string()))
+            ServiceError::from(e).response("Failed to store chunk")
         }
     }
 }
@@ -85,14 +82,25 @@ pub async fn create_chunk_binary(
 /// For Students:
 /// Retrieves a chunk and returns it as Base64
 pub async fn get_chunk(
-    req: HttpRequest,
-    address: web::Path<String>,
+    ctx: RequestContext,
+    path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
+    let address = path.into_inner();
     log::info!("📖 Retrieving chunk: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve chunk address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_chunk(&address, use_network).await {
         Ok(data) => {
@@ -103,8 +111,7 @@ pub async fn get_chunk(
         }
         Err(e) => {
             log::error!("❌ Chunk not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Chunk not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get chunk")
         }
     }
 }
@@ -114,14 +121,25 @@ pub async fn get_chunk(
 /// For Students:
 /// Retrieves a chunk and returns raw bytes
 pub async fn get_chunk_binary(
-    req: HttpRequest,
-    address: web::Path<String>,
+    ctx: RequestContext,
+    path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
+    let address = path.into_inner();
     log::info!("📖 Retrieving binary chunk: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve chunk address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_chunk(&address, use_network).await {
         Ok(data) => {
@@ -132,17 +150,8 @@ pub async fn get_chunk_binary(
         }
         Err(e) => {
             log::error!("❌ Chunk not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Chunk not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get chunk")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
-}
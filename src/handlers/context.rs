@@ -0,0 +1,166 @@
+// src/handlers/context.rs
+//! Request context - The common headers, read and checked once
+//!
+//! For 1st Year CS Students:
+//! Almost every endpoint asks "which store?" (x-store-type) and some ask
+//! "who owns this?" (x-owner-key). Instead of every handler digging in the
+//! headers itself (and quietly guessing when a value is wrong), actix runs
+//! this extractor before the handler: add a `ctx: RequestContext` parameter
+//! and the headers arrive already checked. A typo like
+//! `x-store-type: netwrok` gets a 400 instead of silently using memory.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+
+use crate::config::Config;
+use crate::models::{Consistency, StoreType};
+use crate::services::ServiceError;
+
+pub const STORE_TYPE_HEADER: &str = "x-store-type";
+pub const OWNER_KEY_HEADER: &str = "x-owner-key";
+pub const CONSISTENCY_HEADER: &str = "x-consistency";
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request ID a client may send
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Server-wide defaults for requests that don't choose
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestDefaults {
+    /// Store used when there is no x-store-type header
+    pub store_type: StoreType,
+}
+
+impl RequestDefaults {
+    /// `storage.default_store_type` from the server configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            store_type: config.storage.default_store_type,
+        }
+    }
+}
+
+/// The common request headers, parsed and checked
+///
+/// For Students:
+/// - `x-store-type: memory|disk|network` (missing = the server default)
+/// - `x-owner-key: <key>` for owned PNR names
+/// - `x-consistency: cached|strong` (missing = cached)
+/// - `x-request-id: <id>` (missing = a new random ID)
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub store_type: StoreType,
+    /// Key proving ownership of a PNR name
+    pub owner_key: Option<String>,
+    pub consistency: Consistency,
+    /// The client's ID for this request (or a new one), sent back on the response
+    pub request_id: String,
+}
+
+impl RequestContext {
+    /// Should this request use the Autonomi network?
+    pub fn use_network(&self) -> bool {
+        self.store_type == StoreType::Network
+    }
+
+    pub fn owner_key(&self) -> Option<&str> {
+        self.owner_key.as_deref()
+    }
+
+    /// Parse the headers (once per request - later calls reuse the result)
+    fn extract(req: &HttpRequest) -> Result<Self, ServiceError> {
+        if let Some(context) = req.extensions().get::<RequestContext>() {
+            return Ok(context.clone());
+        }
+
+        let default_store_type = req
+            .app_data::<web::Data<RequestDefaults>>()
+            .map(|defaults| defaults.store_type)
+            .unwrap_or_default();
+        let store_type = match header(req, STORE_TYPE_HEADER)? {
+            Some(value) => value
+                .parse()
+                .map_err(|e: anyhow::Error| ServiceError::InvalidInput(e.to_string()))?,
+            None => default_store_type,
+        };
+        let consistency = match header(req, CONSISTENCY_HEADER)? {
+            Some(value) => value
+                .parse()
+                .map_err(|e: anyhow::Error| ServiceError::InvalidInput(e.to_string()))?,
+            None => Consistency::default(),
+        };
+        let owner_key = header(req, OWNER_KEY_HEADER)?.map(str::to_string);
+        let request_id = match header(req, REQUEST_ID_HEADER)? {
+            Some(value) if is_request_id(value) => value.to_string(),
+            Some(_) => {
+                return Err(ServiceError::InvalidInput(format!(
+                    "Invalid {}: use up to {} letters, digits, '-', '_', '.' or ':'",
+                    REQUEST_ID_HEADER, MAX_REQUEST_ID_LEN
+                )))
+            }
+            None => uuid::Uuid::new_v4().to_string(),
+        };
+
+        let context = Self {
+            store_type,
+            owner_key,
+            consistency,
+            request_id,
+        };
+        req.extensions_mut().insert(context.clone());
+        Ok(context)
+    }
+}
+
+impl FromRequest for RequestContext {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Self::extract(req))
+    }
+}
+
+/// Middleware: Send the request ID back as x-request-id
+///
+/// Only requests whose handler took a `RequestContext` have an ID.
+pub async fn echo_request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let mut response = next.call(req).await?;
+    let request_id = response
+        .request()
+        .extensions()
+        .get::<RequestContext>()
+        .and_then(|context| HeaderValue::from_str(&context.request_id).ok());
+    if let Some(request_id) = request_id {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), request_id);
+    }
+    Ok(response)
+}
+
+/// Helper: A header's value (`None` when missing or empty)
+fn header<'a>(req: &'a HttpRequest, name: &str) -> Result<Option<&'a str>, ServiceError> {
+    match req.headers().get(name) {
+        Some(value) => value
+            .to_str()
+            .map(|value| Some(value.trim()).filter(|value| !value.is_empty()))
+            .map_err(|_| ServiceError::InvalidInput(format!("Invalid {}: not plain text", name))),
+        None => Ok(None),
+    }
+}
+
+/// Helper: Request IDs end up in logs and headers, so keep them simple
+fn is_request_id(value: &str) -> bool {
+    value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
@@ -0,0 +1,38 @@
+// src/handlers/docs.rs
+//! API docs handlers - The OpenAPI document and a page to explore it
+//!
+//! For 1st Year CS Students:
+//! `/openapi.json` is the API description for programs (client
+//! generators, Postman, ...). `/docs` is the same thing for people: a
+//! single HTML page (no internet needed - everything is inside it) that
+//! reads `/openapi.json` and lets you try each endpoint from the browser.
+
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+
+use crate::config::Config;
+use crate::openapi::openapi_document;
+use crate::routes::enabled_groups;
+
+/// The viewer page (reads /openapi.json when it loads)
+const DOCS_PAGE: &str = include_str!("docs.html");
+
+/// GET /openapi.json - OpenAPI 3 description of every enabled endpoint
+///
+/// For Students:
+/// Built from the same table as the routes, minus the groups switched
+/// off in `[features]` - so the document never lists a path that 404s.
+pub async fn get_openapi(config: web::Data<Config>) -> HttpResponse {
+    let document = openapi_document(&enabled_groups(&config.features), config.auth.enabled);
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(document.to_string())
+}
+
+/// GET /docs - Interactive API viewer
+pub async fn get_api_docs() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/html; charset=utf-8"))
+        .body(DOCS_PAGE)
+}
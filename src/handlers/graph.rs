@@ -5,10 +5,17 @@
 //! Graphs are like networks or webs of connected data!
 //! Think: Social network (friends), Road map (cities), Family tree
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpResponse};
+use std::collections::{BTreeMap, HashMap};
 
-use crate::models::{ErrorResponse, GraphEntryRequest, StoreType};
-use crate::services::NetworkService;
+use super::context::RequestContext;
+use super::xml::xml_escape;
+use crate::models::{
+    ErrorCode, ErrorResponse, GraphEntryData, GraphEntryRequest, GraphExportFormat,
+    GraphExportQuery, GraphImportResponse, JsonGraph, JsonGraphDocument, JsonGraphEdge,
+    JsonGraphNode, JsonGraphNodeMetadata,
+};
+use crate::services::{NetworkService, ServiceError};
 
 /// POST /anttp-0/graph_entry - Create graph entry
 ///
@@ -16,25 +23,25 @@ use crate::services::NetworkService;
 /// Store a node in a graph data structure
 /// Graphs connect pieces of data together (like Facebook friends)
 pub async fn create_graph_entry(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<GraphEntryRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("🕸️ Creating graph entry: {}", body.name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     // Validate hex encoding
     if hex::decode(&body.content).is_err() {
         log::error!("❌ Invalid hex encoding");
         return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
             "Content must be hex-encoded (not Base64!)",
         ));
     }
 
     match network
-        .store_graph_entry(&body.name, &body.content, use_network)
+        .store_graph_entry(&body.name, &body.content, &body.descendants, use_network)
         .await
     {
         Ok(address) => {
@@ -45,8 +52,7 @@ pub async fn create_graph_entry(
         }
         Err(e) => {
             log::error!("❌ Failed to create graph entry: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create graph entry", e.to_string()))
+            ServiceError::from(e).response("Failed to create graph entry")
         }
     }
 }
@@ -56,37 +62,300 @@ pub async fn create_graph_entry(
 /// For Students:
 /// Retrieve a node from the graph
 pub async fn get_graph_entry(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let address = path.into_inner();
     log::info!("🕸️ Getting graph entry: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve graph entry address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_graph_entry(&address, use_network).await {
-        Ok((name, content)) => {
+        Ok(entry) => {
             log::info!("✅ Graph entry retrieved");
-            HttpResponse::Ok().json(serde_json::json!({
-                "name": name,
-                "content": content
-            }))
+            HttpResponse::Ok().json(entry)
         }
         Err(e) => {
             log::error!("❌ Graph entry not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Graph entry not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get graph entry")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
+/// GET /anttp-0/graph_entry/{address}/export - Export a graph
+///
+/// For Students:
+/// Walks every entry reachable from the root and writes the whole
+/// graph out as one file. Pick the format with `?format=dot|json|graphml`
+/// (DOT can be drawn with Graphviz: `dot -Tpng graph.dot > graph.png`)
+pub async fn export_graph(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    query: web::Query<GraphExportQuery>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let root = path.into_inner();
+    log::info!("🕸️ Exporting graph from {} as {:?}", root, query.format);
+
+    let use_network = ctx.use_network();
+
+    let root = match network
+        .resolve_address(&root, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve graph root address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
+
+    let entries = match network.walk_graph(&root, query.depth, use_network).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!("❌ Failed to walk graph: {}", e);
+            return ServiceError::from(e).response("Graph export failed");
+        }
+    };
+
+    log::info!("✅ Graph exported ({} entries)", entries.len());
+
+    match query.format {
+        GraphExportFormat::Dot => HttpResponse::Ok()
+            .content_type("text/vnd.graphviz")
+            .body(render_dot(&root, &entries)),
+        GraphExportFormat::Json => HttpResponse::Ok().json(to_json_graph(&entries)),
+        GraphExportFormat::Graphml => HttpResponse::Ok()
+            .content_type("application/graphml+xml")
+            .body(render_graphml(&entries)),
+    }
 }
+
+/// POST /anttp-0/graph_entry/import - Bulk import a graph
+///
+/// For Students:
+/// Takes a JSON Graph Format document (the same shape the json export
+/// produces) and stores every node. Because an entry's address depends
+/// on the addresses it links to, children have to be stored before
+/// their parents - so loops (cycles) can't be imported.
+pub async fn import_graph(
+    ctx: RequestContext,
+    body: web::Json<JsonGraphDocument>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let graph = &body.graph;
+    log::info!("🕸️ Importing graph ({} nodes)", graph.nodes.len());
+
+    let use_network = ctx.use_network();
+
+    if let Some((id, _)) = graph
+        .nodes
+        .iter()
+        .find(|(_, node)| hex::decode(&node.metadata.content).is_err())
+    {
+        log::error!("❌ Invalid hex encoding in node {}", id);
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            format!("Content of node '{}' must be hex-encoded", id),
+        ));
+    }
+
+    let order = match import_order(graph) {
+        Ok(order) => order,
+        Err(message) => {
+            log::error!("❌ {}", message);
+            return HttpResponse::BadRequest()
+                .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+        }
+    };
+
+    let mut addresses: BTreeMap<String, String> = BTreeMap::new();
+    for id in order {
+        let node = &graph.nodes[id];
+        // Internal targets were stored earlier in the order; anything
+        // else is an address that already exists in the store
+        let descendants: Vec<String> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.source == id)
+            .map(|edge| {
+                addresses
+                    .get(&edge.target)
+                    .cloned()
+                    .unwrap_or_else(|| edge.target.clone())
+            })
+            .collect();
+
+        match network
+            .store_graph_entry(&node.label, &node.metadata.content, &descendants, use_network)
+            .await
+        {
+            Ok(address) => {
+                addresses.insert(id.to_string(), address);
+            }
+            Err(e) => {
+                log::error!("❌ Failed to import graph node {}: {}", id, e);
+                return ServiceError::from(e)
+                    .response(format!("Failed to import graph node '{}'", id));
+            }
+        }
+    }
+
+    let roots = graph
+        .nodes
+        .keys()
+        .filter(|id| !graph.edges.iter().any(|edge| &edge.target == *id))
+        .map(|id| addresses[id].clone())
+        .collect();
+
+    log::info!("✅ Graph imported ({} entries)", addresses.len());
+    HttpResponse::Ok().json(GraphImportResponse { addresses, roots })
+}
+
+/// Helper: Order node ids so every node comes after the nodes it links to
+fn import_order(graph: &JsonGraph) -> Result<Vec<&str>, String> {
+    let mut pending: HashMap<&str, usize> =
+        graph.nodes.keys().map(|id| (id.as_str(), 0)).collect();
+    let mut parents: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for edge in &graph.edges {
+        if !graph.nodes.contains_key(&edge.source) {
+            return Err(format!("Edge source '{}' is not a node", edge.source));
+        }
+        if graph.nodes.contains_key(&edge.target) {
+            *pending.get_mut(edge.source.as_str()).unwrap() += 1;
+            parents
+                .entry(edge.target.as_str())
+                .or_default()
+                .push(edge.source.as_str());
+        }
+    }
+
+    let mut ready: Vec<&str> = pending
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(id, _)| *id)
+        .collect();
+    let mut order = Vec::with_capacity(graph.nodes.len());
+
+    while let Some(id) = ready.pop() {
+        order.push(id);
+        for parent in parents.get(id).into_iter().flatten() {
+            let count = pending.get_mut(parent).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.push(parent);
+            }
+        }
+    }
+
+    if order.len() < graph.nodes.len() {
+        return Err("Graph contains a cycle and cannot be imported".to_string());
+    }
+    Ok(order)
+}
+
+/// Helper: Build a JSON Graph Format document from walked entries
+fn to_json_graph(entries: &[(String, GraphEntryData)]) -> JsonGraphDocument {
+    let nodes = entries
+        .iter()
+        .map(|(address, entry)| {
+            let node = JsonGraphNode {
+                label: entry.name.clone(),
+                metadata: JsonGraphNodeMetadata {
+                    content: entry.content.clone(),
+                },
+            };
+            (address.clone(), node)
+        })
+        .collect();
+
+    JsonGraphDocument {
+        graph: JsonGraph {
+            directed: true,
+            nodes,
+            edges: edges(entries)
+                .map(|(source, target)| JsonGraphEdge {
+                    source: source.to_string(),
+                    target: target.to_string(),
+                })
+                .collect(),
+        },
+    }
+}
+
+/// Helper: Render walked entries as Graphviz DOT
+fn render_dot(root: &str, entries: &[(String, GraphEntryData)]) -> String {
+    let mut dot = format!("digraph \"{}\" {{\n", dot_escape(root));
+    for (address, entry) in entries {
+        dot.push_str(&format!(
+            "  \"{}\" [label=\"{}\"];\n",
+            dot_escape(address),
+            dot_escape(&entry.name)
+        ));
+    }
+    for (source, target) in edges(entries) {
+        dot.push_str(&format!(
+            "  \"{}\" -> \"{}\";\n",
+            dot_escape(source),
+            dot_escape(target)
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Helper: Render walked entries as GraphML
+fn render_graphml(entries: &[(String, GraphEntryData)]) -> String {
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+        "  <key id=\"content\" for=\"node\" attr.name=\"content\" attr.type=\"string\"/>\n",
+        "  <graph id=\"G\" edgedefault=\"directed\">\n",
+    ));
+    for (address, entry) in entries {
+        xml.push_str(&format!(
+            "    <node id=\"{}\"><data key=\"name\">{}</data><data key=\"content\">{}</data></node>\n",
+            xml_escape(address),
+            xml_escape(&entry.name),
+            xml_escape(&entry.content)
+        ));
+    }
+    for (source, target) in edges(entries) {
+        xml.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\"/>\n",
+            xml_escape(source),
+            xml_escape(target)
+        ));
+    }
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+/// Helper: Every (source, target) link between walked entries
+fn edges(entries: &[(String, GraphEntryData)]) -> impl Iterator<Item = (&str, &str)> {
+    entries.iter().flat_map(|(address, entry)| {
+        entry
+            .descendants
+            .iter()
+            .map(move |child| (address.as_str(), child.as_str()))
+    })
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
@@ -1,17 +1,26 @@
-// src/handlers/keyvalue.rs
 //! Key/Value handlers - Object storage with buckets
 //!
 //! For 1st Year CS Students:
 //! Key/Value storage is like a filing cabinet with drawers!
 //! - Bucket = Drawer (e.g., "photos", "documents")
 //! - Object = File in the drawer (e.g., "vacation.jpg")
+//!
 //! Like AWS S3 or Google Cloud Storage!
+//! Object names can contain "/" - listing with `delimiter=/`
+//! shows them as folders.
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
 use base64::Engine; // Need this to use encode/decode methods
+use bytes::Bytes;
 
-use crate::models::{ErrorResponse, KeyValueData, KeyValueRequest, StoreType};
-use crate::services::NetworkService;
+use super::auth::authorize_name;
+use super::context::RequestContext;
+use crate::models::{
+    BucketRequest, ErrorCode, ErrorResponse, KeyValueData, KeyValueMetadata, KeyValueRequest,
+    ListBucketsResponse, ListObjectsQuery, ObjectVersionsResponse, VersionQuery,
+};
+use crate::services::{Access, NetworkService, ServiceError};
 
 /// POST /anttp-0/key_value - Create key/value pair
 ///
@@ -20,6 +29,7 @@ use crate::services::NetworkService;
 /// Like: bucket="photos", object="vacation.jpg"
 pub async fn create_key_value(
     req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<KeyValueRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
@@ -29,8 +39,20 @@ pub async fn create_key_value(
         body.object
     );
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    if let Err(message) = validate_bucket_name(&body.bucket)
+        .and_then(|_| validate_object_name(&body.object))
+    {
+        log::error!("❌ {}", message);
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+    }
+    // The bucket is in the body, so the auth middleware couldn't check it
+    if let Err(e) = authorize_name(&req, Access::Write, "key_value", &body.bucket) {
+        log::warn!("🔒 {}", e);
+        return e.error_response();
+    }
 
     // Validate Base64
     if base64::engine::general_purpose::STANDARD
@@ -38,25 +60,29 @@ pub async fn create_key_value(
         .is_err()
     {
         log::error!("❌ Invalid Base64");
-        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid Base64 encoding"));
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Invalid Base64 encoding",
+        ));
     }
 
     match network
-        .store_key_value(&body.bucket, &body.object, &body.content, use_network)
+        .store_key_value(
+            &body.bucket,
+            &body.object,
+            &body.content,
+            body.content_type.as_deref(),
+            use_network,
+        )
         .await
     {
-        Ok(address) => {
-            log::info!("✅ Key/value created: {}", address);
-            HttpResponse::Ok().json(serde_json::json!({
-                "address": address,
-                "bucket": body.bucket,
-                "object": body.object
-            }))
+        Ok(stored) => {
+            log::info!("✅ Key/value created: {}", stored.address);
+            HttpResponse::Ok().json(stored)
         }
         Err(e) => {
             log::error!("❌ Failed to create key/value: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create key/value", e.to_string()))
+            ServiceError::from(e).response("Failed to create key/value")
         }
     }
 }
@@ -64,36 +90,378 @@ pub async fn create_key_value(
 /// GET /anttp-0/key_value/{bucket}/{object} - Get key/value
 ///
 /// For Students:
-/// Retrieve data from bucket/object
+/// Retrieve data from bucket/object. Add `?version_id=` to read an
+/// older version from a versioned bucket.
 pub async fn get_key_value(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<(String, String)>,
+    query: web::Query<VersionQuery>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let (bucket, object) = path.into_inner();
     log::info!("🗄️ Getting key/value: {}/{}", bucket, object);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
-    match network.get_key_value(&bucket, &object, use_network).await {
+    match network
+        .get_key_value(&bucket, &object, query.version_id.as_deref(), use_network)
+        .await
+    {
         Ok(content) => {
             log::info!("✅ Key/value retrieved");
             HttpResponse::Ok().json(KeyValueData { content })
         }
         Err(e) => {
             log::error!("❌ Key/value not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Key/value not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get key/value")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
+/// PUT /anttp-0/binary/key_value/{bucket}/{object} - Store key/value (Binary)
+///
+/// For Students:
+/// Same as POST /anttp-0/key_value, but the body is the raw bytes -
+/// no Base64, so nothing gets 33% bigger. The request's Content-Type
+/// is remembered and sent back on GET.
+pub async fn create_key_value_binary(
+    req: HttpRequest,
+    ctx: RequestContext,
+    path: web::Path<(String, String)>,
+    body: Bytes,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let (bucket, object) = path.into_inner();
+    log::info!("🗄️ Storing binary key/value: {}/{} ({} bytes)", bucket, object, body.len());
+
+    let use_network = ctx.use_network();
+
+    if let Err(message) = validate_bucket_name(&bucket).and_then(|_| validate_object_name(&object)) {
+        log::error!("❌ {}", message);
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+    }
+
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+
+    match network
+        .store_key_value_bytes(&bucket, &object, body, content_type, use_network)
+        .await
+    {
+        Ok(stored) => {
+            log::info!("✅ Binary key/value stored: {}", stored.address);
+            HttpResponse::Ok().json(stored)
+        }
+        Err(e) => {
+            log::error!("❌ Failed to store binary key/value: {}", e);
+            ServiceError::from(e).response("Failed to store key/value")
+        }
+    }
 }
+
+/// GET /anttp-0/binary/key_value/{bucket}/{object} - Get key/value (Binary)
+///
+/// For Students:
+/// Returns the raw bytes with the Content-Type they were stored with
+pub async fn get_key_value_binary(
+    ctx: RequestContext,
+    path: web::Path<(String, String)>,
+    query: web::Query<VersionQuery>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let (bucket, object) = path.into_inner();
+    log::info!("🗄️ Getting binary key/value: {}/{}", bucket, object);
+
+    let use_network = ctx.use_network();
+
+    match network
+        .get_key_value_bytes(&bucket, &object, query.version_id.as_deref(), use_network)
+        .await
+    {
+        Ok((content, metadata)) => {
+            log::info!("✅ Binary key/value retrieved ({} bytes)", content.len());
+            metadata_headers(&metadata).body(content)
+        }
+        Err(e) => {
+            log::error!("❌ Key/value not found: {}", e);
+            ServiceError::from(e).response("Failed to get key/value")
+        }
+    }
+}
+
+/// HEAD /anttp-0/key_value/{bucket}/{object} - Get object metadata
+///
+/// For Students:
+/// Like GET but only the headers come back - handy to check if
+/// something exists, how big it is, or whether it changed (ETag)
+/// without downloading it
+pub async fn head_key_value(
+    ctx: RequestContext,
+    path: web::Path<(String, String)>,
+    query: web::Query<VersionQuery>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let (bucket, object) = path.into_inner();
+    log::info!("🗄️ Getting key/value metadata: {}/{}", bucket, object);
+
+    let use_network = ctx.use_network();
+
+    match network
+        .head_key_value(&bucket, &object, query.version_id.as_deref(), use_network)
+        .await
+    {
+        Ok(metadata) => {
+            log::info!("✅ Key/value metadata retrieved");
+            // A sized (but never sent) body makes Content-Length the object's size
+            metadata_headers(&metadata).body(actix_web::body::SizedStream::new(
+                metadata.size,
+                futures::stream::empty::<Result<Bytes, actix_web::Error>>(),
+            ))
+        }
+        Err(e) => {
+            log::error!("❌ Key/value metadata failed: {}", e);
+            // Same status as GET would give, but HEAD never has a body
+            HttpResponse::build(ServiceError::from(e).status_code()).finish()
+        }
+    }
+}
+
+/// DELETE /anttp-0/key_value/{bucket}/{object} - Delete key/value
+///
+/// For Students:
+/// Removes one object from a bucket. In a versioned bucket this adds a
+/// delete marker instead; `?version_id=` permanently removes one version
+/// (delete the marker's version to undo a delete).
+pub async fn delete_key_value(
+    ctx: RequestContext,
+    path: web::Path<(String, String)>,
+    query: web::Query<VersionQuery>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let (bucket, object) = path.into_inner();
+    log::info!("🗄️ Deleting key/value: {}/{}", bucket, object);
+
+    let use_network = ctx.use_network();
+
+    match network
+        .delete_key_value(&bucket, &object, query.version_id.as_deref(), use_network)
+        .await
+    {
+        Ok(delete_marker) => {
+            log::info!("✅ Key/value deleted");
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "Key/value deleted",
+                "delete_marker_version_id": delete_marker
+            }))
+        }
+        Err(e) => {
+            log::error!("❌ Failed to delete key/value: {}", e);
+            ServiceError::from(e).response("Failed to delete key/value")
+        }
+    }
+}
+
+/// GET /anttp-0/key_value_versions/{bucket}/{object} - List object versions
+///
+/// For Students:
+/// Like register history, newest first. Any `version_id` here can be
+/// passed to GET (`?version_id=`) to read that version back.
+pub async fn list_key_value_versions(
+    ctx: RequestContext,
+    path: web::Path<(String, String)>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let (bucket, object) = path.into_inner();
+    log::info!("🗄️ Listing versions: {}/{}", bucket, object);
+
+    let use_network = ctx.use_network();
+
+    match network.list_key_value_versions(&bucket, &object, use_network).await {
+        Ok(versions) => {
+            log::info!("✅ Listed {} versions", versions.len());
+            HttpResponse::Ok().json(ObjectVersionsResponse {
+                bucket,
+                object,
+                versions,
+            })
+        }
+        Err(e) => {
+            log::error!("❌ Failed to list versions: {}", e);
+            ServiceError::from(e).response("Failed to list versions")
+        }
+    }
+}
+
+/// GET /anttp-0/key_value - List buckets
+///
+/// For Students:
+/// Shows every drawer in the filing cabinet
+pub async fn list_buckets(ctx: RequestContext, network: web::Data<NetworkService>) -> HttpResponse {
+    log::info!("🗄️ Listing buckets");
+
+    let use_network = ctx.use_network();
+
+    match network.list_buckets(use_network).await {
+        Ok(buckets) => {
+            log::info!("✅ Listed {} buckets", buckets.len());
+            HttpResponse::Ok().json(ListBucketsResponse { buckets })
+        }
+        Err(e) => {
+            log::error!("❌ Failed to list buckets: {}", e);
+            ServiceError::from(e).response("Failed to list buckets")
+        }
+    }
+}
+
+/// PUT /anttp-0/key_value/{bucket} - Create bucket
+///
+/// For Students:
+/// Buckets are also created automatically by the first object
+/// stored in them, so this is only needed for empty buckets - or to
+/// switch versioning with a body like `{"versioning": true}`
+pub async fn create_bucket(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    body: web::Bytes,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let bucket = path.into_inner();
+    log::info!("🗄️ Creating bucket: {}", bucket);
+
+    let use_network = ctx.use_network();
+
+    if let Err(message) = validate_bucket_name(&bucket) {
+        log::error!("❌ {}", message);
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+    }
+
+    // The body is optional: empty means "just make sure it exists"
+    let settings = if body.is_empty() {
+        BucketRequest::default()
+    } else {
+        match serde_json::from_slice::<BucketRequest>(&body) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::error!("❌ Invalid bucket settings: {}", e);
+                return ServiceError::InvalidInput(e.to_string())
+                    .response("Invalid bucket settings");
+            }
+        }
+    };
+
+    match network.create_bucket(&bucket, settings.versioning, use_network).await {
+        Ok(bucket) => {
+            log::info!("✅ Bucket ready: {}", bucket.name);
+            HttpResponse::Ok().json(bucket)
+        }
+        Err(e) => {
+            log::error!("❌ Failed to create bucket: {}", e);
+            ServiceError::from(e).response("Failed to create bucket")
+        }
+    }
+}
+
+/// GET /anttp-0/key_value/{bucket} - List objects
+///
+/// For Students:
+/// Like `ls` for a bucket. Try `?prefix=photos/&delimiter=/`
+/// to list one "folder", and `max_keys` + `continuation_token`
+/// to page through big buckets.
+pub async fn list_key_values(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    query: web::Query<ListObjectsQuery>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let bucket = path.into_inner();
+    log::info!("🗄️ Listing objects in bucket: {}", bucket);
+
+    let use_network = ctx.use_network();
+
+    match network.list_key_values(&bucket, &query, use_network).await {
+        Ok(listing) => {
+            log::info!("✅ Listed {} keys", listing.key_count);
+            HttpResponse::Ok().json(listing)
+        }
+        Err(e) => {
+            log::error!("❌ Failed to list objects: {}", e);
+            ServiceError::from(e).response("Failed to list objects")
+        }
+    }
+}
+
+/// DELETE /anttp-0/key_value/{bucket} - Delete bucket
+///
+/// For Students:
+/// Only empty buckets can be deleted (delete the objects first)
+pub async fn delete_bucket(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let bucket = path.into_inner();
+    log::info!("🗄️ Deleting bucket: {}", bucket);
+
+    let use_network = ctx.use_network();
+
+    match network.delete_bucket(&bucket, use_network).await {
+        Ok(_) => {
+            log::info!("✅ Bucket deleted");
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "Bucket deleted"
+            }))
+        }
+        Err(e) => {
+            log::error!("❌ Failed to delete bucket: {}", e);
+            ServiceError::from(e).response("Failed to delete bucket")
+        }
+    }
+}
+
+/// Helper: Response carrying object metadata as HTTP headers
+pub(super) fn metadata_headers(metadata: &KeyValueMetadata) -> actix_web::HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(metadata.content_type.as_str())
+        .insert_header((header::ETAG, format!("\"{}\"", metadata.etag)));
+    if let Some(version_id) = &metadata.version_id {
+        response.insert_header(("x-version-id", version_id.as_str()));
+    }
+    if let Some(modified) = chrono::DateTime::from_timestamp(metadata.last_modified, 0) {
+        response.insert_header((
+            header::LAST_MODIFIED,
+            modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        ));
+    }
+    response
+}
+
+/// Helper: Bucket names are 1-63 letters, digits, '.', '-' or '_'
+pub(super) fn validate_bucket_name(bucket: &str) -> Result<(), String> {
+    let valid = (1..=63).contains(&bucket.len())
+        && bucket
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !valid {
+        return Err(format!(
+            "Invalid bucket name '{}': use 1-63 letters, digits, '.', '-' or '_'",
+            bucket
+        ));
+    }
+    Ok(())
+}
+
+/// Helper: Object names are 1-1024 bytes
+pub(super) fn validate_object_name(object: &str) -> Result<(), String> {
+    if object.is_empty() || object.len() > 1024 {
+        return Err("Object name must be between 1 and 1024 bytes".to_string());
+    }
+    Ok(())
+}
+
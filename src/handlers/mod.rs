@@ -1,26 +1,39 @@
 // src/handlers/mod.rs
 //! HTTP request handlers
 
+pub mod admin;
 pub mod archives;
+pub mod auth;
 pub mod chunks;
 pub mod commands;
+pub mod context;
+pub mod docs;
 pub mod graph;
 pub mod keyvalue;
 pub mod pnr;
 pub mod pointers;
 pub mod publicdata;
 pub mod registers;
+pub mod s3;
 pub mod scratchpads;
 pub mod tarchive;
+pub mod webdav;
+mod xml;
 
+pub use admin::*;
 pub use archives::*;
+pub use auth::*;
 pub use chunks::*;
 pub use commands::*;
+pub use context::*;
+pub use docs::*;
 pub use graph::*;
 pub use keyvalue::*;
 pub use pnr::*;
 pub use pointers::*;
 pub use publicdata::*;
 pub use registers::*;
+pub use s3::*;
 pub use scratchpads::*;
 pub use tarchive::*;
+pub use webdav::*;
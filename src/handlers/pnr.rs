@@ -1,4 +1,3 @@
-// src/handlers/pnr.rs
 //! PNR handlers - Pointer Name Registry (DNS-like system)
 //!
 //! For 1st Year CS Students:
@@ -6,41 +5,72 @@
 //! It's like DNS for the Autonomi network!
 //! Instead of "google.com" → "172.217.14.206"
 //! You get "my-website" → "abc123...network-address"
+//!
+//! Names can have sub-names, like DNS subdomains: the "blog" record
+//! of `my-site` is reachable as `blog.my-site`. The "@" record is the
+//! name itself. Every name has an owner: the `x-owner-key` sent when
+//! registering it (or a key the server makes up and returns once).
+//! Only that key can change or delete the name.
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use std::collections::HashMap;
 
-use crate::models::{ErrorResponse, PnrRequest, StoreType};
-use crate::services::NetworkService;
+use super::auth::authorize_name;
+use super::context::RequestContext;
+use crate::models::{ErrorCode, ErrorResponse, PnrRecord, PnrRecordType, PnrRequest};
+use crate::services::{is_network_address, split_pnr_name, Access, NetworkService, ServiceError};
 
 /// POST /anttp-0/pnr - Create PNR
 ///
 /// For Students:
 /// Create a name registry (like registering a domain name)
+/// A name that is already taken answers 409 Conflict. Without an
+/// `x-owner-key` header a random key is made and returned as
+/// `owner_key` - keep it, it is the only way to change the name later.
 pub async fn create_pnr(
     req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<PnrRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("🌐 Creating PNR: {}", body.name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    if let Err(message) = validate_request(&body.name, &body.records) {
+        log::error!("❌ Invalid PNR: {}", message);
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+    }
+    // The name is in the body, so the auth middleware couldn't check it
+    if let Err(e) = authorize_name(&req, Access::Write, "pnr", &body.name) {
+        log::warn!("🔒 {}", e);
+        return e.error_response();
+    }
+
+    let (owner_key, generated) = match ctx.owner_key() {
+        Some(owner_key) => (owner_key.to_string(), false),
+        None => (hex::encode(rand::random::<[u8; 32]>()), true),
+    };
 
     match network
-        .store_pnr(&body.name, &body.records, use_network)
+        .store_pnr(&body.name, &body.records, &owner_key, use_network)
         .await
     {
         Ok(address) => {
             log::info!("✅ PNR created: {}", address);
-            HttpResponse::Ok().json(serde_json::json!({
+            let mut response = serde_json::json!({
                 "address": address,
                 "name": body.name
-            }))
+            });
+            if generated {
+                response["owner_key"] = serde_json::Value::from(owner_key);
+            }
+            HttpResponse::Ok().json(response)
         }
         Err(e) => {
             log::error!("❌ Failed to create PNR: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create PNR", e.to_string()))
+            ServiceError::from(e).response("Failed to create PNR")
         }
     }
 }
@@ -50,7 +80,7 @@ pub async fn create_pnr(
 /// For Students:
 /// Update DNS-like records (like changing where a domain points)
 pub async fn update_pnr(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     body: web::Json<PnrRequest>,
     network: web::Data<NetworkService>,
@@ -58,11 +88,18 @@ pub async fn update_pnr(
     let name = path.into_inner();
     log::info!("🌐 Updating PNR: {}", name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    if let Err(message) = validate_body_name(&name, &body.name)
+        .and_then(|_| validate_request(&name, &body.records))
+    {
+        log::error!("❌ Invalid PNR: {}", message);
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+    }
 
     match network
-        .update_pnr(&name, &body.records, use_network)
+        .update_pnr(&name, &body.records, ctx.owner_key(), use_network)
         .await
     {
         Ok(_) => {
@@ -74,8 +111,7 @@ pub async fn update_pnr(
         }
         Err(e) => {
             log::error!("❌ Failed to update PNR: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to update PNR", e.to_string()))
+            ServiceError::from(e).response("Failed to update PNR")
         }
     }
 }
@@ -84,19 +120,30 @@ pub async fn update_pnr(
 ///
 /// For Students:
 /// Look up DNS-like records (like "nslookup")
+/// Asking for `blog.my-site` returns just the "blog" record.
 pub async fn get_pnr(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let name = path.into_inner();
     log::info!("🌐 Getting PNR: {}", name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
-    match network.get_pnr(&name, use_network).await {
-        Ok(records) => {
+    let (pnr_name, sub_name) = split_pnr_name(&name);
+    match network.get_pnr(pnr_name, use_network).await {
+        Ok(mut records) => {
+            if let Some(sub_name) = sub_name {
+                records.retain(|key, _| key == sub_name);
+                if records.is_empty() {
+                    log::error!("❌ PNR sub-name not found: {}", name);
+                    return HttpResponse::NotFound().json(ErrorResponse::new(
+                        ErrorCode::NotFound,
+                        format!("PNR not found: {}", name),
+                    ));
+                }
+            }
             log::info!("✅ PNR retrieved");
             HttpResponse::Ok().json(serde_json::json!({
                 "name": name,
@@ -105,8 +152,7 @@ pub async fn get_pnr(
         }
         Err(e) => {
             log::error!("❌ PNR not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("PNR not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get pNR")
         }
     }
 }
@@ -116,7 +162,7 @@ pub async fn get_pnr(
 /// For Students:
 /// Add more records without replacing existing ones
 pub async fn append_pnr(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     body: web::Json<PnrRequest>,
     network: web::Data<NetworkService>,
@@ -124,11 +170,18 @@ pub async fn append_pnr(
     let name = path.into_inner();
     log::info!("🌐 Appending to PNR: {}", name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    if let Err(message) = validate_body_name(&name, &body.name)
+        .and_then(|_| validate_request(&name, &body.records))
+    {
+        log::error!("❌ Invalid PNR: {}", message);
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+    }
 
     match network
-        .append_pnr(&name, &body.records, use_network)
+        .append_pnr(&name, &body.records, ctx.owner_key(), use_network)
         .await
     {
         Ok(_) => {
@@ -140,17 +193,104 @@ pub async fn append_pnr(
         }
         Err(e) => {
             log::error!("❌ Failed to append PNR: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to append PNR", e.to_string()))
+            ServiceError::from(e).response("Failed to append PNR")
+        }
+    }
+}
+
+/// DELETE /anttp-0/pnr/{name} - Delete a PNR name or one record
+///
+/// For Students:
+/// `my-site` deletes the whole name, `blog.my-site` deletes
+/// only its "blog" record (like removing one DNS entry)
+pub async fn delete_pnr(
+    ctx: RequestContext,
+    path: web::Path<String>,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let name = path.into_inner();
+    log::info!("🌐 Deleting PNR: {}", name);
+
+    let use_network = ctx.use_network();
+    let owner_key = ctx.owner_key();
+
+    let result = match split_pnr_name(&name) {
+        (pnr_name, Some(sub_name)) => {
+            network
+                .delete_pnr_record(pnr_name, sub_name, owner_key, use_network)
+                .await
+        }
+        (pnr_name, None) => network.delete_pnr(pnr_name, owner_key, use_network).await,
+    };
+
+    match result {
+        Ok(_) => {
+            log::info!("✅ PNR deleted: {}", name);
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "PNR deleted"
+            }))
+        }
+        Err(e) => {
+            log::error!("❌ Failed to delete PNR: {}", e);
+            ServiceError::from(e).response("Failed to delete PNR")
+        }
+    }
+}
+
+/// Helper: The name in the body must match the name in the URL
+fn validate_body_name(path_name: &str, body_name: &str) -> Result<(), String> {
+    if path_name != body_name {
+        return Err(format!(
+            "Name in body ('{}') does not match name in URL ('{}')",
+            body_name, path_name
+        ));
+    }
+    Ok(())
+}
+
+/// Helper: Check a name and its records before storing them
+fn validate_request(name: &str, records: &HashMap<String, PnrRecord>) -> Result<(), String> {
+    if !is_valid_label(name) {
+        return Err(format!(
+            "Invalid PNR name '{}': use 1-63 lowercase letters, digits or '-' (sub-names go in the record keys)",
+            name
+        ));
+    }
+
+    for (sub_name, record) in records {
+        if sub_name != "@" && !sub_name.split('.').all(is_valid_label) {
+            return Err(format!(
+                "Invalid sub-name '{}': use '@' for the name itself or dot-separated labels",
+                sub_name
+            ));
+        }
+
+        match record.record_type {
+            PnrRecordType::A | PnrRecordType::X if !is_network_address(&record.address) => {
+                return Err(format!(
+                    "Record '{}' ({}) needs a 64-character hex address",
+                    sub_name, record.record_type
+                ));
+            }
+            PnrRecordType::Txt if record.address.is_empty() || record.address.len() > 255 => {
+                return Err(format!(
+                    "Record '{}' (TXT) needs between 1 and 255 characters of text",
+                    sub_name
+                ));
+            }
+            _ => {}
         }
     }
+    Ok(())
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
+/// Helper: A DNS-style label - lowercase letters, digits and inner hyphens
+fn is_valid_label(label: &str) -> bool {
+    (1..=63).contains(&label.len())
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
 }
@@ -7,24 +7,24 @@
 //! - You can update where they point
 //! - Like DNS: "mywebsite.com" points to "192.168.1.1"
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpResponse};
 
-use crate::models::{ErrorResponse, PointerData, PointerRequest, PointerResponse, StoreType};
-use crate::services::NetworkService;
+use super::context::RequestContext;
+use crate::models::{PointerData, PointerRequest, PointerResponse};
+use crate::services::{NetworkService, ServiceError};
 
 /// POST /anttp-0/pointer - Create pointer
 ///
 /// For Students:
 /// Creates a new pointer that points to a chunk/archive/etc
 pub async fn create_pointer(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<PointerRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("📝 Creating pointer: {} → {}", body.name, body.content);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     match network
         .store_pointer(&body.name, &body.content, use_network)
@@ -36,8 +36,7 @@ pub async fn create_pointer(
         }
         Err(e) => {
             log::error!("❌ Failed to create pointer: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create pointer", e.to_string()))
+            ServiceError::from(e).response("Failed to create pointer")
         }
     }
 }
@@ -47,7 +46,7 @@ pub async fn create_pointer(
 /// For Students:
 /// Changes where the pointer points to (like updating a bookmark!)
 pub async fn update_pointer(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     body: web::Json<PointerRequest>,
     network: web::Data<NetworkService>,
@@ -55,8 +54,7 @@ pub async fn update_pointer(
     let address = path.into_inner();
     log::info!("📝 Updating pointer {}: → {}", address, body.content);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     match network
         .update_pointer(&address, &body.name, &body.content, use_network)
@@ -71,8 +69,7 @@ pub async fn update_pointer(
         }
         Err(e) => {
             log::error!("❌ Failed to update pointer: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to update pointer", e.to_string()))
+            ServiceError::from(e).response("Failed to update pointer")
         }
     }
 }
@@ -82,15 +79,25 @@ pub async fn update_pointer(
 /// For Students:
 /// Follows the pointer to see where it points!
 pub async fn get_pointer(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let address = path.into_inner();
     log::info!("📖 Getting pointer: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve pointer address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_pointer(&address, use_network).await {
         Ok(target) => {
@@ -99,17 +106,8 @@ pub async fn get_pointer(
         }
         Err(e) => {
             log::error!("❌ Pointer not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Pointer not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get pointer")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
-}
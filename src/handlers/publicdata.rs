@@ -6,11 +6,11 @@
 //! Just store raw bytes, no encoding needed
 //! Perfect for images, videos, binary files
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpResponse};
 use bytes::Bytes;
 
-use crate::models::{ErrorResponse, StoreType};
-use crate::services::NetworkService;
+use super::context::RequestContext;
+use crate::services::{NetworkService, ServiceError};
 
 /// POST /anttp-0/binary/public_data - Create public data
 ///
@@ -18,14 +18,13 @@ use crate::services::NetworkService;
 /// Store raw binary data (like an image or video)
 /// No encoding! Just send the raw bytes
 pub async fn create_public_data(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: Bytes,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("📤 Creating public data ({} bytes)", body.len());
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     match network.store_public_data(body, use_network).await {
         Ok(address) => {
@@ -36,8 +35,7 @@ pub async fn create_public_data(
         }
         Err(e) => {
             log::error!("❌ Failed to create public data: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create public data", e.to_string()))
+            ServiceError::from(e).response("Failed to create public data")
         }
     }
 }
@@ -48,15 +46,25 @@ pub async fn create_public_data(
 /// Retrieve raw binary data
 /// Returns the exact bytes you stored!
 pub async fn get_public_data(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let address = path.into_inner();
     log::info!("📥 Getting public data: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve public data address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_public_data(&address, use_network).await {
         Ok(data) => {
@@ -67,17 +75,8 @@ pub async fn get_public_data(
         }
         Err(e) => {
             log::error!("❌ Public data not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Public data not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get public data")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
-}
@@ -7,32 +7,30 @@
 //! - Update existing entries
 //! - See the history of all changes!
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpResponse};
 
-use crate::models::{
-    ErrorResponse, RegisterData, RegisterRequest, RegisterResponse,
-    StoreType,
-};
-use crate::services::NetworkService;
+use super::context::RequestContext;
+use crate::models::{ErrorCode, ErrorResponse, RegisterData, RegisterRequest, RegisterResponse};
+use crate::services::{NetworkService, ServiceError};
 
 /// POST /anttp-0/register - Create register
 ///
 /// For Students:
 /// Creates a new mutable register with hex-encoded content
 pub async fn create_register(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<RegisterRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("📝 Creating register: {}", body.name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     // Validate hex encoding
     if hex::decode(&body.content).is_err() {
         log::error!("❌ Invalid hex encoding");
         return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
             "Content must be hex-encoded (not Base64!)",
         ));
     }
@@ -47,8 +45,7 @@ pub async fn create_register(
         }
         Err(e) => {
             log::error!("❌ Failed to create register: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create register", e.to_string()))
+            ServiceError::from(e).response("Failed to create register")
         }
     }
 }
@@ -58,7 +55,7 @@ pub async fn create_register(
 /// For Students:
 /// Updates an existing register - adds a new entry to the history
 pub async fn update_register(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     body: web::Json<RegisterRequest>,
     network: web::Data<NetworkService>,
@@ -66,14 +63,15 @@ pub async fn update_register(
     let address = path.into_inner();
     log::info!("📝 Updating register: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     // Validate hex encoding
     if hex::decode(&body.content).is_err() {
         log::error!("❌ Invalid hex encoding");
-        return HttpResponse::BadRequest()
-            .json(ErrorResponse::new("Content must be hex-encoded"));
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Content must be hex-encoded",
+        ));
     }
 
     match network
@@ -89,8 +87,7 @@ pub async fn update_register(
         }
         Err(e) => {
             log::error!("❌ Failed to update register: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to update register", e.to_string()))
+            ServiceError::from(e).response("Failed to update register")
         }
     }
 }
@@ -100,15 +97,25 @@ pub async fn update_register(
 /// For Students:
 /// Gets the latest value from the register
 pub async fn get_register(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let address = path.into_inner();
     log::info!("📖 Getting register: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve register address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_register(&address, use_network).await {
         Ok(content) => {
@@ -117,8 +124,7 @@ pub async fn get_register(
         }
         Err(e) => {
             log::error!("❌ Register not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Register not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get register")
         }
     }
 }
@@ -129,15 +135,25 @@ pub async fn get_register(
 /// Gets ALL the values this register has ever had!
 /// Like seeing all the edits to a Google Doc
 pub async fn get_register_history(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let address = path.into_inner();
     log::info!("📜 Getting register history: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve register address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_register_history(&address, use_network).await {
         Ok(history) => {
@@ -146,17 +162,8 @@ pub async fn get_register_history(
         }
         Err(e) => {
             log::error!("❌ Failed to get history: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Register not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get register")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
-}
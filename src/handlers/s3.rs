@@ -0,0 +1,879 @@
+//! S3 handlers - An S3-compatible face for the key/value store
+//!
+//! For 1st Year CS Students:
+//! S3 is Amazon's object storage API, and lots of tools speak it
+//! (aws-cli, rclone, every AWS SDK). This listener answers S3 requests
+//! on its own port (S3_PORT), so those tools can use our buckets directly:
+//! `aws --endpoint-url http://127.0.0.1:18889 s3 cp photo.jpg s3://photos/`
+//!
+//! S3 puts the bucket first in the path (`/{bucket}/{key}`) and decides
+//! what to do from the method *and* the query string - `POST ?uploads`
+//! starts a multipart upload, `PUT ?partNumber=1&uploadId=...` uploads a
+//! part. So there is one entry point, `s3_request`, that works out which
+//! S3 operation was asked for. Answers are XML, like real S3.
+//!
+//! Every request must be signed (SigV4) with a key from S3_ACCESS_KEYS.
+
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
+use bytes::Bytes;
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
+
+use super::keyvalue::{metadata_headers, validate_bucket_name, validate_object_name};
+use super::xml::xml_escape;
+use crate::models::{KeyValueMetadata, ListObjectsQuery, StoreType};
+use crate::services::s3::parse_query;
+use crate::services::{KeyValueError, NetworkService, S3Gateway, S3UploadError, SignedRequest};
+
+const S3_XMLNS: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+/// Any method, any path - work out the S3 operation and run it
+pub async fn s3_request(
+    req: HttpRequest,
+    body: web::Bytes,
+    network: web::Data<NetworkService>,
+    gateway: web::Data<S3Gateway>,
+) -> HttpResponse {
+    let signed = SignedRequest {
+        method: req.method().as_str(),
+        path: req.uri().path(),
+        query: req.query_string(),
+        headers: signed_headers(&req),
+    };
+    let body = match gateway.authenticate(&signed, body) {
+        Ok(body) => body,
+        Err(e) => {
+            log::warn!("🔒 S3 request refused: {}", e);
+            let status = if e.is_client_error() {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::FORBIDDEN
+            };
+            return s3_error(status, e.code(), &e.to_string(), req.path());
+        }
+    };
+
+    let (bucket, key) = split_path(req.path());
+    let query: HashMap<String, String> = parse_query(req.query_string()).into_iter().collect();
+    let s3 = S3Call {
+        req: &req,
+        network: &network,
+        gateway: &gateway,
+        use_network: gateway.config().store_type == StoreType::Network,
+        query,
+    };
+
+    if let Some(bucket) = &bucket {
+        if let Err(message) = validate_bucket_name(bucket) {
+            return s3_error(
+                StatusCode::BAD_REQUEST,
+                "InvalidBucketName",
+                &message,
+                req.path(),
+            );
+        }
+    }
+    if let Some(key) = &key {
+        if let Err(message) = validate_object_name(key) {
+            return s3_error(
+                StatusCode::BAD_REQUEST,
+                "KeyTooLongError",
+                &message,
+                req.path(),
+            );
+        }
+    }
+
+    let method = req.method().clone();
+    match (bucket, key) {
+        (None, _) if method == Method::GET => s3.list_buckets().await,
+        (Some(bucket), None) => match method {
+            Method::GET if s3.has("location") => s3.get_bucket_location(&bucket).await,
+            Method::GET => s3.list_objects(&bucket).await,
+            Method::HEAD => s3.head_bucket(&bucket).await,
+            Method::PUT => s3.create_bucket(&bucket).await,
+            Method::DELETE => s3.delete_bucket(&bucket).await,
+            Method::POST if s3.has("delete") => s3.delete_objects(&bucket, &body).await,
+            _ => not_implemented(&req),
+        },
+        (Some(bucket), Some(key)) => match method {
+            Method::GET => s3.get_object(&bucket, &key).await,
+            Method::HEAD => s3.head_object(&bucket, &key).await,
+            Method::PUT if s3.has("uploadId") => s3.upload_part(&bucket, &key, body).await,
+            Method::PUT if req.headers().contains_key("x-amz-copy-source") => {
+                s3.copy_object(&bucket, &key).await
+            }
+            Method::PUT => s3.put_object(&bucket, &key, body).await,
+            Method::DELETE if s3.has("uploadId") => s3.abort_upload(&bucket, &key).await,
+            Method::DELETE => s3.delete_object(&bucket, &key).await,
+            Method::POST if s3.has("uploads") => s3.create_upload(&bucket, &key).await,
+            Method::POST if s3.has("uploadId") => s3.complete_upload(&bucket, &key, &body).await,
+            _ => not_implemented(&req),
+        },
+        _ => not_implemented(&req),
+    }
+}
+
+/// One S3 request, after its signature has been checked
+struct S3Call<'a> {
+    req: &'a HttpRequest,
+    network: &'a NetworkService,
+    gateway: &'a S3Gateway,
+    use_network: bool,
+    query: HashMap<String, String>,
+}
+
+impl S3Call<'_> {
+    fn has(&self, name: &str) -> bool {
+        self.query.contains_key(name)
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        self.query.get(name).cloned()
+    }
+
+    fn resource(&self) -> &str {
+        self.req.path()
+    }
+
+    // ========================================================================
+    // BUCKETS
+    // ========================================================================
+
+    /// GET / - ListBuckets
+    async fn list_buckets(&self) -> HttpResponse {
+        log::info!("🪣 S3 ListBuckets");
+        match self.network.list_buckets(self.use_network).await {
+            Ok(buckets) => {
+                let mut xml = format!(
+                    "<ListAllMyBucketsResult xmlns=\"{}\"><Owner><ID>anttp</ID><DisplayName>anttp</DisplayName></Owner><Buckets>",
+                    S3_XMLNS
+                );
+                for bucket in buckets {
+                    xml.push_str(&format!(
+                        "<Bucket><Name>{}</Name><CreationDate>{}</CreationDate></Bucket>",
+                        xml_escape(&bucket.name),
+                        iso_date(bucket.created)
+                    ));
+                }
+                xml.push_str("</Buckets></ListAllMyBucketsResult>");
+                xml_ok(xml)
+            }
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// PUT /{bucket} - CreateBucket
+    async fn create_bucket(&self, bucket: &str) -> HttpResponse {
+        log::info!("🪣 S3 CreateBucket: {}", bucket);
+        match self
+            .network
+            .create_bucket(bucket, None, self.use_network)
+            .await
+        {
+            Ok(_) => HttpResponse::Ok()
+                .insert_header((header::LOCATION, format!("/{}", bucket)))
+                .finish(),
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// HEAD /{bucket} - HeadBucket
+    async fn head_bucket(&self, bucket: &str) -> HttpResponse {
+        match self.bucket_exists(bucket).await {
+            Ok(true) => HttpResponse::Ok()
+                .insert_header(("x-amz-bucket-region", self.gateway.config().region.as_str()))
+                .finish(),
+            Ok(false) => self.key_value_error(KeyValueError::BucketNotFound.into()),
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// GET /{bucket}?location - GetBucketLocation
+    async fn get_bucket_location(&self, bucket: &str) -> HttpResponse {
+        match self.bucket_exists(bucket).await {
+            Ok(true) => xml_ok(format!(
+                "<LocationConstraint xmlns=\"{}\">{}</LocationConstraint>",
+                S3_XMLNS,
+                xml_escape(&self.gateway.config().region)
+            )),
+            Ok(false) => self.key_value_error(KeyValueError::BucketNotFound.into()),
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// DELETE /{bucket} - DeleteBucket
+    async fn delete_bucket(&self, bucket: &str) -> HttpResponse {
+        log::info!("🪣 S3 DeleteBucket: {}", bucket);
+        match self.network.delete_bucket(bucket, self.use_network).await {
+            Ok(()) => HttpResponse::NoContent().finish(),
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// GET /{bucket} - ListObjectsV2 (`list-type=2`) or the older ListObjects
+    ///
+    /// For Students:
+    /// V2 pages with opaque continuation tokens, V1 with a "marker" (the
+    /// last key seen). Both map onto the same key/value listing.
+    async fn list_objects(&self, bucket: &str) -> HttpResponse {
+        let v2 = self.param("list-type").as_deref() == Some("2");
+        let max_keys = match self.param("max-keys").map(|value| value.parse::<usize>()) {
+            Some(Ok(max_keys)) => Some(max_keys),
+            Some(Err(_)) => {
+                return s3_error(
+                    StatusCode::BAD_REQUEST,
+                    "InvalidArgument",
+                    "max-keys must be a number",
+                    self.resource(),
+                )
+            }
+            None => None,
+        };
+        let query = ListObjectsQuery {
+            prefix: self.param("prefix"),
+            delimiter: self.param("delimiter"),
+            max_keys,
+            continuation_token: if v2 {
+                self.param("continuation-token")
+            } else {
+                None
+            },
+            start_after: if v2 {
+                self.param("start-after")
+            } else {
+                self.param("marker")
+            },
+        };
+        log::info!(
+            "🪣 S3 ListObjects{}: {}",
+            if v2 { "V2" } else { "" },
+            bucket
+        );
+
+        let listing = match self
+            .network
+            .list_key_values(bucket, &query, self.use_network)
+            .await
+        {
+            Ok(listing) => listing,
+            Err(e) if e.downcast_ref::<KeyValueError>().is_some() => {
+                return self.key_value_error(e)
+            }
+            Err(e) => {
+                return s3_error(
+                    StatusCode::BAD_REQUEST,
+                    "InvalidArgument",
+                    &e.to_string(),
+                    self.resource(),
+                )
+            }
+        };
+
+        let mut xml = format!(
+            "<ListBucketResult xmlns=\"{}\"><Name>{}</Name><Prefix>{}</Prefix><MaxKeys>{}</MaxKeys><IsTruncated>{}</IsTruncated>",
+            S3_XMLNS,
+            xml_escape(bucket),
+            xml_escape(&listing.prefix),
+            listing.max_keys,
+            listing.is_truncated
+        );
+        if let Some(delimiter) = &listing.delimiter {
+            xml.push_str(&format!("<Delimiter>{}</Delimiter>", xml_escape(delimiter)));
+        }
+        if v2 {
+            xml.push_str(&format!("<KeyCount>{}</KeyCount>", listing.key_count));
+            for (tag, value) in [
+                ("ContinuationToken", &query.continuation_token),
+                ("NextContinuationToken", &listing.next_continuation_token),
+                ("StartAfter", &query.start_after),
+            ] {
+                if let Some(value) = value {
+                    xml.push_str(&format!("<{0}>{1}</{0}>", tag, xml_escape(value)));
+                }
+            }
+        } else {
+            xml.push_str(&format!(
+                "<Marker>{}</Marker>",
+                xml_escape(query.start_after.as_deref().unwrap_or(""))
+            ));
+            if listing.is_truncated {
+                // The next page starts after the last key or common prefix on this one
+                let last_key = listing.contents.last().map(|object| object.object.as_str());
+                let last_prefix = listing.common_prefixes.last().map(String::as_str);
+                if let Some(next_marker) = last_key.max(last_prefix) {
+                    xml.push_str(&format!(
+                        "<NextMarker>{}</NextMarker>",
+                        xml_escape(next_marker)
+                    ));
+                }
+            }
+        }
+        for object in &listing.contents {
+            xml.push_str(&format!(
+                "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>\"{}\"</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+                xml_escape(&object.object),
+                iso_date(object.last_modified),
+                object.etag,
+                object.size
+            ));
+        }
+        for prefix in &listing.common_prefixes {
+            xml.push_str(&format!(
+                "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+                xml_escape(prefix)
+            ));
+        }
+        xml.push_str("</ListBucketResult>");
+        xml_ok(xml)
+    }
+
+    /// POST /{bucket}?delete - DeleteObjects (missing keys count as deleted, like S3)
+    async fn delete_objects(&self, bucket: &str, body: &Bytes) -> HttpResponse {
+        let Ok(body) = std::str::from_utf8(body) else {
+            return s3_error(
+                StatusCode::BAD_REQUEST,
+                "MalformedXML",
+                "Body is not UTF-8",
+                self.resource(),
+            );
+        };
+        let quiet = xml_elements(body, "Quiet").first().map(|q| q.trim()) == Some("true");
+        let keys: Vec<String> = xml_elements(body, "Object")
+            .into_iter()
+            .filter_map(|object| {
+                xml_elements(object, "Key")
+                    .first()
+                    .map(|key| xml_unescape(key))
+            })
+            .collect();
+        log::info!("🪣 S3 DeleteObjects: {} ({} keys)", bucket, keys.len());
+
+        let mut xml = format!("<DeleteResult xmlns=\"{}\">", S3_XMLNS);
+        for key in keys {
+            match self
+                .network
+                .delete_key_value(bucket, &key, None, self.use_network)
+                .await
+            {
+                Err(e)
+                    if !matches!(
+                        e.downcast_ref::<KeyValueError>(),
+                        Some(KeyValueError::ObjectNotFound)
+                    ) =>
+                {
+                    xml.push_str(&format!(
+                        "<Error><Key>{}</Key><Code>InternalError</Code><Message>{}</Message></Error>",
+                        xml_escape(&key),
+                        xml_escape(&e.to_string())
+                    ));
+                }
+                _ if quiet => {}
+                _ => xml.push_str(&format!(
+                    "<Deleted><Key>{}</Key></Deleted>",
+                    xml_escape(&key)
+                )),
+            }
+        }
+        xml.push_str("</DeleteResult>");
+        xml_ok(xml)
+    }
+
+    // ========================================================================
+    // OBJECTS
+    // ========================================================================
+
+    /// PUT /{bucket}/{key} - PutObject
+    async fn put_object(&self, bucket: &str, key: &str, body: Bytes) -> HttpResponse {
+        log::info!("🪣 S3 PutObject: {}/{} ({} bytes)", bucket, key, body.len());
+        if let Some(response) = self.require_bucket(bucket).await {
+            return response;
+        }
+        let content_type = self.content_type();
+        match self
+            .network
+            .store_key_value_bytes(bucket, key, body, content_type.as_deref(), self.use_network)
+            .await
+        {
+            Ok(_) => {
+                self.stored(bucket, key, |metadata| {
+                    HttpResponse::Ok()
+                        .insert_header((header::ETAG, format!("\"{}\"", metadata.etag)))
+                        .finish()
+                })
+                .await
+            }
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// PUT /{bucket}/{key} with x-amz-copy-source - CopyObject
+    async fn copy_object(&self, bucket: &str, key: &str) -> HttpResponse {
+        let source = self
+            .req
+            .headers()
+            .get("x-amz-copy-source")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        // "/bucket/key" or "bucket/key", URL-encoded, maybe with ?versionId=
+        let source = source.split('?').next().unwrap_or_default();
+        let (source_bucket, source_key) = match split_path(source) {
+            (Some(source_bucket), Some(source_key)) => (source_bucket, source_key),
+            _ => {
+                return s3_error(
+                    StatusCode::BAD_REQUEST,
+                    "InvalidArgument",
+                    "Invalid x-amz-copy-source",
+                    self.resource(),
+                )
+            }
+        };
+        log::info!(
+            "🪣 S3 CopyObject: {}/{} → {}/{}",
+            source_bucket,
+            source_key,
+            bucket,
+            key
+        );
+        if let Some(response) = self.require_bucket(bucket).await {
+            return response;
+        }
+
+        let (content, metadata) = match self
+            .network
+            .get_key_value_bytes(&source_bucket, &source_key, None, self.use_network)
+            .await
+        {
+            Ok(found) => found,
+            Err(e) => return self.key_value_error(e),
+        };
+        let replace = self
+            .req
+            .headers()
+            .get("x-amz-metadata-directive")
+            .is_some_and(|directive| directive.as_bytes().eq_ignore_ascii_case(b"REPLACE"));
+        let content_type = if replace {
+            self.content_type()
+        } else {
+            Some(metadata.content_type)
+        };
+
+        match self
+            .network
+            .store_key_value_bytes(bucket, key, content, content_type.as_deref(), self.use_network)
+            .await
+        {
+            Ok(_) => self.stored(bucket, key, |metadata| {
+                xml_ok(format!(
+                    "<CopyObjectResult xmlns=\"{}\"><LastModified>{}</LastModified><ETag>\"{}\"</ETag></CopyObjectResult>",
+                    S3_XMLNS,
+                    iso_date(metadata.last_modified),
+                    metadata.etag
+                ))
+            })
+            .await,
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// GET /{bucket}/{key} - GetObject (with single `Range: bytes=` support)
+    async fn get_object(&self, bucket: &str, key: &str) -> HttpResponse {
+        log::info!("🪣 S3 GetObject: {}/{}", bucket, key);
+        let version_id = self.param("versionId");
+        let (content, metadata) = match self
+            .network
+            .get_key_value_bytes(bucket, key, version_id.as_deref(), self.use_network)
+            .await
+        {
+            Ok(found) => found,
+            Err(e) => return self.key_value_error(e),
+        };
+
+        let range = self
+            .req
+            .headers()
+            .get(header::RANGE)
+            .and_then(|value| value.to_str().ok());
+        let Some(range) = range else {
+            return metadata_headers(&metadata)
+                .insert_header((header::ACCEPT_RANGES, "bytes"))
+                .body(content);
+        };
+        match parse_range(range, content.len()) {
+            Some((start, end)) => metadata_headers(&metadata)
+                .status(StatusCode::PARTIAL_CONTENT)
+                .insert_header((header::ACCEPT_RANGES, "bytes"))
+                .insert_header((
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, content.len()),
+                ))
+                .body(content.slice(start..=end)),
+            None => s3_error(
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                "InvalidRange",
+                "The requested range is not satisfiable",
+                self.resource(),
+            ),
+        }
+    }
+
+    /// HEAD /{bucket}/{key} - HeadObject
+    async fn head_object(&self, bucket: &str, key: &str) -> HttpResponse {
+        let version_id = self.param("versionId");
+        match self
+            .network
+            .head_key_value(bucket, key, version_id.as_deref(), self.use_network)
+            .await
+        {
+            // A sized (but never sent) body makes Content-Length the object's size
+            Ok(metadata) => metadata_headers(&metadata)
+                .insert_header((header::ACCEPT_RANGES, "bytes"))
+                .body(actix_web::body::SizedStream::new(
+                    metadata.size,
+                    futures::stream::empty::<Result<Bytes, actix_web::Error>>(),
+                )),
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// DELETE /{bucket}/{key} - DeleteObject (deleting a missing key succeeds, like S3)
+    async fn delete_object(&self, bucket: &str, key: &str) -> HttpResponse {
+        log::info!("🪣 S3 DeleteObject: {}/{}", bucket, key);
+        let version_id = self.param("versionId");
+        match self
+            .network
+            .delete_key_value(bucket, key, version_id.as_deref(), self.use_network)
+            .await
+        {
+            Ok(Some(marker_id)) => HttpResponse::NoContent()
+                .insert_header(("x-amz-delete-marker", "true"))
+                .insert_header(("x-amz-version-id", marker_id))
+                .finish(),
+            Ok(None) => HttpResponse::NoContent().finish(),
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<KeyValueError>(),
+                    Some(KeyValueError::ObjectNotFound)
+                ) =>
+            {
+                HttpResponse::NoContent().finish()
+            }
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    // ========================================================================
+    // MULTIPART UPLOADS
+    // ========================================================================
+
+    /// POST /{bucket}/{key}?uploads - CreateMultipartUpload
+    async fn create_upload(&self, bucket: &str, key: &str) -> HttpResponse {
+        if let Some(response) = self.require_bucket(bucket).await {
+            return response;
+        }
+        let content_type = self.content_type();
+        let upload_id = match self
+            .gateway
+            .create_upload(bucket, key, content_type.as_deref())
+            .await
+        {
+            Ok(upload_id) => upload_id,
+            Err(e) => return self.upload_error(e),
+        };
+        xml_ok(format!(
+            "<InitiateMultipartUploadResult xmlns=\"{}\"><Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+            S3_XMLNS,
+            xml_escape(bucket),
+            xml_escape(key),
+            upload_id
+        ))
+    }
+
+    /// PUT /{bucket}/{key}?partNumber=N&uploadId=ID - UploadPart
+    async fn upload_part(&self, bucket: &str, key: &str, body: Bytes) -> HttpResponse {
+        let upload_id = self.param("uploadId").unwrap_or_default();
+        let part_number = match self.param("partNumber").and_then(|n| n.parse::<u32>().ok()) {
+            Some(part_number @ 1..=10000) => part_number,
+            _ => {
+                return s3_error(
+                    StatusCode::BAD_REQUEST,
+                    "InvalidArgument",
+                    "partNumber must be 1-10000",
+                    self.resource(),
+                )
+            }
+        };
+        log::info!(
+            "🪣 S3 UploadPart {} of {}: {} bytes",
+            part_number,
+            upload_id,
+            body.len()
+        );
+        match self
+            .gateway
+            .upload_part(&upload_id, bucket, key, part_number, body)
+            .await
+        {
+            Ok(etag) => HttpResponse::Ok()
+                .insert_header((header::ETAG, format!("\"{}\"", etag)))
+                .finish(),
+            Err(e) => self.upload_error(e),
+        }
+    }
+
+    /// POST /{bucket}/{key}?uploadId=ID - CompleteMultipartUpload
+    async fn complete_upload(&self, bucket: &str, key: &str, body: &Bytes) -> HttpResponse {
+        let upload_id = self.param("uploadId").unwrap_or_default();
+        let Ok(body) = std::str::from_utf8(body) else {
+            return s3_error(
+                StatusCode::BAD_REQUEST,
+                "MalformedXML",
+                "Body is not UTF-8",
+                self.resource(),
+            );
+        };
+        let parts: Option<Vec<(u32, String)>> = xml_elements(body, "Part")
+            .into_iter()
+            .map(|part| {
+                let number = xml_elements(part, "PartNumber")
+                    .first()?
+                    .trim()
+                    .parse()
+                    .ok()?;
+                let etag = xml_unescape(xml_elements(part, "ETag").first()?.trim());
+                Some((number, etag))
+            })
+            .collect();
+        let parts = match parts {
+            Some(parts) if !parts.is_empty() => parts,
+            _ => {
+                return s3_error(
+                    StatusCode::BAD_REQUEST,
+                    "MalformedXML",
+                    "Expected a list of <Part> elements",
+                    self.resource(),
+                )
+            }
+        };
+
+        let (content, content_type) = match self
+            .gateway
+            .complete_upload(&upload_id, bucket, key, &parts)
+            .await
+        {
+            Ok(assembled) => assembled,
+            Err(e) => return self.upload_error(e),
+        };
+        match self
+            .network
+            .store_key_value_bytes(bucket, key, content, content_type.as_deref(), self.use_network)
+            .await
+        {
+            Ok(_) => self.stored(bucket, key, |metadata| {
+                xml_ok(format!(
+                    "<CompleteMultipartUploadResult xmlns=\"{}\"><Location>/{}/{}</Location><Bucket>{}</Bucket><Key>{}</Key><ETag>\"{}\"</ETag></CompleteMultipartUploadResult>",
+                    S3_XMLNS,
+                    xml_escape(bucket),
+                    xml_escape(key),
+                    xml_escape(bucket),
+                    xml_escape(key),
+                    metadata.etag
+                ))
+            })
+            .await,
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    /// DELETE /{bucket}/{key}?uploadId=ID - AbortMultipartUpload
+    async fn abort_upload(&self, bucket: &str, key: &str) -> HttpResponse {
+        let upload_id = self.param("uploadId").unwrap_or_default();
+        match self.gateway.abort_upload(&upload_id, bucket, key).await {
+            Ok(()) => HttpResponse::NoContent().finish(),
+            Err(e) => self.upload_error(e),
+        }
+    }
+
+    // ========================================================================
+    // HELPERS
+    // ========================================================================
+
+    async fn bucket_exists(&self, bucket: &str) -> anyhow::Result<bool> {
+        let buckets = self.network.list_buckets(self.use_network).await?;
+        Ok(buckets.iter().any(|existing| existing.name == bucket))
+    }
+
+    /// S3 never creates buckets implicitly, unlike the JSON key/value API
+    async fn require_bucket(&self, bucket: &str) -> Option<HttpResponse> {
+        match self.bucket_exists(bucket).await {
+            Ok(true) => None,
+            Ok(false) => Some(self.key_value_error(KeyValueError::BucketNotFound.into())),
+            Err(e) => Some(self.key_value_error(e)),
+        }
+    }
+
+    /// Answer a write with the metadata of the object just stored
+    async fn stored(
+        &self,
+        bucket: &str,
+        key: &str,
+        respond: impl FnOnce(KeyValueMetadata) -> HttpResponse,
+    ) -> HttpResponse {
+        match self
+            .network
+            .head_key_value(bucket, key, None, self.use_network)
+            .await
+        {
+            Ok(metadata) => respond(metadata),
+            Err(e) => self.key_value_error(e),
+        }
+    }
+
+    fn content_type(&self) -> Option<String> {
+        self.req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    }
+
+    /// Map key/value errors onto S3 error codes
+    fn key_value_error(&self, e: anyhow::Error) -> HttpResponse {
+        let (status, code) = match e.downcast_ref::<KeyValueError>() {
+            Some(KeyValueError::BucketNotFound) => (StatusCode::NOT_FOUND, "NoSuchBucket"),
+            Some(KeyValueError::ObjectNotFound) => (StatusCode::NOT_FOUND, "NoSuchKey"),
+            Some(KeyValueError::BucketNotEmpty) => (StatusCode::CONFLICT, "BucketNotEmpty"),
+            Some(KeyValueError::VersionNotFound(_)) => (StatusCode::NOT_FOUND, "NoSuchVersion"),
+            None => {
+                log::error!("❌ S3 request failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "InternalError")
+            }
+        };
+        s3_error(status, code, &e.to_string(), self.resource())
+    }
+
+    fn upload_error(&self, e: S3UploadError) -> HttpResponse {
+        let status = match e {
+            S3UploadError::NoSuchUpload => StatusCode::NOT_FOUND,
+            S3UploadError::TooManyUploads => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        s3_error(status, e.code(), &e.to_string(), self.resource())
+    }
+}
+
+/// Helper: Lower-cased headers for signature checking
+fn signed_headers(req: &HttpRequest) -> HashMap<String, String> {
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for (name, value) in req.headers() {
+        let value = String::from_utf8_lossy(value.as_bytes()).trim().to_string();
+        headers
+            .entry(name.as_str().to_string())
+            .and_modify(|existing| {
+                existing.push(',');
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    headers
+}
+
+/// Helper: `/bucket/some/key` → (Some("bucket"), Some("some/key")), percent-decoded
+fn split_path(path: &str) -> (Option<String>, Option<String>) {
+    let decode = |part: &str| percent_decode_str(part).decode_utf8_lossy().into_owned();
+    let path = path.strip_prefix('/').unwrap_or(path);
+    match path.split_once('/') {
+        Some((bucket, key)) if !key.is_empty() => (Some(decode(bucket)), Some(decode(key))),
+        Some((bucket, _)) => (Some(decode(bucket)), None),
+        None if !path.is_empty() => (Some(decode(path)), None),
+        None => (None, None),
+    }
+}
+
+/// Helper: `bytes=0-99`, `bytes=100-` or `bytes=-100` → inclusive (start, end)
+fn parse_range(range: &str, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            (len.checked_sub(suffix.min(len))?, len.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, len.checked_sub(1)?),
+        (start, end) => (
+            start.parse().ok()?,
+            end.parse::<usize>().ok()?.min(len.checked_sub(1)?),
+        ),
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+/// Helper: Inner text of every `<tag>...</tag>` (good enough for S3's small request bodies)
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after_open = &rest[start + open.len()..];
+        let Some(end) = after_open.find(&close) else {
+            break;
+        };
+        found.push(&after_open[..end]);
+        rest = &after_open[end + close.len()..];
+    }
+    found
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Helper: S3 XML dates look like 2026-10-18T12:00:00.000Z
+fn iso_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%dT%H:%M:%S.000Z")
+        .to_string()
+}
+
+fn xml_ok(xml: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/xml")
+        .body(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            xml
+        ))
+}
+
+/// Helper: S3-style XML error body
+fn s3_error(status: StatusCode, code: &str, message: &str, resource: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/xml")
+        .body(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error><Code>{}</Code><Message>{}</Message><Resource>{}</Resource></Error>",
+            code,
+            xml_escape(message),
+            xml_escape(resource)
+        ))
+}
+
+fn not_implemented(req: &HttpRequest) -> HttpResponse {
+    log::warn!(
+        "⚠️  Unsupported S3 request: {} {}?{}",
+        req.method(),
+        req.path(),
+        req.query_string()
+    );
+    s3_error(
+        StatusCode::NOT_IMPLEMENTED,
+        "NotImplemented",
+        "This S3 operation is not supported by the gateway",
+        req.path(),
+    )
+}
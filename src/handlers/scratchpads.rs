@@ -6,14 +6,15 @@
 //! - PUBLIC: Everyone can read (like a bulletin board)
 //! - PRIVATE: Only you can read (like a diary with a lock)
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpResponse};
 use base64::Engine; // Need this to use encode/decode methods
 
+use super::context::RequestContext;
 use crate::models::{
-    ErrorResponse, ScratchpadData, ScratchpadRequest, ScratchpadResponse,
-    ScratchpadUpdateRequest, StoreType,
+    ErrorCode, ErrorResponse, ScratchpadData, ScratchpadRequest, ScratchpadResponse,
+    ScratchpadUpdateRequest,
 };
-use crate::services::NetworkService;
+use crate::services::{NetworkService, ServiceError};
 
 // ============================================================================
 // PUBLIC SCRATCHPAD
@@ -24,14 +25,13 @@ use crate::services::NetworkService;
 /// For Students:
 /// Anyone can read this! Like a public message board
 pub async fn create_public_scratchpad(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<ScratchpadRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("📝 Creating public scratchpad: {}", body.name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     // Validate Base64
     if base64::engine::general_purpose::STANDARD
@@ -39,7 +39,10 @@ pub async fn create_public_scratchpad(
         .is_err()
     {
         log::error!("❌ Invalid Base64");
-        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid Base64 encoding"));
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Invalid Base64 encoding",
+        ));
     }
 
     match network
@@ -52,8 +55,7 @@ pub async fn create_public_scratchpad(
         }
         Err(e) => {
             log::error!("❌ Failed to create scratchpad: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create scratchpad", e.to_string()))
+            ServiceError::from(e).response("Failed to create scratchpad")
         }
     }
 }
@@ -63,7 +65,7 @@ pub async fn create_public_scratchpad(
 /// For Students:
 /// Updates the public message on the board
 pub async fn update_public_scratchpad(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<(String, String)>,
     body: web::Json<ScratchpadUpdateRequest>,
     network: web::Data<NetworkService>,
@@ -71,15 +73,17 @@ pub async fn update_public_scratchpad(
     let (address, name) = path.into_inner();
     log::info!("📝 Updating public scratchpad: {}/{}", address, name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     // Validate Base64
     if base64::engine::general_purpose::STANDARD
         .decode(&body.content)
         .is_err()
     {
-        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid Base64 encoding"));
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Invalid Base64 encoding",
+        ));
     }
 
     match network
@@ -95,8 +99,7 @@ pub async fn update_public_scratchpad(
         }
         Err(e) => {
             log::error!("❌ Failed to update scratchpad: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to update scratchpad", e.to_string()))
+            ServiceError::from(e).response("Failed to update scratchpad")
         }
     }
 }
@@ -106,15 +109,25 @@ pub async fn update_public_scratchpad(
 /// For Students:
 /// Read the public message!
 pub async fn get_public_scratchpad(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<String>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let address = path.into_inner();
     log::info!("📖 Getting public scratchpad: {}", address);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve scratchpad address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network.get_public_scratchpad(&address, use_network).await {
         Ok(content) => {
@@ -123,8 +136,7 @@ pub async fn get_public_scratchpad(
         }
         Err(e) => {
             log::error!("❌ Scratchpad not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Scratchpad not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get scratchpad")
         }
     }
 }
@@ -138,21 +150,23 @@ pub async fn get_public_scratchpad(
 /// For Students:
 /// Encrypted! Only you can read it (if you have the name/key)
 pub async fn create_private_scratchpad(
-    req: HttpRequest,
+    ctx: RequestContext,
     body: web::Json<ScratchpadRequest>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     log::info!("📝 Creating private scratchpad: {}", body.name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     // Validate Base64
     if base64::engine::general_purpose::STANDARD
         .decode(&body.content)
         .is_err()
     {
-        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid Base64 encoding"));
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Invalid Base64 encoding",
+        ));
     }
 
     match network
@@ -165,15 +179,14 @@ pub async fn create_private_scratchpad(
         }
         Err(e) => {
             log::error!("❌ Failed to create private scratchpad: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create scratchpad", e.to_string()))
+            ServiceError::from(e).response("Failed to create scratchpad")
         }
     }
 }
 
 /// PUT /anttp-0/private_scratchpad/{address}/{name} - Update private scratchpad
 pub async fn update_private_scratchpad(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<(String, String)>,
     body: web::Json<ScratchpadUpdateRequest>,
     network: web::Data<NetworkService>,
@@ -181,14 +194,16 @@ pub async fn update_private_scratchpad(
     let (address, name) = path.into_inner();
     log::info!("📝 Updating private scratchpad: {}/{}", address, name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
 
     if base64::engine::general_purpose::STANDARD
         .decode(&body.content)
         .is_err()
     {
-        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid Base64 encoding"));
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "Invalid Base64 encoding",
+        ));
     }
 
     match network
@@ -204,8 +219,7 @@ pub async fn update_private_scratchpad(
         }
         Err(e) => {
             log::error!("❌ Failed to update scratchpad: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to update scratchpad", e.to_string()))
+            ServiceError::from(e).response("Failed to update scratchpad")
         }
     }
 }
@@ -215,15 +229,25 @@ pub async fn update_private_scratchpad(
 /// For Students:
 /// You need the name (like a password) to decrypt it!
 pub async fn get_private_scratchpad(
-    req: HttpRequest,
+    ctx: RequestContext,
     path: web::Path<(String, String)>,
     network: web::Data<NetworkService>,
 ) -> HttpResponse {
     let (address, name) = path.into_inner();
     log::info!("📖 Getting private scratchpad: {}/{}", address, name);
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+
+    let address = match network
+        .resolve_address(&address, use_network, ctx.consistency)
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log::error!("❌ Could not resolve scratchpad address: {}", e);
+            return ServiceError::from(e).response("Failed to resolve name");
+        }
+    };
 
     match network
         .get_private_scratchpad(&address, &name, use_network)
@@ -235,17 +259,8 @@ pub async fn get_private_scratchpad(
         }
         Err(e) => {
             log::error!("❌ Scratchpad not found: {}", e);
-            HttpResponse::NotFound()
-                .json(ErrorResponse::with_details("Scratchpad not found", e.to_string()))
+            ServiceError::from(e).response("Failed to get scratchpad")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
-}
@@ -7,13 +7,15 @@
 //! Think: .tar.gz files you download from GitHub!
 
 use actix_multipart::Multipart;
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpResponse, ResponseError};
 use bytes::Bytes;
 use futures::StreamExt;
 use std::path::PathBuf;
 
-use crate::models::{ArchiveResponse, ErrorResponse, StoreType};
-use crate::services::NetworkService;
+use super::context::RequestContext;
+use crate::config::Config;
+use crate::models::{ArchiveResponse, ErrorCode, ErrorResponse};
+use crate::services::{NetworkService, ServiceError};
 
 /// POST /anttp-0/multipart/tarchive - Create tarchive
 ///
@@ -21,25 +23,29 @@ use crate::services::NetworkService;
 /// Like regular archives but stored in TAR format
 /// TAR = Tape Archive (bundling multiple files into one)
 pub async fn create_tarchive(
-    req: HttpRequest,
+    ctx: RequestContext,
     mut payload: Multipart,
     network: web::Data<NetworkService>,
+    config: web::Data<Config>,
 ) -> HttpResponse {
     log::info!("📦 Creating tarchive from multipart upload");
 
-    let store_type = get_store_type(&req);
-    let use_network = store_type == StoreType::Network;
+    let use_network = ctx.use_network();
+    let max_bytes = config.limits.multipart_body_bytes;
 
     // Parse multipart form data
     let mut files = Vec::new();
+    let mut total: usize = 0;
 
     while let Some(item) = payload.next().await {
         let mut field = match item {
             Ok(field) => field,
             Err(e) => {
                 log::error!("❌ Failed to read multipart field: {}", e);
-                return HttpResponse::BadRequest()
-                    .json(ErrorResponse::new(format!("Invalid multipart data: {}", e)));
+                return HttpResponse::BadRequest().json(ErrorResponse::new(
+                    ErrorCode::InvalidInput,
+                    format!("Invalid multipart data: {}", e),
+                ));
             }
         };
 
@@ -59,12 +65,21 @@ pub async fn create_tarchive(
                 Ok(data) => data,
                 Err(e) => {
                     log::error!("❌ Failed to read chunk: {}", e);
-                    return HttpResponse::BadRequest().json(ErrorResponse::new(format!(
-                        "Failed to read file data: {}",
-                        e
-                    )));
+                    return HttpResponse::BadRequest().json(ErrorResponse::new(
+                        ErrorCode::InvalidInput,
+                        format!("Failed to read file data: {}", e),
+                    ));
                 }
             };
+            total = total.saturating_add(chunk.len());
+            if total > max_bytes {
+                log::error!("❌ Upload is over {} bytes", max_bytes);
+                return ServiceError::PayloadTooLarge(format!(
+                    "Upload is larger than {} bytes (limits.multipart_body_bytes)",
+                    max_bytes
+                ))
+                .error_response();
+            }
             content.extend_from_slice(&chunk);
         }
 
@@ -73,7 +88,10 @@ pub async fn create_tarchive(
 
     if files.is_empty() {
         log::error!("❌ No files in tarchive");
-        return HttpResponse::BadRequest().json(ErrorResponse::new("No files provided"));
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::InvalidInput,
+            "No files provided",
+        ));
     }
 
     log::info!("📦 Tarchive contains {} files", files.len());
@@ -86,17 +104,8 @@ pub async fn create_tarchive(
         }
         Err(e) => {
             log::error!("❌ Failed to create tarchive: {}", e);
-            HttpResponse::InternalServerError()
-                .json(ErrorResponse::with_details("Failed to create tarchive", e.to_string()))
+            ServiceError::from(e).response("Failed to create tarchive")
         }
     }
 }
 
-/// Helper: Extract store type from x-store-type header
-fn get_store_type(req: &HttpRequest) -> StoreType {
-    req.headers()
-        .get("x-store-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse().ok())
-        .unwrap_or_default()
-}
@@ -0,0 +1,1180 @@
+// src/handlers/webdav.rs
+//! WebDAV handlers - Mount archives and key/value buckets as a network drive
+//!
+//! For 1st Year CS Students:
+//! WebDAV is HTTP plus a few extra methods (PROPFIND to list a folder,
+//! MKCOL to make one, MOVE to rename) that file managers understand.
+//! Point a client (Finder, Windows Explorer, rclone, cadaver, davfs2)
+//! at `http://127.0.0.1:18888/webdav/` and you can browse like a disk:
+//!
+//! ```text
+//! /webdav/
+//! ├── archive/{address-or-name}/...   archives (read-only snapshots)
+//! └── kv/{bucket}/...                 key/value buckets (read-write)
+//! ```
+//!
+//! Archives never change, so writing into one (PUT, DELETE, MOVE) builds
+//! a *new* archive and tells you its address in the `X-Archive-Address`
+//! header. Buckets are ordinary writable folders: "directories" are key
+//! prefixes up to a "/", and MKCOL stores an empty "dir/" marker object.
+//!
+//! This is class 1 WebDAV (no LOCK), so macOS Finder mounts it read-only.
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use bytes::Bytes;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use super::context::RequestContext;
+use super::keyvalue::{metadata_headers, validate_bucket_name, validate_object_name};
+use super::xml::xml_escape;
+use crate::models::{
+    ArchiveEntry, Consistency, ErrorCode, ErrorResponse, KeyValueMetadata, ListObjectsQuery,
+};
+use crate::services::{ArchiveChange, ArchiveError, KeyValueError, NetworkService, ServiceError};
+
+/// Everything in a path segment except unreserved characters is escaped in hrefs
+const HREF_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const ALLOWED_METHODS: &str = "OPTIONS, GET, HEAD, PUT, DELETE, MKCOL, MOVE, PROPFIND";
+
+/// Any method under /webdav - work out the resource and run it
+pub async fn webdav_request(
+    req: HttpRequest,
+    ctx: RequestContext,
+    body: web::Bytes,
+    network: web::Data<NetworkService>,
+) -> HttpResponse {
+    let Some(target) = DavTarget::parse(req.path()) else {
+        return HttpResponse::NotFound().json(ErrorResponse::new(
+            ErrorCode::NotFound,
+            "No such WebDAV resource",
+        ));
+    };
+    if let Err(message) = target.validate() {
+        return HttpResponse::BadRequest()
+            .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+    }
+
+    let dav = DavCall {
+        req: &req,
+        network: &network,
+        use_network: ctx.use_network(),
+        consistency: ctx.consistency,
+    };
+
+    match req.method().as_str() {
+        "OPTIONS" => HttpResponse::Ok()
+            .insert_header(("DAV", "1"))
+            .insert_header((header::ALLOW, ALLOWED_METHODS))
+            .insert_header(("MS-Author-Via", "DAV"))
+            .finish(),
+        "PROPFIND" => dav.propfind(&target).await,
+        // HEAD is a GET without a body - actix drops the body for us
+        "GET" | "HEAD" => dav.get(&target).await,
+        "PUT" => dav.put(&target, body).await,
+        "MKCOL" if !body.is_empty() => {
+            HttpResponse::UnsupportedMediaType().json(ErrorResponse::new(
+                ErrorCode::UnsupportedMediaType,
+                "MKCOL request bodies are not supported",
+            ))
+        }
+        "MKCOL" => dav.mkcol(&target).await,
+        "DELETE" => dav.delete(&target).await,
+        "MOVE" => dav.move_to(&target).await,
+        _ => HttpResponse::MethodNotAllowed()
+            .insert_header((header::ALLOW, ALLOWED_METHODS))
+            .json(ErrorResponse::new(
+                ErrorCode::MethodNotAllowed,
+                format!("{} is not supported by this WebDAV server", req.method()),
+            )),
+    }
+}
+
+// ============================================================================
+// RESOURCES
+// ============================================================================
+
+/// Where a WebDAV path points
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DavTarget {
+    /// /webdav/
+    Root,
+    /// /webdav/archive/ - archives can't be listed, only opened by address
+    Archives,
+    /// /webdav/archive/{name}/{path} - `path` is empty for the archive root
+    Archive { name: String, path: String },
+    /// /webdav/kv/
+    Buckets,
+    /// /webdav/kv/{bucket}/{key} - `key` is empty for the bucket root
+    Bucket { bucket: String, key: String },
+}
+
+impl DavTarget {
+    /// Parse a request path (or Destination header path) under /webdav
+    fn parse(path: &str) -> Option<Self> {
+        let rest = path.strip_prefix("/webdav")?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        let segments: Vec<String> = rest
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+            .collect();
+        // No climbing out of a bucket or archive
+        if segments
+            .iter()
+            .any(|segment| segment == "." || segment == "..")
+        {
+            return None;
+        }
+
+        match segments.split_first() {
+            None => Some(Self::Root),
+            Some((area, rest)) => {
+                let (name, path) = match rest.split_first() {
+                    Some((name, path)) => (Some(name.clone()), path.join("/")),
+                    None => (None, String::new()),
+                };
+                match (area.as_str(), name) {
+                    ("archive", None) => Some(Self::Archives),
+                    ("archive", Some(name)) => Some(Self::Archive { name, path }),
+                    ("kv", None) => Some(Self::Buckets),
+                    ("kv", Some(bucket)) => Some(Self::Bucket { bucket, key: path }),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Bucket and object names follow the key/value rules
+    fn validate(&self) -> Result<(), String> {
+        if let Self::Bucket { bucket, key } = self {
+            validate_bucket_name(bucket)?;
+            if !key.is_empty() {
+                validate_object_name(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// URL of this resource; collections end in "/"
+    fn href(&self, collection: bool) -> String {
+        let mut segments: Vec<&str> = Vec::new();
+        match self {
+            Self::Root => {}
+            Self::Archives => segments.push("archive"),
+            Self::Archive { name, path } => {
+                segments.extend(["archive", name.as_str()]);
+                segments.extend(path.split('/').filter(|s| !s.is_empty()));
+            }
+            Self::Buckets => segments.push("kv"),
+            Self::Bucket { bucket, key } => {
+                segments.extend(["kv", bucket.as_str()]);
+                segments.extend(key.split('/').filter(|s| !s.is_empty()));
+            }
+        }
+        let mut href = String::from("/webdav/");
+        for segment in &segments {
+            href.push_str(&utf8_percent_encode(segment, HREF_ENCODE).to_string());
+            href.push('/');
+        }
+        if !collection && !segments.is_empty() {
+            href.pop();
+        }
+        href
+    }
+
+    /// Last path segment, shown as the display name
+    fn name(&self) -> &str {
+        match self {
+            Self::Root => "webdav",
+            Self::Archives => "archive",
+            Self::Buckets => "kv",
+            Self::Archive { name, path } => path
+                .rsplit('/')
+                .next()
+                .filter(|s| !s.is_empty())
+                .unwrap_or(name),
+            Self::Bucket { bucket, key } => key
+                .rsplit('/')
+                .next()
+                .filter(|s| !s.is_empty())
+                .unwrap_or(bucket),
+        }
+    }
+}
+
+/// What a /webdav path touches, as the data type and name a scope checks
+///
+/// For Students:
+/// The drive is only another door to the same data, so a bucket is
+/// checked as `key_value/<bucket>` and an archive as `archive` - a
+/// `write:webdav` token can't write buckets its scopes don't name. Only
+/// the root folder itself is `webdav`.
+pub(crate) fn dav_scope_target(path: &str) -> (&'static str, Option<String>) {
+    match DavTarget::parse(path) {
+        Some(DavTarget::Bucket { bucket, .. }) => ("key_value", Some(bucket)),
+        Some(DavTarget::Buckets) => ("key_value", None),
+        Some(DavTarget::Archive { .. } | DavTarget::Archives) => ("archive", None),
+        Some(DavTarget::Root) | None => ("webdav", None),
+    }
+}
+
+/// Helper: The path of a Destination header (a full URL or just a path)
+pub(crate) fn destination_path(destination: &str) -> Option<&str> {
+    let path = match destination.split_once("://") {
+        Some((_, rest)) => &rest[rest.find('/')?..],
+        None => destination,
+    };
+    path.split(['?', '#']).next()
+}
+
+/// The properties PROPFIND reports for one file or folder
+#[derive(Debug, Clone)]
+struct DavResource {
+    name: String,
+    collection: bool,
+    size: u64,
+    content_type: Option<String>,
+    etag: Option<String>,
+    modified: Option<i64>,
+}
+
+impl DavResource {
+    fn collection(name: &str, modified: Option<i64>) -> Self {
+        Self {
+            name: name.to_string(),
+            collection: true,
+            size: 0,
+            content_type: None,
+            etag: None,
+            modified,
+        }
+    }
+
+    fn object(name: &str, metadata: &KeyValueMetadata) -> Self {
+        Self {
+            name: name.to_string(),
+            collection: false,
+            size: metadata.size,
+            content_type: Some(metadata.content_type.clone()),
+            etag: Some(metadata.etag.clone()),
+            modified: Some(metadata.last_modified),
+        }
+    }
+
+    fn archive_file(name: &str, entry: &ArchiveEntry) -> Self {
+        Self {
+            name: name.to_string(),
+            collection: false,
+            size: entry.size,
+            content_type: Some("application/octet-stream".to_string()),
+            etag: Some(entry.sha256.clone()),
+            modified: Some(entry.modified),
+        }
+    }
+}
+
+/// One WebDAV request
+struct DavCall<'a> {
+    req: &'a HttpRequest,
+    network: &'a NetworkService,
+    use_network: bool,
+    consistency: Consistency,
+}
+
+impl DavCall<'_> {
+    // ========================================================================
+    // READING - PROPFIND and GET
+    // ========================================================================
+
+    /// PROPFIND - Describe a resource (Depth: 0) and its children (Depth: 1)
+    async fn propfind(&self, target: &DavTarget) -> HttpResponse {
+        let depth = self.header("Depth").unwrap_or("infinity");
+        let with_children = match depth {
+            "0" => false,
+            "1" => true,
+            _ => {
+                return HttpResponse::Forbidden()
+                    .content_type("application/xml; charset=utf-8")
+                    .body("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:error xmlns:D=\"DAV:\"><D:propfind-finite-depth/></D:error>");
+            }
+        };
+        log::info!("📂 WebDAV PROPFIND {} (depth {})", self.req.path(), depth);
+
+        let resource = match self.stat(target).await {
+            Ok(Some(resource)) => resource,
+            Ok(None) => return not_found(target),
+            Err(response) => return response,
+        };
+
+        let own_href = target.href(resource.collection);
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">",
+        );
+        push_response(&mut xml, &own_href, &resource);
+        if with_children && resource.collection {
+            let children = match self.children(target).await {
+                Ok(children) => children,
+                Err(response) => return response,
+            };
+            for child in &children {
+                push_response(&mut xml, &child_href(&own_href, child), child);
+            }
+        }
+        xml.push_str("</D:multistatus>");
+
+        HttpResponse::build(StatusCode::MULTI_STATUS)
+            .content_type("application/xml; charset=utf-8")
+            .body(xml)
+    }
+
+    /// GET - A file's content, or a simple HTML index for a folder
+    async fn get(&self, target: &DavTarget) -> HttpResponse {
+        log::info!("📂 WebDAV GET {}", self.req.path());
+        match target {
+            DavTarget::Bucket { bucket, key } if !key.is_empty() => {
+                match self
+                    .network
+                    .get_key_value_bytes(bucket, key, None, self.use_network)
+                    .await
+                {
+                    Ok((content, metadata)) => return metadata_headers(&metadata).body(content),
+                    Err(e) if is_not_found(&e) => {}
+                    Err(e) => return failed("Failed to read object", e),
+                }
+            }
+            DavTarget::Archive { name, path } if !path.is_empty() => {
+                let (_, entries) = match self.archive(name).await {
+                    Ok(archive) => archive,
+                    Err(response) => return response,
+                };
+                if let Some(entry) = entries.iter().find(|entry| &entry.path == path) {
+                    return match self
+                        .network
+                        .get_chunk(&entry.address, self.use_network)
+                        .await
+                    {
+                        Ok(content) => {
+                            let mut response = HttpResponse::Ok();
+                            response
+                                .content_type("application/octet-stream")
+                                .insert_header((header::ETAG, format!("\"{}\"", entry.sha256)));
+                            if let Some(modified) = http_date(entry.modified) {
+                                response.insert_header((header::LAST_MODIFIED, modified));
+                            }
+                            response.body(content)
+                        }
+                        Err(e) => failed("Failed to read archive file", e),
+                    };
+                }
+            }
+            _ => {}
+        }
+
+        // Not a file - maybe a folder
+        match self.stat(target).await {
+            Ok(Some(resource)) if resource.collection => {}
+            Ok(_) => return not_found(target),
+            Err(response) => return response,
+        }
+        let children = match self.children(target).await {
+            Ok(children) => children,
+            Err(response) => return response,
+        };
+
+        let own_href = target.href(true);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Index of {0}</title></head><body><h1>Index of {0}</h1><ul>",
+            xml_escape(self.req.path())
+        );
+        if *target != DavTarget::Root {
+            html.push_str("<li><a href=\"../\">../</a></li>");
+        }
+        for child in &children {
+            let suffix = if child.collection { "/" } else { "" };
+            html.push_str(&format!(
+                "<li><a href=\"{}\">{}{}</a></li>",
+                xml_escape(&child_href(&own_href, child)),
+                xml_escape(&child.name),
+                suffix
+            ));
+        }
+        html.push_str("</ul></body></html>");
+        HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(html)
+    }
+
+    /// What is at `target`? `None` if nothing is
+    async fn stat(&self, target: &DavTarget) -> Result<Option<DavResource>, HttpResponse> {
+        match target {
+            DavTarget::Root | DavTarget::Archives | DavTarget::Buckets => {
+                Ok(Some(DavResource::collection(target.name(), None)))
+            }
+            DavTarget::Archive { name, path } => {
+                let entries = match self.archive(name).await {
+                    Ok((_, entries)) => entries,
+                    Err(_) => return Ok(None),
+                };
+                if path.is_empty() {
+                    return Ok(Some(DavResource::collection(name, None)));
+                }
+                if let Some(entry) = entries.iter().find(|entry| &entry.path == path) {
+                    return Ok(Some(DavResource::archive_file(target.name(), entry)));
+                }
+                let folder = format!("{}/", path);
+                let newest = entries
+                    .iter()
+                    .filter(|entry| entry.path.starts_with(&folder))
+                    .map(|entry| entry.modified)
+                    .max();
+                Ok(newest.map(|modified| DavResource::collection(target.name(), Some(modified))))
+            }
+            DavTarget::Bucket { bucket, key } if key.is_empty() => {
+                match self.network.list_buckets(self.use_network).await {
+                    Ok(buckets) => Ok(buckets
+                        .iter()
+                        .find(|found| &found.name == bucket)
+                        .map(|found| DavResource::collection(bucket, Some(found.created)))),
+                    Err(e) => Err(failed("Failed to list buckets", e)),
+                }
+            }
+            DavTarget::Bucket { bucket, key } => {
+                match self
+                    .network
+                    .head_key_value(bucket, key, None, self.use_network)
+                    .await
+                {
+                    Ok(metadata) => return Ok(Some(DavResource::object(target.name(), &metadata))),
+                    Err(e) if is_not_found(&e) => {}
+                    Err(e) => return Err(failed("Failed to read object", e)),
+                }
+                // A folder is any prefix with something under it
+                let query = ListObjectsQuery {
+                    prefix: Some(format!("{}/", key)),
+                    max_keys: Some(1),
+                    ..Default::default()
+                };
+                match self
+                    .network
+                    .list_key_values(bucket, &query, self.use_network)
+                    .await
+                {
+                    Ok(page) if page.key_count > 0 => {
+                        Ok(Some(DavResource::collection(target.name(), None)))
+                    }
+                    Ok(_) => Ok(None),
+                    Err(e) if is_not_found(&e) => Ok(None),
+                    Err(e) => Err(failed("Failed to list objects", e)),
+                }
+            }
+        }
+    }
+
+    /// The files and folders directly inside a collection
+    async fn children(&self, target: &DavTarget) -> Result<Vec<DavResource>, HttpResponse> {
+        match target {
+            DavTarget::Root => Ok(vec![
+                DavResource::collection("archive", None),
+                DavResource::collection("kv", None),
+            ]),
+            DavTarget::Archives => Ok(Vec::new()),
+            DavTarget::Archive { name, path } => {
+                let (_, entries) = self.archive(name).await?;
+                let prefix = folder_prefix(path);
+                let mut children: Vec<DavResource> = Vec::new();
+                for entry in &entries {
+                    let Some(rest) = entry.path.strip_prefix(&prefix) else {
+                        continue;
+                    };
+                    match rest.split_once('/') {
+                        Some((folder, _)) => {
+                            match children.iter_mut().find(|child| child.name == folder) {
+                                Some(existing) => {
+                                    existing.modified = existing.modified.max(Some(entry.modified))
+                                }
+                                None => children
+                                    .push(DavResource::collection(folder, Some(entry.modified))),
+                            }
+                        }
+                        None => children.push(DavResource::archive_file(rest, entry)),
+                    }
+                }
+                Ok(children)
+            }
+            DavTarget::Buckets => match self.network.list_buckets(self.use_network).await {
+                Ok(buckets) => Ok(buckets
+                    .iter()
+                    .map(|bucket| DavResource::collection(&bucket.name, Some(bucket.created)))
+                    .collect()),
+                Err(e) => Err(failed("Failed to list buckets", e)),
+            },
+            DavTarget::Bucket { bucket, key } => {
+                let prefix = folder_prefix(key);
+                let mut children = Vec::new();
+                let mut query = ListObjectsQuery {
+                    prefix: Some(prefix.clone()),
+                    delimiter: Some("/".to_string()),
+                    ..Default::default()
+                };
+                loop {
+                    let page = match self
+                        .network
+                        .list_key_values(bucket, &query, self.use_network)
+                        .await
+                    {
+                        Ok(page) => page,
+                        Err(e) if is_not_found(&e) => return Err(not_found(target)),
+                        Err(e) => return Err(failed("Failed to list objects", e)),
+                    };
+                    for object in &page.contents {
+                        // The folder's own MKCOL marker isn't a child
+                        if object.object != prefix {
+                            children
+                                .push(DavResource::object(&object.object[prefix.len()..], object));
+                        }
+                    }
+                    for common_prefix in &page.common_prefixes {
+                        let folder = common_prefix[prefix.len()..].trim_end_matches('/');
+                        children.push(DavResource::collection(folder, None));
+                    }
+                    match page.next_continuation_token {
+                        Some(token) if page.is_truncated => query.continuation_token = Some(token),
+                        _ => break,
+                    }
+                }
+                Ok(children)
+            }
+        }
+    }
+
+    // ========================================================================
+    // WRITING - PUT, MKCOL, DELETE and MOVE
+    // ========================================================================
+
+    /// PUT - Store a file (a new archive for archive paths)
+    async fn put(&self, target: &DavTarget, body: Bytes) -> HttpResponse {
+        log::info!("📂 WebDAV PUT {} ({} bytes)", self.req.path(), body.len());
+        match target {
+            DavTarget::Bucket { bucket, key } if !key.is_empty() => {
+                let existing = match self.stat(target).await {
+                    Ok(existing) => existing,
+                    Err(response) => return response,
+                };
+                if existing
+                    .as_ref()
+                    .is_some_and(|resource| resource.collection)
+                {
+                    return collection_conflict(target);
+                }
+                match self
+                    .stat(&DavTarget::Bucket {
+                        bucket: bucket.clone(),
+                        key: String::new(),
+                    })
+                    .await
+                {
+                    Ok(Some(_)) => {}
+                    Ok(None) => {
+                        return HttpResponse::Conflict().json(ErrorResponse::new(
+                            ErrorCode::Conflict,
+                            format!("Bucket not found: {}", bucket),
+                        ))
+                    }
+                    Err(response) => return response,
+                }
+
+                let content_type = self.header("Content-Type");
+                match self
+                    .network
+                    .store_key_value_bytes(bucket, key, body, content_type, self.use_network)
+                    .await
+                {
+                    Ok(_) if existing.is_some() => HttpResponse::NoContent().finish(),
+                    Ok(_) => HttpResponse::Created().finish(),
+                    Err(e) => failed("Failed to store object", e),
+                }
+            }
+            DavTarget::Archive { name, path } if !path.is_empty() => {
+                let change = ArchiveChange::Put {
+                    path: path.clone(),
+                    content: body,
+                    modified: None,
+                };
+                self.update_archive(name, vec![change], Some(path), StatusCode::CREATED)
+                    .await
+            }
+            _ => collection_conflict(target),
+        }
+    }
+
+    /// MKCOL - Create a bucket, or a folder inside one
+    async fn mkcol(&self, target: &DavTarget) -> HttpResponse {
+        log::info!("📂 WebDAV MKCOL {}", self.req.path());
+        match self.stat(target).await {
+            Ok(Some(_)) => {
+                return HttpResponse::MethodNotAllowed().json(ErrorResponse::new(
+                    ErrorCode::MethodNotAllowed,
+                    format!("Already exists: {}", self.req.path()),
+                ))
+            }
+            Ok(None) => {}
+            Err(response) => return response,
+        }
+
+        match target {
+            DavTarget::Bucket { bucket, key } if key.is_empty() => {
+                match self
+                    .network
+                    .create_bucket(bucket, None, self.use_network)
+                    .await
+                {
+                    Ok(_) => HttpResponse::Created().finish(),
+                    Err(e) => failed("Failed to create bucket", e),
+                }
+            }
+            DavTarget::Bucket { bucket, key } => {
+                // The parent has to exist, like `mkdir` without `-p`
+                let parent = match key.rsplit_once('/') {
+                    Some((parent, _)) => parent.to_string(),
+                    None => String::new(),
+                };
+                let parent = DavTarget::Bucket {
+                    bucket: bucket.clone(),
+                    key: parent,
+                };
+                match self.stat(&parent).await {
+                    Ok(Some(resource)) if resource.collection => {}
+                    Ok(_) => {
+                        return HttpResponse::Conflict().json(ErrorResponse::new(
+                            ErrorCode::Conflict,
+                            "Parent folder does not exist",
+                        ))
+                    }
+                    Err(response) => return response,
+                }
+
+                let marker = format!("{}/", key);
+                match self
+                    .network
+                    .store_key_value_bytes(bucket, &marker, Bytes::new(), None, self.use_network)
+                    .await
+                {
+                    Ok(_) => HttpResponse::Created().finish(),
+                    Err(e) => failed("Failed to create folder", e),
+                }
+            }
+            DavTarget::Archive { .. } => HttpResponse::Forbidden().json(ErrorResponse::new(
+                ErrorCode::Forbidden,
+                "Archives only hold files - PUT a file to create its folder",
+            )),
+            _ => HttpResponse::Forbidden().json(ErrorResponse::new(
+                ErrorCode::Forbidden,
+                "Collections can only be created under /webdav/kv/",
+            )),
+        }
+    }
+
+    /// DELETE - Remove a file or a whole folder (a new archive for archive paths)
+    async fn delete(&self, target: &DavTarget) -> HttpResponse {
+        log::info!("📂 WebDAV DELETE {}", self.req.path());
+        match target {
+            DavTarget::Bucket { bucket, key } => {
+                let resource = match self.stat(target).await {
+                    Ok(Some(resource)) => resource,
+                    Ok(None) => return not_found(target),
+                    Err(response) => return response,
+                };
+                // Deleting its files would only hide them behind delete markers,
+                // and the bucket still couldn't go - refuse before touching anything
+                if key.is_empty() {
+                    match self
+                        .network
+                        .bucket_keeps_versions(bucket, self.use_network)
+                        .await
+                    {
+                        Ok(false) => {}
+                        Ok(true) => {
+                            return HttpResponse::Conflict().json(ErrorResponse::new(
+                                ErrorCode::Conflict,
+                                format!(
+                                    "Bucket {} keeps versions - delete them through the API first",
+                                    bucket
+                                ),
+                            ))
+                        }
+                        Err(e) => return failed("Failed to delete bucket", e),
+                    }
+                }
+                if let Err(response) = self.delete_objects(bucket, key, resource.collection).await {
+                    return response;
+                }
+                if key.is_empty() {
+                    if let Err(e) = self.network.delete_bucket(bucket, self.use_network).await {
+                        return failed("Failed to delete bucket", e);
+                    }
+                }
+                HttpResponse::NoContent().finish()
+            }
+            DavTarget::Archive { name, path } if !path.is_empty() => {
+                let change = ArchiveChange::Delete { path: path.clone() };
+                self.update_archive(name, vec![change], None, StatusCode::NO_CONTENT)
+                    .await
+            }
+            _ => HttpResponse::Forbidden().json(ErrorResponse::new(
+                ErrorCode::Forbidden,
+                format!("{} can't be deleted", self.req.path()),
+            )),
+        }
+    }
+
+    /// MOVE - Rename within a bucket space or within one archive
+    async fn move_to(&self, source: &DavTarget) -> HttpResponse {
+        let Some(destination) = self.destination() else {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::InvalidInput,
+                "Missing or invalid Destination header",
+            ));
+        };
+        if let Err(message) = destination.validate() {
+            return HttpResponse::BadRequest()
+                .json(ErrorResponse::new(ErrorCode::InvalidInput, message));
+        }
+        log::info!(
+            "📂 WebDAV MOVE {} → {}",
+            self.req.path(),
+            destination.href(false)
+        );
+        if destination == *source {
+            return HttpResponse::Forbidden().json(ErrorResponse::new(
+                ErrorCode::Forbidden,
+                "Source and destination are the same",
+            ));
+        }
+        let overwrite = !self
+            .header("Overwrite")
+            .is_some_and(|value| value.eq_ignore_ascii_case("F"));
+
+        let resource = match self.stat(source).await {
+            Ok(Some(resource)) => resource,
+            Ok(None) => return not_found(source),
+            Err(response) => return response,
+        };
+        let existing = match self.stat(&destination).await {
+            Ok(existing) => existing,
+            Err(response) => return response,
+        };
+        if existing.is_some() && !overwrite {
+            return HttpResponse::PreconditionFailed().json(ErrorResponse::new(
+                ErrorCode::PreconditionFailed,
+                "Destination exists and Overwrite is F",
+            ));
+        }
+        let status = if existing.is_some() {
+            StatusCode::NO_CONTENT
+        } else {
+            StatusCode::CREATED
+        };
+
+        match (source, &destination) {
+            (
+                DavTarget::Bucket { bucket, key },
+                DavTarget::Bucket {
+                    bucket: to_bucket,
+                    key: to_key,
+                },
+            ) if !key.is_empty() && !to_key.is_empty() => {
+                if resource.collection && to_bucket == bucket && is_inside(to_key, key) {
+                    return HttpResponse::Forbidden().json(ErrorResponse::new(
+                        ErrorCode::Forbidden,
+                        "Can't move a folder into itself",
+                    ));
+                }
+                if let Some(existing) = &existing {
+                    if let Err(response) = self
+                        .delete_objects(to_bucket, to_key, existing.collection)
+                        .await
+                    {
+                        return response;
+                    }
+                }
+                match self
+                    .move_objects(bucket, key, to_bucket, to_key, resource.collection)
+                    .await
+                {
+                    Ok(()) => HttpResponse::build(status).finish(),
+                    Err(response) => response,
+                }
+            }
+            (
+                DavTarget::Archive { name, path },
+                DavTarget::Archive {
+                    name: to_name,
+                    path: to_path,
+                },
+            ) if name == to_name && !path.is_empty() && !to_path.is_empty() => {
+                let mut changes = Vec::new();
+                if existing.is_some() {
+                    changes.push(ArchiveChange::Delete {
+                        path: to_path.clone(),
+                    });
+                }
+                changes.push(ArchiveChange::Rename {
+                    from: path.clone(),
+                    to: to_path.clone(),
+                });
+                self.update_archive(name, changes, Some(to_path), status)
+                    .await
+            }
+            _ => HttpResponse::Forbidden().json(ErrorResponse::new(
+                ErrorCode::Forbidden,
+                "MOVE works between paths in /webdav/kv/, or within one archive",
+            )),
+        }
+    }
+
+    /// Delete one object, or every object under a folder
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        key: &str,
+        collection: bool,
+    ) -> Result<(), HttpResponse> {
+        let keys = if collection {
+            self.keys_under(bucket, &folder_prefix(key)).await?
+        } else {
+            vec![key.to_string()]
+        };
+        for key in keys {
+            if let Err(e) = self
+                .network
+                .delete_key_value(bucket, &key, None, self.use_network)
+                .await
+            {
+                return Err(failed("Failed to delete object", e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy then delete - one object, or every object under a folder
+    ///
+    /// For Students:
+    /// Every object is copied before any source is deleted, so a failed
+    /// copy leaves the sources as they were (the copies made so far are
+    /// removed again). If deleting a source fails, the move is done but
+    /// that object is at both paths - the 207 answer lists each one.
+    async fn move_objects(
+        &self,
+        bucket: &str,
+        key: &str,
+        to_bucket: &str,
+        to_key: &str,
+        collection: bool,
+    ) -> Result<(), HttpResponse> {
+        let moves: Vec<(String, String)> = if collection {
+            let (from, to) = (folder_prefix(key), folder_prefix(to_key));
+            self.keys_under(bucket, &from)
+                .await?
+                .into_iter()
+                .map(|key| (format!("{}{}", to, &key[from.len()..]), key))
+                .collect()
+        } else {
+            vec![(to_key.to_string(), key.to_string())]
+        };
+
+        for (index, (to, from)) in moves.iter().enumerate() {
+            if let Err(e) = self.copy_object(bucket, from, to_bucket, to).await {
+                for (copied, _) in &moves[..index] {
+                    if let Err(e) = self
+                        .network
+                        .delete_key_value(to_bucket, copied, None, self.use_network)
+                        .await
+                    {
+                        log::warn!(
+                            "⚠️ Couldn't remove partial copy {}/{}: {}",
+                            to_bucket,
+                            copied,
+                            e
+                        );
+                    }
+                }
+                return Err(failed("Failed to copy object", e));
+            }
+        }
+
+        let mut left_behind = Vec::new();
+        for (_, from) in &moves {
+            if let Err(e) = self
+                .network
+                .delete_key_value(bucket, from, None, self.use_network)
+                .await
+            {
+                log::error!("❌ Moved {}/{} but couldn't delete it: {}", bucket, from, e);
+                left_behind.push((from.as_str(), ServiceError::from(e).status_code()));
+            }
+        }
+        if left_behind.is_empty() {
+            return Ok(());
+        }
+
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">",
+        );
+        for (from, status) in left_behind {
+            let href = DavTarget::Bucket {
+                bucket: bucket.to_string(),
+                key: from.to_string(),
+            }
+            .href(false);
+            xml.push_str(&format!(
+                "<D:response><D:href>{}</D:href><D:status>HTTP/1.1 {}</D:status></D:response>",
+                xml_escape(&href),
+                status
+            ));
+        }
+        xml.push_str("</D:multistatus>");
+        Err(HttpResponse::build(StatusCode::MULTI_STATUS)
+            .content_type("application/xml; charset=utf-8")
+            .body(xml))
+    }
+
+    /// Helper: Copy one object (content and Content-Type) to another key
+    async fn copy_object(
+        &self,
+        bucket: &str,
+        from: &str,
+        to_bucket: &str,
+        to: &str,
+    ) -> anyhow::Result<()> {
+        let (content, metadata) = self
+            .network
+            .get_key_value_bytes(bucket, from, None, self.use_network)
+            .await?;
+        self.network
+            .store_key_value_bytes(
+                to_bucket,
+                to,
+                content,
+                Some(&metadata.content_type),
+                self.use_network,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Every key starting with `prefix`, across all pages
+    async fn keys_under(&self, bucket: &str, prefix: &str) -> Result<Vec<String>, HttpResponse> {
+        let mut keys = Vec::new();
+        let mut query = ListObjectsQuery {
+            prefix: Some(prefix.to_string()),
+            ..Default::default()
+        };
+        loop {
+            let page = match self
+                .network
+                .list_key_values(bucket, &query, self.use_network)
+                .await
+            {
+                Ok(page) => page,
+                Err(e) => return Err(failed("Failed to list objects", e)),
+            };
+            keys.extend(page.contents.into_iter().map(|object| object.object));
+            match page.next_continuation_token {
+                Some(token) if page.is_truncated => query.continuation_token = Some(token),
+                _ => break,
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Apply changes to an archive and point the client at the new one
+    async fn update_archive(
+        &self,
+        name: &str,
+        changes: Vec<ArchiveChange>,
+        path: Option<&str>,
+        status: StatusCode,
+    ) -> HttpResponse {
+        let (address, _) = match self.archive(name).await {
+            Ok(archive) => archive,
+            Err(response) => return response,
+        };
+        match self
+            .network
+            .update_archive(&address, changes, self.use_network)
+            .await
+        {
+            Ok((new_address, _)) => {
+                log::info!("✅ WebDAV wrote new archive: {}", new_address);
+                let location = DavTarget::Archive {
+                    name: new_address.clone(),
+                    path: path.unwrap_or_default().to_string(),
+                }
+                .href(path.is_none());
+                HttpResponse::build(status)
+                    .insert_header(("X-Archive-Address", new_address))
+                    .insert_header((header::LOCATION, location))
+                    .finish()
+            }
+            // "Overwrite: F" onto a taken path is a failed precondition in WebDAV
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<ArchiveError>(),
+                    Some(ArchiveError::PathExists(_))
+                ) =>
+            {
+                HttpResponse::PreconditionFailed().json(ErrorResponse::with_details(
+                    ErrorCode::PreconditionFailed,
+                    "Failed to update archive",
+                    e.to_string(),
+                ))
+            }
+            Err(e) => failed("Failed to update archive", e),
+        }
+    }
+
+    // ========================================================================
+    // HELPERS
+    // ========================================================================
+
+    /// Resolve an archive address or PNR name and read its manifest
+    async fn archive(&self, name: &str) -> Result<(String, Vec<ArchiveEntry>), HttpResponse> {
+        let address = match self
+            .network
+            .resolve_address(name, self.use_network, self.consistency)
+            .await
+        {
+            Ok(address) => address,
+            Err(e) => return Err(ServiceError::from(e).response("Failed to resolve name")),
+        };
+        match self
+            .network
+            .get_archive_manifest(&address, self.use_network)
+            .await
+        {
+            Ok(entries) => Ok((address, entries)),
+            Err(e) => Err(ServiceError::from(e).response("Failed to get archive")),
+        }
+    }
+
+    /// Destination header - a full URL or just a path
+    fn destination(&self) -> Option<DavTarget> {
+        DavTarget::parse(destination_path(self.header("Destination")?)?)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.req
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+}
+
+/// Helper: One `<D:response>` element of a multistatus body
+fn push_response(xml: &mut String, href: &str, resource: &DavResource) {
+    xml.push_str(&format!(
+        "<D:response><D:href>{}</D:href><D:propstat><D:prop><D:displayname>{}</D:displayname>",
+        xml_escape(href),
+        xml_escape(&resource.name)
+    ));
+    if resource.collection {
+        xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+    } else {
+        xml.push_str(&format!(
+            "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength>",
+            resource.size
+        ));
+    }
+    if let Some(content_type) = &resource.content_type {
+        xml.push_str(&format!(
+            "<D:getcontenttype>{}</D:getcontenttype>",
+            xml_escape(content_type)
+        ));
+    }
+    if let Some(etag) = &resource.etag {
+        xml.push_str(&format!("<D:getetag>\"{}\"</D:getetag>", xml_escape(etag)));
+    }
+    if let Some(modified) = resource.modified.and_then(http_date) {
+        xml.push_str(&format!(
+            "<D:getlastmodified>{}</D:getlastmodified>",
+            modified
+        ));
+    }
+    xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>");
+}
+
+/// Helper: href of a child given its collection's href (ending in "/")
+fn child_href(parent: &str, child: &DavResource) -> String {
+    let suffix = if child.collection { "/" } else { "" };
+    format!(
+        "{}{}{}",
+        parent,
+        utf8_percent_encode(&child.name, HREF_ENCODE),
+        suffix
+    )
+}
+
+/// Helper: "" stays "", "docs" becomes "docs/"
+fn folder_prefix(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", path)
+    }
+}
+
+/// Helper: Is `path` the folder `folder` or somewhere inside it?
+fn is_inside(path: &str, folder: &str) -> bool {
+    path == folder || path.starts_with(&folder_prefix(folder))
+}
+
+/// Helper: Unix timestamp → RFC 1123 date, as HTTP and WebDAV expect
+fn http_date(timestamp: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+}
+
+/// Helper: Missing bucket or object
+fn is_not_found(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<KeyValueError>(),
+        Some(KeyValueError::BucketNotFound | KeyValueError::ObjectNotFound)
+    )
+}
+
+fn not_found(target: &DavTarget) -> HttpResponse {
+    HttpResponse::NotFound().json(ErrorResponse::new(
+        ErrorCode::NotFound,
+        format!("Not found: {}", target.href(false)),
+    ))
+}
+
+/// Helper: PUT on a folder (or somewhere that can't hold files)
+fn collection_conflict(target: &DavTarget) -> HttpResponse {
+    HttpResponse::MethodNotAllowed()
+        .insert_header((
+            header::ALLOW,
+            "OPTIONS, GET, HEAD, DELETE, MKCOL, MOVE, PROPFIND",
+        ))
+        .json(ErrorResponse::new(
+            ErrorCode::MethodNotAllowed,
+            format!("{} is a folder", target.href(true)),
+        ))
+}
+
+/// Helper: Map service errors to WebDAV statuses (logging unexpected ones)
+fn failed(context: &str, e: anyhow::Error) -> HttpResponse {
+    let error = ServiceError::from(e);
+    if error.status_code().is_server_error() {
+        log::error!("❌ {}: {}", context, error);
+    }
+    error.response(context)
+}
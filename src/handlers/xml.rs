@@ -0,0 +1,12 @@
+// src/handlers/xml.rs
+//! XML helpers shared by the XML-speaking handlers (S3, WebDAV, GraphML)
+
+/// Escape text for XML content and attribute values
+pub(crate) fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,138 @@
+// src/lib.rs
+//! AntTP-Compatible Rust Backend - the server as a library
+//!
+//! For 1st Year CS Students:
+//! `main.rs` loads the settings (`config.rs`) and starts the listeners; everything
+//! else lives here. That way other crates can use the same code: the
+//! client SDK (`client/`) reuses the `models` types, and its tests start
+//! a real API server inside the test process with `api_server`.
+
+pub mod config;
+pub mod cors;
+pub mod handlers;
+pub mod models;
+pub mod openapi;
+pub mod routes;
+pub mod services;
+
+use actix_web::dev::Server;
+use actix_web::{middleware, web, App, HttpResponse, HttpServer};
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use config::{Config, ConfigError};
+use handlers::{AuthPolicy, RequestDefaults};
+use services::{NetworkService, PublishConfig, ServiceError, TokenStore};
+
+/// Health check endpoint
+pub async fn health_check() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "healthy",
+        "version": env!("CARGO_PKG_VERSION"),
+        "description": "AntTP-compatible Rust backend"
+    }))
+}
+
+/// Everything the API listener shares between requests
+#[derive(Clone)]
+pub struct ApiState {
+    pub network: Arc<NetworkService>,
+    pub config: Arc<Config>,
+    /// Server-side directory publishing (`None` = disabled)
+    pub publish: Option<PublishConfig>,
+    /// Certificate and key for HTTPS (`None` = plain HTTP)
+    pub tls: Option<rustls::ServerConfig>,
+    /// API tokens (checked only when `auth.enabled`)
+    pub tokens: Arc<TokenStore>,
+    /// What each route needs from a token
+    pub auth: Arc<AuthPolicy>,
+}
+
+impl ApiState {
+    /// The state for a (validated) configuration - reads the TLS files
+    /// and the tokens issued before the last restart
+    pub fn new(network: Arc<NetworkService>, config: Arc<Config>) -> Result<Self, ConfigError> {
+        Ok(Self {
+            publish: PublishConfig::from_config(&config)?,
+            tls: config.tls.load()?,
+            tokens: Arc::new(TokenStore::load(
+                &config.auth,
+                config.storage.data_dir.as_deref(),
+            )?),
+            auth: Arc::new(AuthPolicy::from_config(&config)?),
+            network,
+            config,
+        })
+    }
+}
+
+/// Start the API (every route in `routes.rs`) on an already bound listener
+///
+/// For Students:
+/// Taking a listener instead of an address lets tests bind to port 0 -
+/// "any free port" - and ask the listener which port they got.
+pub fn api_server(listener: TcpListener, state: ApiState) -> std::io::Result<Server> {
+    // Share network service across all requests
+    let service_data = web::Data::from(state.network);
+    let publish_data = web::Data::new(state.publish);
+    let defaults_data = web::Data::new(RequestDefaults::from_config(&state.config));
+    let tokens_data = web::Data::from(state.tokens);
+    let auth_data = web::Data::from(state.auth);
+    let workers = state.config.server.workers;
+    let config = state.config.clone();
+    let config_data = web::Data::from(state.config);
+
+    let server = HttpServer::new(move || {
+        let features = &config.features;
+        App::new()
+            // Add middleware
+            // Bearer tokens (inside CORS, so refusals still reach browser pages)
+            .wrap(middleware::Condition::new(
+                config.auth.enabled,
+                middleware::from_fn(handlers::authenticate),
+            ))
+            .wrap(cors::cors(&config))
+            // gzip/brotli/zstd responses for clients that send Accept-Encoding
+            .wrap(middleware::Condition::new(
+                features.compression,
+                middleware::Compress::default(),
+            ))
+            .wrap(middleware::from_fn(handlers::echo_request_id))
+            // Default format plus the request ID, to find a client's request in the log
+            .wrap(middleware::Condition::new(
+                config.logging.access_log,
+                middleware::Logger::new(
+                    r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{x-request-id}o"#,
+                ),
+            ))
+            .app_data(service_data.clone())
+            .app_data(publish_data.clone())
+            .app_data(defaults_data.clone())
+            .app_data(config_data.clone())
+            .app_data(tokens_data.clone())
+            .app_data(auth_data.clone())
+            // Body limits (routes with bigger uploads set their own, see routes.rs)
+            .app_data(web::PayloadConfig::new(config.limits.binary_body_bytes))
+            // Bad JSON bodies and query strings get the same error shape as handler errors
+            .app_data(
+                web::JsonConfig::default()
+                    .limit(config.limits.json_body_bytes)
+                    .error_handler(|err, _| ServiceError::InvalidInput(err.to_string()).into()),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|err, _| ServiceError::InvalidInput(err.to_string()).into()),
+            )
+            // Every endpoint, from the route table (also used for /openapi.json)
+            .configure(|cfg| routes::configure(cfg, &config))
+    });
+    let server = match workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+    let server = match state.tls {
+        Some(tls) => server.listen_rustls_0_23(listener, tls)?,
+        None => server.listen(listener)?,
+    };
+    Ok(server.run())
+}
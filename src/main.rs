@@ -6,40 +6,51 @@
 //! It's like opening a restaurant - we set up tables (routes)
 //! and hire staff (handlers) to serve customers (HTTP requests)!
 
-mod handlers;
-mod models;
-mod services;
-
-use actix_cors::Cors;
-use actix_web::{middleware, web, App, HttpResponse, HttpServer};
-use services::NetworkService;
+use actix_web::{middleware, web, App, HttpServer};
+use anttp_rust_backend::config::{Config, LogFormat, LoggingConfig, ServerArgs};
+use anttp_rust_backend::services::{DnsConfig, DnsServer, NetworkService, S3Config, S3Gateway};
+use anttp_rust_backend::{api_server, handlers, routes, ApiState};
+use clap::Parser;
+use std::fmt::Display;
+use std::io::Write;
+use std::net::TcpListener;
 use std::sync::Arc;
 
-/// Health check endpoint
-async fn health_check() -> HttpResponse {
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "healthy",
-        "version": env!("CARGO_PKG_VERSION"),
-        "description": "AntTP-compatible Rust backend"
-    }))
-}
-
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    // Settings: defaults < config file < environment < command line
+    let args = ServerArgs::parse();
+    let config = Config::load(&args).unwrap_or_else(|e| config_error(e));
+    if args.print_config {
+        let text = toml::to_string_pretty(&config.redacted()).unwrap_or_else(|e| config_error(e));
+        print!("{}", text);
+        return Ok(());
+    }
+
     // Initialize logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    init_logging(&config.logging);
 
     log::info!("🦀 ════════════════════════════════════════════════════════");
     log::info!("🦀  AntTP-Compatible Rust Backend");
     log::info!("🦀  Version: {}", env!("CARGO_PKG_VERSION"));
     log::info!("🦀 ════════════════════════════════════════════════════════");
+    match &args.config {
+        Some(path) => log::info!("⚙️  Configuration: {}", path.display()),
+        None => log::info!("⚙️  Configuration: defaults and environment (no --config file)"),
+    }
+
+    if let Some(dir) = &config.storage.data_dir {
+        std::fs::create_dir_all(dir)
+            .unwrap_or_else(|e| config_error(format!("storage.data_dir {}: {}", dir.display(), e)));
+        log::info!("📁 Data directory: {}", dir.display());
+    }
 
     // Initialize network service
     log::info!("🌐 Initializing network service...");
     let network_service = match NetworkService::new().await {
         Ok(service) => {
             log::info!("✅ Network service initialized");
-            Arc::new(service)
+            Arc::new(service.with_precompress(config.storage.archive_precompress))
         }
         Err(e) => {
             log::error!("❌ Failed to initialize network service: {}", e);
@@ -47,75 +58,122 @@ async fn main() -> std::io::Result<()> {
             log::warn!("⚠️  Continuing with limited functionality...");
             // In production, you might want to exit here
             // For tutorial purposes, we allow it to continue
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Network init failed: {}", e),
-            ));
+            return Err(std::io::Error::other(format!(
+                "Network init failed: {}",
+                e
+            )));
         }
     };
 
-    // Get server configuration from environment
-    let host = std::env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = std::env::var("SERVER_PORT")
-        .unwrap_or_else(|_| "18888".to_string())
-        .parse::<u16>()
-        .expect("SERVER_PORT must be a valid port number");
+    // Checked before anything listens: TLS files, publish root, listener addresses
+    let config = Arc::new(config);
+    let state =
+        ApiState::new(network_service.clone(), config.clone()).unwrap_or_else(|e| config_error(e));
+    let dns_config = DnsConfig::from_config(&config).unwrap_or_else(|e| config_error(e));
+    let s3_config = S3Config::from_config(&config).unwrap_or_else(|e| config_error(e));
+
+    // Optional DNS listener answering from PNR records
+    match dns_config {
+        Some(dns_config) => {
+            let dns_server = DnsServer::new(dns_config, network_service.clone());
+            actix_web::rt::spawn(async move {
+                if let Err(e) = dns_server.run().await {
+                    log::error!("❌ DNS listener stopped: {}", e);
+                }
+            });
+        }
+        None => log::info!("🌍 DNS listener disabled (set DNS_ENABLED=true to enable)"),
+    }
 
-    let bind_address = format!("{}:{}", host, port);
+    let bind_address = config.server.bind_address();
+    let scheme = if state.tls.is_some() { "https" } else { "http" };
 
     log::info!("🚀 Starting HTTP server...");
-    log::info!("📍 Listening on: http://{}", bind_address);
-    log::info!("");
-    log::info!("📋 Available endpoints:");
-    log::info!("   Health:              GET  /health");
-    log::info!("");
-    log::info!("   Chunks (JSON):       POST /anttp-0/chunk");
-    log::info!("   Chunks (JSON):       GET  /anttp-0/chunk/{{address}}");
-    log::info!("   Chunks (Binary):     POST /anttp-0/binary/chunk");
-    log::info!("   Chunks (Binary):     GET  /anttp-0/binary/chunk/{{address}}");
-    log::info!("");
-    log::info!("   Registers:           POST /anttp-0/register");
-    log::info!("   Registers:           PUT  /anttp-0/register/{{address}}");
-    log::info!("   Registers:           GET  /anttp-0/register/{{address}}");
-    log::info!("   Register History:    GET  /anttp-0/register_history/{{address}}");
-    log::info!("");
-    log::info!("   Pointers:            POST /anttp-0/pointer");
-    log::info!("   Pointers:            PUT  /anttp-0/pointer/{{address}}");
-    log::info!("   Pointers:            GET  /anttp-0/pointer/{{address}}");
-    log::info!("");
-    log::info!("   Public Scratchpad:   POST /anttp-0/public_scratchpad");
-    log::info!("   Public Scratchpad:   PUT  /anttp-0/public_scratchpad/{{address}}/{{name}}");
-    log::info!("   Public Scratchpad:   GET  /anttp-0/public_scratchpad/{{address}}");
-    log::info!("");
-    log::info!("   Private Scratchpad:  POST /anttp-0/private_scratchpad");
-    log::info!("   Private Scratchpad:  PUT  /anttp-0/private_scratchpad/{{address}}/{{name}}");
-    log::info!("   Private Scratchpad:  GET  /anttp-0/private_scratchpad/{{address}}/{{name}}");
-    log::info!("");
-    log::info!("   Archives (Multipart):POST /anttp-0/multipart/public_archive");
-    log::info!("   Archives (Path):     POST /anttp-0/multipart/public_archive/{{path}}");
-    log::info!("   Archives (Root):     GET  /anttp-0/public_archive/{{address}}");
-    log::info!("   Archives (File):     GET  /anttp-0/public_archive/{{address}}/{{path}}");
-    log::info!("");
-    log::info!("   Tarchive:            POST /anttp-0/multipart/tarchive");
-    log::info!("");
-    log::info!("   Graph Entry:         POST /anttp-0/graph_entry");
-    log::info!("   Graph Entry:         GET  /anttp-0/graph_entry/{{address}}");
+    log::info!("📍 Listening on: {}://{}", scheme, bind_address);
     log::info!("");
-    log::info!("   PNR:                 POST /anttp-0/pnr");
-    log::info!("   PNR:                 PUT  /anttp-0/pnr/{{name}}");
-    log::info!("   PNR:                 GET  /anttp-0/pnr/{{name}}");
-    log::info!("   PNR:                 PATCH /anttp-0/pnr/{{name}}");
-    log::info!("");
-    log::info!("   Key/Value:           POST /anttp-0/key_value");
-    log::info!("   Key/Value:           GET  /anttp-0/key_value/{{bucket}}/{{object}}");
-    log::info!("");
-    log::info!("   Public Data:         POST /anttp-0/binary/public_data");
-    log::info!("   Public Data:         GET  /anttp-0/binary/public_data/{{address}}");
+    if config.features.api_docs {
+        log::info!(
+            "📋 Available endpoints (try them at {}://{}/docs):",
+            scheme,
+            bind_address
+        );
+    } else {
+        log::info!("📋 Available endpoints:");
+    }
+    for group in routes::enabled_groups(&config.features) {
+        // One line per path, with all of its methods
+        let mut paths: Vec<(String, Vec<&str>)> = Vec::new();
+        for route in &group.routes {
+            let path = route.path();
+            match paths.iter_mut().find(|(known, _)| *known == path) {
+                Some((_, methods)) => methods.push(route.method.as_str()),
+                None => paths.push((path, vec![route.method.as_str()])),
+            }
+        }
+        for (path, methods) in paths {
+            log::info!("   {:<12} {:<20} {}", group.name, methods.join("|"), path);
+        }
+    }
     log::info!("");
-    log::info!("   Commands:            GET  /anttp-0/command");
+    match &s3_config {
+        Some(s3) => {
+            log::info!(
+                "   S3 Gateway:          {}://{} ({} access key(s), region {})",
+                scheme,
+                s3.bind_address,
+                s3.access_keys.len(),
+                s3.region
+            );
+        }
+        None => log::info!("   S3 Gateway:          disabled (set S3_ENABLED=true and S3_ACCESS_KEYS)"),
+    }
+    match &state.publish {
+        Some(publish) => log::info!(
+            "   Publish Directory:   POST /anttp-0/admin/publish_directory (from {})",
+            publish.root.display()
+        ),
+        None => log::info!("   Publish Directory:   disabled (set PUBLISH_ROOT to enable)"),
+    }
+    log::info!(
+        "   Default Store Type:  {:?}",
+        config.storage.default_store_type
+    );
+    // Same-origin pages only skip these lists for /docs, /openapi.json and /health
+    let list_or_none = |list: &[String]| match list.is_empty() {
+        true => "none".to_string(),
+        false => list.join(", "),
+    };
+    log::info!(
+        "   CORS Read Origins:   {}",
+        list_or_none(&config.cors.read_origins)
+    );
+    log::info!(
+        "   CORS Write Origins:  {}",
+        list_or_none(&config.cors.write_origins)
+    );
+    if config.auth.enabled {
+        log::info!(
+            "   Authentication:      bearer tokens ({} token(s), anonymous: {})",
+            state.tokens.len(),
+            list_or_none(&config.auth.anonymous_scopes)
+        );
+        if state.tokens.is_empty() {
+            log::warn!("⚠️  No tokens yet - set auth.admin_token (AUTH_ADMIN_TOKEN) to issue some");
+        }
+    } else {
+        log::info!("   Authentication:      disabled (set AUTH_ENABLED=true to require tokens)");
+    }
+    log::info!("   Configuration:       GET /anttp-0/admin/config");
     log::info!("");
     log::info!("🧪 Test with:");
-    log::info!("   curl -X POST http://{}:{}/anttp-0/chunk \\", host, port);
+    log::info!(
+        "   curl -X POST {}://{}/anttp-0/chunk \\",
+        scheme,
+        bind_address
+    );
+    if config.auth.enabled {
+        log::info!("     -H 'Authorization: Bearer <token>' \\");
+    }
     log::info!("     -H 'Content-Type: application/json' \\");
     log::info!("     -H 'x-store-type: memory' \\");
     log::info!("     -d '{{\"content\":\"SGVsbG8gV29ybGQh\"}}'");
@@ -123,176 +181,80 @@ async fn main() -> std::io::Result<()> {
     log::info!("🦀 ════════════════════════════════════════════════════════");
 
     // Start HTTP server
-    let service_data = web::Data::from(network_service);
-    
-    HttpServer::new(move || {
-        // Configure CORS
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header()
-            .max_age(3600);
+    let service_data = web::Data::from(network_service.clone());
+
+    // The S3 listener gets its own port: S3 clients expect buckets at the path root
+    let s3_server = match s3_config {
+        Some(s3) => {
+            let bind_address = s3.bind_address;
+            let gateway_data = web::Data::new(S3Gateway::new(s3));
+            let service_data = service_data.clone();
+            let body_limit = config.limits.s3_body_bytes;
+            let access_log = config.logging.access_log;
+            let server = HttpServer::new(move || {
+                App::new()
+                    .wrap(middleware::Condition::new(
+                        access_log,
+                        middleware::Logger::default(),
+                    ))
+                    .app_data(service_data.clone())
+                    .app_data(gateway_data.clone())
+                    .app_data(web::PayloadConfig::new(body_limit))
+                    // Every path and method - s3_request works out the S3 operation
+                    .default_service(web::route().to(handlers::s3_request))
+            });
+            let server = match &state.tls {
+                Some(tls) => server.bind_rustls_0_23(bind_address, tls.clone())?,
+                None => server.bind(bind_address)?,
+            };
+            log::info!("🪣 S3 gateway listening on {}://{}", scheme, bind_address);
+            Some(server.run())
+        }
+        None => None,
+    };
+
+    let api_server = api_server(TcpListener::bind(&bind_address)?, state)?;
+
+    match s3_server {
+        Some(s3_server) => futures::future::try_join(api_server, s3_server)
+            .await
+            .map(|_| ()),
+        None => api_server.await,
+    }
+}
+
+/// Stop before starting anything: the settings can't work
+///
+/// For Students:
+/// `!` means "never returns" - the process ends here, with exit code 2
+/// (the usual code for "you called me wrong"), and the message names the
+/// setting to fix.
+fn config_error(error: impl Display) -> ! {
+    eprintln!("❌ Configuration error: {}", error);
+    std::process::exit(2);
+}
 
-        App::new()
-            // Add middleware
-            .wrap(cors)
-            .wrap(middleware::Logger::default())
-            // Share network service across all requests
-            .app_data(service_data.clone())
-            // Health check (no prefix)
-            .route("/health", web::get().to(health_check))
-            // ========================================
-            // CHUNK ENDPOINTS - /anttp-0/chunk
-            // ========================================
-            .route("/anttp-0/chunk", web::post().to(handlers::create_chunk))
-            .route(
-                "/anttp-0/chunk/{address}",
-                web::get().to(handlers::get_chunk),
-            )
-            .route(
-                "/anttp-0/binary/chunk",
-                web::post().to(handlers::create_chunk_binary),
-            )
-            .route(
-                "/anttp-0/binary/chunk/{address}",
-                web::get().to(handlers::get_chunk_binary),
-            )
-            // ========================================
-            // REGISTER ENDPOINTS - /anttp-0/register
-            // ========================================
-            .route("/anttp-0/register", web::post().to(handlers::create_register))
-            .route(
-                "/anttp-0/register/{address}",
-                web::get().to(handlers::get_register),
-            )
-            .route(
-                "/anttp-0/register/{address}",
-                web::put().to(handlers::update_register),
-            )
-            .route(
-                "/anttp-0/register_history/{address}",
-                web::get().to(handlers::get_register_history),
-            )
-            // ========================================
-            // POINTER ENDPOINTS - /anttp-0/pointer
-            // ========================================
-            .route("/anttp-0/pointer", web::post().to(handlers::create_pointer))
-            .route(
-                "/anttp-0/pointer/{address}",
-                web::get().to(handlers::get_pointer),
-            )
-            .route(
-                "/anttp-0/pointer/{address}",
-                web::put().to(handlers::update_pointer),
-            )
-            // ========================================
-            // PUBLIC SCRATCHPAD - /anttp-0/public_scratchpad
-            // ========================================
-            .route(
-                "/anttp-0/public_scratchpad",
-                web::post().to(handlers::create_public_scratchpad),
-            )
-            .route(
-                "/anttp-0/public_scratchpad/{address}/{name}",
-                web::put().to(handlers::update_public_scratchpad),
-            )
-            .route(
-                "/anttp-0/public_scratchpad/{address}",
-                web::get().to(handlers::get_public_scratchpad),
-            )
-            // ========================================
-            // PRIVATE SCRATCHPAD - /anttp-0/private_scratchpad
-            // ========================================
-            .route(
-                "/anttp-0/private_scratchpad",
-                web::post().to(handlers::create_private_scratchpad),
-            )
-            .route(
-                "/anttp-0/private_scratchpad/{address}/{name}",
-                web::put().to(handlers::update_private_scratchpad),
-            )
-            .route(
-                "/anttp-0/private_scratchpad/{address}/{name}",
-                web::get().to(handlers::get_private_scratchpad),
-            )
-            // ========================================
-            // ARCHIVE ENDPOINTS - /anttp-0/multipart/public_archive
-            // ========================================
-            .route(
-                "/anttp-0/multipart/public_archive",
-                web::post().to(handlers::create_archive),
-            )
-            .route(
-                "/anttp-0/multipart/public_archive/{path:.*}",
-                web::post().to(handlers::create_archive_with_path),
-            )
-            .route(
-                "/anttp-0/public_archive/{address}",
-                web::get().to(handlers::get_archive_root),
-            )
-            .route(
-                "/anttp-0/public_archive/{address}/{path:.*}",
-                web::get().to(handlers::get_archive_file),
-            )
-            // ========================================
-            // TARCHIVE ENDPOINT - /anttp-0/multipart/tarchive
-            // ========================================
-            .route(
-                "/anttp-0/multipart/tarchive",
-                web::post().to(handlers::create_tarchive),
-            )
-            // ========================================
-            // GRAPH ENDPOINTS - /anttp-0/graph_entry
-            // ========================================
-            .route(
-                "/anttp-0/graph_entry",
-                web::post().to(handlers::create_graph_entry),
-            )
-            .route(
-                "/anttp-0/graph_entry/{address}",
-                web::get().to(handlers::get_graph_entry),
-            )
-            // ========================================
-            // PNR ENDPOINTS - /anttp-0/pnr
-            // ========================================
-            .route("/anttp-0/pnr", web::post().to(handlers::create_pnr))
-            .route(
-                "/anttp-0/pnr/{name}",
-                web::put().to(handlers::update_pnr),
-            )
-            .route("/anttp-0/pnr/{name}", web::get().to(handlers::get_pnr))
-            .route(
-                "/anttp-0/pnr/{name}",
-                web::patch().to(handlers::append_pnr),
-            )
-            // ========================================
-            // KEY/VALUE ENDPOINTS - /anttp-0/key_value
-            // ========================================
-            .route(
-                "/anttp-0/key_value",
-                web::post().to(handlers::create_key_value),
-            )
-            .route(
-                "/anttp-0/key_value/{bucket}/{object}",
-                web::get().to(handlers::get_key_value),
-            )
-            // ========================================
-            // PUBLIC DATA ENDPOINTS - /anttp-0/binary/public_data
-            // ========================================
-            .route(
-                "/anttp-0/binary/public_data",
-                web::post().to(handlers::create_public_data),
-            )
-            .route(
-                "/anttp-0/binary/public_data/{address}",
-                web::get().to(handlers::get_public_data),
-            )
-            // ========================================
-            // COMMANDS ENDPOINT - /anttp-0/command
-            // ========================================
-            .route("/anttp-0/command", web::get().to(handlers::get_commands))
-    })
-    .bind(&bind_address)?
-    .run()
-    .await
+/// Set up env_logger from `[logging]`
+///
+/// For Students:
+/// "text" is the usual readable line; "json" writes one JSON object per
+/// line, which log collectors (Loki, Elasticsearch, ...) can read
+/// without guessing where the message starts.
+fn init_logging(logging: &LoggingConfig) {
+    let mut builder = env_logger::Builder::new();
+    builder.parse_filters(&logging.level);
+    if logging.format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let timestamp =
+                chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            let line = serde_json::json!({
+                "timestamp": timestamp,
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    }
+    builder.init();
 }
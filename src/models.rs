@@ -5,6 +5,7 @@
 //! These are the "shapes" of data we send and receive.
 //! Think of them like forms - each field is a blank to fill in!
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -13,21 +14,21 @@ use std::collections::HashMap;
 // ============================================================================
 
 /// Request to create a chunk (JSON format)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChunkRequest {
     /// Base64-encoded content
     pub content: String,
 }
 
 /// Response after creating a chunk
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChunkResponse {
     /// Network address where chunk is stored
     pub address: String,
 }
 
 /// Chunk data for retrieval
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChunkData {
     pub content: String,
 }
@@ -37,7 +38,7 @@ pub struct ChunkData {
 // ============================================================================
 
 /// Request to create a register
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RegisterRequest {
     /// Name of the register
     pub name: String,
@@ -46,19 +47,19 @@ pub struct RegisterRequest {
 }
 
 /// Response after creating a register
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RegisterResponse {
     pub address: String,
 }
 
 /// Register data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RegisterData {
     pub content: String,
 }
 
 /// Register history entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RegisterHistoryEntry {
     pub content: String,
     pub timestamp: i64,
@@ -69,7 +70,7 @@ pub struct RegisterHistoryEntry {
 // ============================================================================
 
 /// Request to create a pointer
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PointerRequest {
     /// Name of the pointer
     pub name: String,
@@ -78,13 +79,13 @@ pub struct PointerRequest {
 }
 
 /// Response after creating a pointer
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PointerResponse {
     pub address: String,
 }
 
 /// Pointer data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PointerData {
     /// The address this pointer points to
     pub content: String,
@@ -95,7 +96,7 @@ pub struct PointerData {
 // ============================================================================
 
 /// Request to create a scratchpad
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScratchpadRequest {
     /// Name (required for private scratchpads)
     pub name: String,
@@ -104,20 +105,20 @@ pub struct ScratchpadRequest {
 }
 
 /// Request to update a scratchpad
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScratchpadUpdateRequest {
     /// Base64-encoded content
     pub content: String,
 }
 
 /// Response after creating a scratchpad
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScratchpadResponse {
     pub address: String,
 }
 
 /// Scratchpad data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScratchpadData {
     pub content: String,
 }
@@ -127,12 +128,180 @@ pub struct ScratchpadData {
 // ============================================================================
 
 /// Response after creating an archive
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ArchiveResponse {
     /// ONLY field from network - matches AntTP exactly
     pub address: String,
 }
 
+/// Response after uploading an archive: the address plus what went into it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveUploadResponse {
+    pub address: String,
+    /// One entry per uploaded file, sorted by path
+    pub files: Vec<ArchiveEntry>,
+}
+
+/// One delete or rename in PATCH /anttp-0/public_archive/{address}
+///
+/// `{"op": "delete", "path": "old.html"}` or
+/// `{"op": "rename", "from": "blog", "to": "posts"}` - folders work too.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum ArchivePathChange {
+    Delete { path: String },
+    Rename { from: String, to: String },
+}
+
+/// Body of PATCH /anttp-0/public_archive/{address}
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArchivePatchRequest {
+    /// Applied in order; if any fails, no new archive is made
+    pub changes: Vec<ArchivePathChange>,
+}
+
+/// What happened to one entry of an uploaded zip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ZipEntryStatus {
+    Stored,
+    Skipped,
+    Rejected,
+}
+
+/// Result for one zip entry
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ZipEntryResult {
+    /// Name as stored in the zip
+    pub name: String,
+    /// Path in the archive (for stored entries)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub status: ZipEntryStatus,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Response after uploading a zip
+///
+/// Nothing is stored if any entry is rejected - `address` is then missing
+/// and `error` says why.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ZipUploadResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub entries: Vec<ZipEntryResult>,
+}
+
+/// Bundle formats an archive can be downloaded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleFormat {
+    Zip,
+    Tar,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+}
+
+impl BundleFormat {
+    /// MIME type sent in Content-Type
+    pub fn content_type(self) -> &'static str {
+        match self {
+            BundleFormat::Zip => "application/zip",
+            BundleFormat::Tar => "application/x-tar",
+            BundleFormat::TarGz => "application/gzip",
+        }
+    }
+
+    /// File extension for the download's filename
+    pub fn extension(self) -> &'static str {
+        match self {
+            BundleFormat::Zip => "zip",
+            BundleFormat::Tar => "tar",
+            BundleFormat::TarGz => "tar.gz",
+        }
+    }
+}
+
+/// Query string for reading a whole archive
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveDownloadQuery {
+    /// Download as a zip/tar/tar.gz bundle instead of JSON
+    pub format: Option<BundleFormat>,
+    /// Only include files in this folder (e.g. "css")
+    pub prefix: Option<String>,
+}
+
+/// Query string for archive updates
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveUpdateQuery {
+    /// Pointer address to move to the new archive
+    pub pointer: Option<String>,
+}
+
+/// Response after changing an archive
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveUpdateResponse {
+    /// The new archive
+    pub address: String,
+    /// The archive the changes were made to (it still exists, unchanged)
+    pub previous: String,
+    /// The new archive's manifest - unchanged files keep their chunk address
+    pub files: Vec<ArchiveEntry>,
+    /// Pointer that now targets `address`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+}
+
+/// One file of a desired state, described by its content hash
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ManifestFile {
+    pub path: String,
+    /// SHA-256 of the file content (hex)
+    pub sha256: String,
+}
+
+/// A local file-hash manifest to compare an archive against
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveManifestRequest {
+    pub files: Vec<ManifestFile>,
+}
+
+/// What differs between two archives (or an archive and a manifest)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveDiffResponse {
+    pub from: String,
+    /// The other archive; absent when comparing against a supplied manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Only in the new state
+    pub added: Vec<String>,
+    /// Only in `from`
+    pub removed: Vec<String>,
+    /// In both, with different content
+    pub modified: Vec<String>,
+    /// In both, with the same content
+    pub unchanged: Vec<String>,
+}
+
+/// The smallest set of steps that turns an archive into a desired state
+///
+/// Apply `changes` with PATCH (skip it if empty), then PUT the `upload`
+/// files to the archive PATCH returned.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveSyncPlan {
+    pub address: String,
+    /// Paths whose content the archive doesn't have yet - only these need uploading
+    pub upload: Vec<String>,
+    /// Deletes, then renames (files that only moved need no upload)
+    pub changes: Vec<ArchivePathChange>,
+    /// Number of files that are already right
+    pub unchanged: usize,
+}
+
 /// Archive file for internal use
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArchiveFile {
@@ -141,6 +310,37 @@ pub struct ArchiveFile {
     pub content: Vec<u8>,
 }
 
+/// One file in an archive manifest
+///
+/// An archive is stored as a manifest (a list of these) with each file's
+/// content stored separately as a chunk - so unchanged files can be shared
+/// between archive versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveEntry {
+    /// Relative path inside the archive, "/"-separated (e.g. "css/site.css")
+    pub path: String,
+    /// Chunk address holding the file content
+    pub address: String,
+    pub size: u64,
+    /// SHA-256 of the content (hex) - compare these to see if files changed
+    pub sha256: String,
+    /// Unix timestamp of the file's last modification
+    pub modified: i64,
+    /// Precompressed copies (best first), sent as-is to clients accepting them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encodings: Vec<ArchiveEncoding>,
+}
+
+/// A compressed copy of an archive file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveEncoding {
+    /// Content-Encoding name: "br" or "gzip"
+    pub encoding: String,
+    /// Chunk address holding the compressed bytes
+    pub address: String,
+    pub size: u64,
+}
+
 // Helper module for base64 serialization of bytes
 mod base64_bytes {
     use base64::{engine::general_purpose, Engine as _};
@@ -164,32 +364,214 @@ mod base64_bytes {
     }
 }
 
+// ============================================================================
+// APP CONFIG - Routing rules shipped inside a site archive (app-conf.json)
+// ============================================================================
+
+/// Routing rules for serving an archive as a website
+///
+/// For Students:
+/// Put an `app-conf.json` at the root of the archive and the server
+/// follows it when files are requested - like a tiny web server config:
+/// ```json
+/// {
+///   "redirects": [{"from": "/old.html", "to": "/new.html", "status": 301}],
+///   "rewrites": [{"from": "/app/**", "to": "/app/index.html"}],
+///   "headers": [{"path": "**/*.html", "headers": {"Cache-Control": "no-cache"}}],
+///   "errorPages": {"404": "/404.html"},
+///   "cleanUrls": true
+/// }
+/// ```
+/// Paths are globs: `*` stays inside one folder, `**` crosses folders.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AppConfig {
+    /// Answer with a redirect instead of a file (checked first)
+    #[serde(default)]
+    pub redirects: Vec<AppRedirect>,
+    /// Serve another file when the requested one doesn't exist
+    #[serde(default)]
+    pub rewrites: Vec<AppRewrite>,
+    /// AntTP's form of rewrites: pattern → file
+    #[serde(default)]
+    pub route_map: HashMap<String, String>,
+    /// Extra response headers for matching paths (see `app_config::ALLOWED_HEADERS`)
+    #[serde(default)]
+    pub headers: Vec<AppHeaderRule>,
+    /// Page to serve for an error status ("404", "500")
+    #[serde(default)]
+    pub error_pages: HashMap<String, String>,
+    /// Serve "about.html" for "/about"
+    #[serde(default)]
+    pub clean_urls: bool,
+}
+
+/// One redirect rule
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AppRedirect {
+    pub from: String,
+    /// Path in the archive, or a full URL ("https://...")
+    pub to: String,
+    /// 301 (default), 302, 303, 307 or 308
+    #[serde(default)]
+    pub status: Option<u16>,
+}
+
+/// One rewrite rule
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AppRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+/// Headers added to responses for paths matching `path`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AppHeaderRule {
+    pub path: String,
+    pub headers: HashMap<String, String>,
+}
+
 // ============================================================================
 // GRAPH - Graph data structure
 // ============================================================================
 
 /// Request to create a graph entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GraphEntryRequest {
     pub name: String,
     /// Hex-encoded content
     pub content: String,
+    /// Addresses of the entries this one links to (the graph edges)
+    #[serde(default)]
+    pub descendants: Vec<String>,
+}
+
+/// Graph entry data
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GraphEntryData {
+    pub name: String,
+    /// Hex-encoded content
+    pub content: String,
+    pub descendants: Vec<String>,
+}
+
+/// Output format for graph exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphExportFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// JSON Graph Format (jsongraphformat.info)
+    Json,
+    /// GraphML (XML)
+    Graphml,
+}
+
+/// Query string for graph exports
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct GraphExportQuery {
+    #[serde(default)]
+    pub format: GraphExportFormat,
+    /// How many links to follow from the root (unlimited if not set)
+    pub depth: Option<usize>,
+}
+
+/// JSON Graph Format document - used for both export and bulk import
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JsonGraphDocument {
+    pub graph: JsonGraph,
+}
+
+/// A single graph in JSON Graph Format
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JsonGraph {
+    #[serde(default = "default_true")]
+    pub directed: bool,
+    /// Nodes keyed by id (the entry address on export)
+    pub nodes: std::collections::BTreeMap<String, JsonGraphNode>,
+    #[serde(default)]
+    pub edges: Vec<JsonGraphEdge>,
+}
+
+/// A graph node - label is the entry name
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JsonGraphNode {
+    pub label: String,
+    pub metadata: JsonGraphNodeMetadata,
+}
+
+/// Extra node data carried alongside the label
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JsonGraphNodeMetadata {
+    /// Hex-encoded content
+    pub content: String,
+}
+
+/// A directed edge between two node ids
+///
+/// On import, a target that is not a node in the document
+/// is treated as the address of an entry that already exists.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JsonGraphEdge {
+    pub source: String,
+    pub target: String,
+}
+
+/// Response after a bulk graph import
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GraphImportResponse {
+    /// Node id from the document → address it was stored at
+    pub addresses: std::collections::BTreeMap<String, String>,
+    /// Addresses of nodes that nothing else in the document links to
+    pub roots: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 // ============================================================================
 // PNR - Pointer Name Registry
 // ============================================================================
 
+/// What a PNR record's `address` holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum PnrRecordType {
+    /// Address of immutable data (chunk, archive, public data)
+    #[serde(alias = "a")]
+    A,
+    /// Address of a pointer - resolution follows it to the data
+    #[serde(alias = "x")]
+    X,
+    /// Free text, never resolved (served as a DNS TXT answer)
+    #[serde(rename = "TXT", alias = "txt")]
+    Txt,
+}
+
+impl std::fmt::Display for PnrRecordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::A => write!(f, "A"),
+            Self::X => write!(f, "X"),
+            Self::Txt => write!(f, "TXT"),
+        }
+    }
+}
+
 /// DNS-like record
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PnrRecord {
     pub address: String,
-    pub record_type: String,
+    pub record_type: PnrRecordType,
     pub ttl: u32,
 }
 
 /// Request to create/update PNR
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Record keys are sub-names: "@" is the name itself, "blog"
+/// makes `blog.my-site`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PnrRequest {
     pub name: String,
     pub records: HashMap<String, PnrRecord>,
@@ -200,55 +582,342 @@ pub struct PnrRequest {
 // ============================================================================
 
 /// Request to create key/value pair
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KeyValueRequest {
     pub bucket: String,
     pub object: String,
     /// Base64-encoded content
     pub content: String,
+    /// MIME type of the decoded content (defaults to application/octet-stream)
+    #[serde(default)]
+    pub content_type: Option<String>,
 }
 
 /// Key/value data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KeyValueData {
     pub content: String,
 }
 
+/// Metadata kept alongside every object
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyValueMetadata {
+    pub bucket: String,
+    pub object: String,
+    pub content_type: String,
+    /// Size of the decoded content in bytes
+    pub size: u64,
+    /// Unix timestamp of the last write
+    pub last_modified: i64,
+    /// SHA-256 of the decoded content (hex)
+    pub etag: String,
+    /// Set when the object was written to a versioned bucket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+}
+
+/// Response after storing a key/value object
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyValueResponse {
+    pub address: String,
+    pub bucket: String,
+    pub object: String,
+    /// New version ID (versioned buckets only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+}
+
+/// `?version_id=` on object reads and deletes
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct VersionQuery {
+    pub version_id: Option<String>,
+}
+
+/// One entry in an object's version history
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyValueVersion {
+    /// "null" for the version written while versioning was off
+    pub version_id: String,
+    /// Is this what a plain GET returns (or, for a delete marker, hides)?
+    pub is_latest: bool,
+    pub is_delete_marker: bool,
+    pub last_modified: i64,
+    /// Delete markers have no content, so no size/etag/content type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// Response listing an object's versions, newest first
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObjectVersionsResponse {
+    pub bucket: String,
+    pub object: String,
+    pub versions: Vec<KeyValueVersion>,
+}
+
+/// A key/value bucket
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyValueBucket {
+    pub name: String,
+    /// Unix timestamp
+    pub created: i64,
+    /// Keep every version of every object (and turn deletes into delete markers)
+    #[serde(default)]
+    pub versioning: bool,
+}
+
+/// Optional body for PUT /anttp-0/key_value/{bucket}
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct BucketRequest {
+    /// Turn versioning on (true) or suspend it (false); absent leaves it as is
+    #[serde(default)]
+    pub versioning: Option<bool>,
+}
+
+/// Response listing all buckets
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListBucketsResponse {
+    pub buckets: Vec<KeyValueBucket>,
+}
+
+/// Query string for listing objects (like S3 ListObjectsV2)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ListObjectsQuery {
+    /// Only list objects whose key starts with this
+    pub prefix: Option<String>,
+    /// Roll keys up into common prefixes at this separator (usually "/")
+    pub delimiter: Option<String>,
+    /// Page size (1-1000, default 1000)
+    pub max_keys: Option<usize>,
+    /// `next_continuation_token` from the previous page
+    pub continuation_token: Option<String>,
+    /// Start listing after this key
+    pub start_after: Option<String>,
+}
+
+/// One page of an object listing
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListObjectsResponse {
+    pub bucket: String,
+    pub prefix: String,
+    pub delimiter: Option<String>,
+    pub contents: Vec<KeyValueMetadata>,
+    /// "Folders" - key prefixes up to and including the delimiter
+    pub common_prefixes: Vec<String>,
+    /// Number of contents + common prefixes in this page
+    pub key_count: usize,
+    pub max_keys: usize,
+    pub is_truncated: bool,
+    pub next_continuation_token: Option<String>,
+}
+
+// ============================================================================
+// PUBLISH - Server-side directory publishing (admin)
+// ============================================================================
+
+/// What to do when the walk meets a symbolic link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Leave links out (listed under `skipped`)
+    #[default]
+    Skip,
+    /// Publish what the link points to - only if it stays inside PUBLISH_ROOT
+    Follow,
+    /// Refuse to publish a directory containing links
+    Error,
+}
+
+/// How to store the published files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishFormat {
+    #[default]
+    Archive,
+    Tarchive,
+}
+
+/// Request to publish a directory on the server's disk
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PublishRequest {
+    /// Directory to publish, inside PUBLISH_ROOT (relative paths are taken from there)
+    pub path: String,
+    /// Glob patterns to leave out (e.g. ".git", "*.tmp", "drafts/**")
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    #[serde(default)]
+    pub symlinks: SymlinkPolicy,
+    #[serde(default)]
+    pub format: PublishFormat,
+    /// Pointer address to move to the new archive
+    #[serde(default)]
+    pub pointer: Option<String>,
+    /// PNR name (or `sub.name`) whose record should point at the new archive
+    #[serde(default)]
+    pub pnr: Option<String>,
+}
+
+/// A file or directory the walk left out, and why
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SkippedPath {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Result of publishing a directory
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PublishResponse {
+    pub address: String,
+    pub format: PublishFormat,
+    /// Number of files published
+    pub files: usize,
+    /// Total size of the published files
+    pub bytes: u64,
+    pub skipped: Vec<SkippedPath>,
+    /// Pointer that now targets `address`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    /// PNR name whose record now resolves to `address`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pnr: Option<String>,
+}
+
+// ============================================================================
+// TOKENS - API tokens and their scopes (admin)
+// ============================================================================
+
+/// Request to issue an API token
+///
+/// For Students:
+/// Scopes say what the token may do: `read:archive`, `write:key_value/notes`
+/// (only the "notes" bucket), `read:*` (read everything) or `admin`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TokenRequest {
+    /// Label to recognize the token by (e.g. "deploy-bot")
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Lifetime in seconds (never expires when missing)
+    #[serde(default)]
+    pub expires_in_seconds: Option<u64>,
+}
+
+/// Where a token is defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenSource {
+    /// In the `[auth]` config (change the config to remove it)
+    Config,
+    /// Issued with POST /anttp-0/admin/token
+    Issued,
+}
+
+/// A token's description - never the secret itself
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub source: TokenSource,
+    /// Unix timestamp (issued tokens only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+    /// Unix timestamp after which the token is refused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+/// A newly issued token - the only time its secret is shown
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IssuedTokenResponse {
+    /// Send as `Authorization: Bearer <token>`
+    pub token: String,
+    #[serde(flatten)]
+    pub info: TokenInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TokenListResponse {
+    pub tokens: Vec<TokenInfo>,
+}
+
 // ============================================================================
 // ERROR - Error responses
 // ============================================================================
 
 /// Standard error response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// For Students:
+/// `error` is for people and may change; `code` is for programs and
+/// stays the same, so clients can `match` on it safely.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ErrorResponse {
     pub error: String,
+    pub code: ErrorCode,
     pub details: Option<String>,
 }
 
 impl ErrorResponse {
-    pub fn new(error: impl Into<String>) -> Self {
+    pub fn new(code: ErrorCode, error: impl Into<String>) -> Self {
         Self {
             error: error.into(),
+            code,
             details: None,
         }
     }
 
-    pub fn with_details(error: impl Into<String>, details: impl Into<String>) -> Self {
+    pub fn with_details(
+        code: ErrorCode,
+        error: impl Into<String>,
+        details: impl Into<String>,
+    ) -> Self {
         Self {
             error: error.into(),
+            code,
             details: Some(details.into()),
         }
     }
 }
 
+/// Machine-readable error codes (serialized in snake_case)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request itself is wrong (bad JSON, bad path, missing field)
+    InvalidInput,
+    NotFound,
+    /// The address exists but holds a different kind of data
+    TypeMismatch,
+    /// The change clashes with what is stored (already exists, not empty)
+    Conflict,
+    /// Credentials (like x-owner-key) are needed
+    Unauthorized,
+    /// Credentials were given but don't allow this
+    Forbidden,
+    PayloadTooLarge,
+    MethodNotAllowed,
+    PreconditionFailed,
+    UnsupportedMediaType,
+    /// The storage backend (e.g. the Autonomi network) can't be reached
+    BackendUnavailable,
+    /// Stored data couldn't be decoded
+    Corrupt,
+    Internal,
+}
+
 // ============================================================================
 // STORAGE TYPE - From x-store-type header
 // ============================================================================
 
 /// Storage backend type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum StoreType {
     /// RAM storage (fast, temporary)
+    #[default]
     Memory,
     /// Disk storage (persistent)
     Disk,
@@ -256,9 +925,14 @@ pub enum StoreType {
     Network,
 }
 
-impl Default for StoreType {
-    fn default() -> Self {
-        Self::Memory
+impl StoreType {
+    /// The x-store-type header value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Memory => "memory",
+            Self::Disk => "disk",
+            Self::Network => "network",
+        }
     }
 }
 
@@ -274,3 +948,44 @@ impl std::str::FromStr for StoreType {
         }
     }
 }
+
+// ============================================================================
+// CONSISTENCY - From x-consistency header
+// ============================================================================
+
+/// How fresh an answer must be
+///
+/// For Students:
+/// Looking up a PNR name takes several reads, so answers are cached for
+/// the record's TTL. `Strong` skips those caches - slower, but you see a
+/// change the moment it is made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Consistency {
+    /// Cached answers are fine (until they expire)
+    #[default]
+    Cached,
+    /// Always read the current state
+    Strong,
+}
+
+impl Consistency {
+    /// The x-consistency header value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cached => "cached",
+            Self::Strong => "strong",
+        }
+    }
+}
+
+impl std::str::FromStr for Consistency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cached" => Ok(Self::Cached),
+            "strong" => Ok(Self::Strong),
+            _ => Err(anyhow::anyhow!("Invalid consistency: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+// src/openapi.rs
+//! OpenAPI document - The route table, as a machine-readable API description
+//!
+//! For 1st Year CS Students:
+//! OpenAPI is a standard JSON format that describes an HTTP API: every
+//! path, its methods, parameters, and the JSON shapes going in and out.
+//! Tools read it to draw documentation pages, generate client code, or
+//! test the API. We never write it by hand: it is built from the route
+//! table (`routes.rs`), and the JSON shapes come from the `models.rs`
+//! types themselves (schemars reads their `#[derive(JsonSchema)]`).
+//! With `[auth]` enabled, each operation also names the token scope it needs.
+
+use schemars::generate::SchemaSettings;
+use schemars::Schema;
+use serde_json::{json, Map, Value};
+
+use crate::handlers::{CONSISTENCY_HEADER, OWNER_KEY_HEADER, REQUEST_ID_HEADER, STORE_TYPE_HEADER};
+use crate::models::ErrorResponse;
+use crate::routes::{ApiGroup, ApiRoute, Body};
+use crate::services::Access;
+
+/// Methods an OpenAPI 3.0 path item can describe (WebDAV's are left out)
+const OPENAPI_METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Build the OpenAPI 3.0 document for these routes (`auth`: tokens are required)
+pub fn openapi_document(groups: &[ApiGroup], auth: bool) -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let error_schema = generator.subschema_for::<ErrorResponse>();
+
+    let mut paths = Map::new();
+    // Query structs are expanded once all schemas are known (see below)
+    let mut queries = Vec::new();
+    for group in groups {
+        for route in &group.routes {
+            let method = route.method.as_str().to_ascii_lowercase();
+            if !OPENAPI_METHODS.contains(&method.as_str()) {
+                continue;
+            }
+            let path = route.path();
+            if let Some(query) = route.query {
+                queries.push((path.clone(), method.clone(), query(&mut generator)));
+            }
+            let mut operation = operation(group, route, &mut generator);
+            if auth && !group.public {
+                operation["security"] = json!([{ "bearerAuth": [] }]);
+                operation["description"] =
+                    json!(format!("Token scope: `{}`", scope_for(group, route)));
+            }
+            // operationIds must be unique: one handler serving many methods gets a suffix
+            let handler_routes = groups
+                .iter()
+                .flat_map(|group| &group.routes)
+                .filter(|other| other.handler == route.handler)
+                .count();
+            if handler_routes > 1 {
+                operation["operationId"] = json!(format!("{}_{}", route.handler, method));
+            }
+            let item = paths.entry(path).or_insert_with(|| json!({}));
+            item[method] = operation;
+        }
+    }
+
+    // Transforms (like Option → "nullable") run as the definitions are taken
+    let schemas = generator.take_definitions(true);
+    for (path, method, query) in queries {
+        let parameters = query_parameters(&query, &schemas);
+        if let Some(list) = paths[&path][&method]["parameters"].as_array_mut() {
+            list.extend(parameters);
+        }
+    }
+
+    let mut document = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "AntTP-Compatible Rust Backend",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Store and read data on the Autonomi network (or in memory / on disk \
+                            for development). Errors share one JSON shape with a stable `code`."
+        },
+        "tags": groups
+            .iter()
+            .map(|group| json!({ "name": group.name, "description": group.description }))
+            .collect::<Vec<_>>(),
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "parameters": {
+                "StoreType": header_parameter(
+                    STORE_TYPE_HEADER,
+                    "Where data lives (missing = the server default)",
+                    json!({ "type": "string", "enum": ["memory", "disk", "network"] }),
+                ),
+                "Consistency": header_parameter(
+                    CONSISTENCY_HEADER,
+                    "`strong` skips the name cache (missing = cached)",
+                    json!({ "type": "string", "enum": ["cached", "strong"] }),
+                ),
+                "RequestId": header_parameter(
+                    REQUEST_ID_HEADER,
+                    "Your ID for this request, echoed on the response (missing = a new one)",
+                    json!({ "type": "string", "maxLength": 128, "pattern": "^[A-Za-z0-9_.:-]+$" }),
+                ),
+                "OwnerKey": header_parameter(
+                    OWNER_KEY_HEADER,
+                    "Key proving ownership of a PNR name",
+                    json!({ "type": "string" }),
+                ),
+            },
+            "responses": {
+                "Error": {
+                    "description": "Error (see `code`)",
+                    "content": { "application/json": { "schema": error_schema } }
+                }
+            }
+        }
+    });
+    if auth {
+        document["components"]["securitySchemes"] = json!({
+            "bearerAuth": {
+                "type": "http",
+                "scheme": "bearer",
+                "description": "API token (`Authorization: Bearer <token>`)"
+            }
+        });
+    }
+    document
+}
+
+/// Helper: The narrowest scope allowing a route ("write:key_value/{bucket}", "admin")
+fn scope_for(group: &ApiGroup, route: &ApiRoute) -> String {
+    if route.access == Access::Admin {
+        return "admin".to_string();
+    }
+    // The drive is checked as the bucket or archive a path points into
+    if group.name == "webdav" {
+        let access = route.access.as_str();
+        return format!(
+            "{}:key_value/{{bucket}} under kv/, {}:archive under archive/",
+            access, access
+        );
+    }
+    let name = group
+        .name_param
+        .filter(|param| route.named_in_body || route.path_params().contains(param))
+        .map(|param| format!("/{{{}}}", param))
+        .unwrap_or_default();
+    format!("{}:{}{}", route.access.as_str(), group.name, name)
+}
+
+/// Helper: One method on one path
+fn operation(
+    group: &ApiGroup,
+    route: &ApiRoute,
+    generator: &mut schemars::SchemaGenerator,
+) -> Value {
+    let mut parameters: Vec<Value> = route
+        .path_params()
+        .into_iter()
+        .map(|name| {
+            let mut parameter = json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" }
+            });
+            // `{object:.*}` catches the rest of the path, slashes included
+            if route.pattern.contains(&format!("{{{}:", name)) {
+                parameter["description"] = json!("May contain '/'");
+            }
+            parameter
+        })
+        .collect();
+    if route.context {
+        for name in ["StoreType", "Consistency", "RequestId"] {
+            parameters.push(json!({ "$ref": format!("#/components/parameters/{}", name) }));
+        }
+    }
+    if route.owner_key {
+        parameters.push(json!({ "$ref": "#/components/parameters/OwnerKey" }));
+    }
+
+    let mut success = json!({ "description": "Success" });
+    // HEAD answers carry headers only
+    if route.method != actix_web::http::Method::HEAD {
+        if let Some(content) = content(route.response, generator) {
+            success["content"] = content;
+        }
+    }
+
+    let mut operation = json!({
+        "tags": [group.name],
+        "summary": route.summary,
+        "operationId": route.handler,
+        "parameters": parameters,
+        "responses": {
+            "200": success,
+            "default": { "$ref": "#/components/responses/Error" }
+        }
+    });
+    if let Some(content) = content(route.request, generator) {
+        operation["requestBody"] = json!({ "required": true, "content": content });
+    }
+    operation
+}
+
+/// Helper: The `content` map of a body (`None` for no body)
+fn content(body: Body, generator: &mut schemars::SchemaGenerator) -> Option<Value> {
+    let binary = json!({ "type": "string", "format": "binary" });
+    let content = match body {
+        Body::Empty => return None,
+        Body::Json(schema) => json!({ "application/json": { "schema": schema(generator) } }),
+        Body::Object => json!({ "application/json": { "schema": { "type": "object" } } }),
+        Body::Binary(types) => Value::Object(
+            types
+                .iter()
+                .map(|content_type| (content_type.to_string(), json!({ "schema": binary })))
+                .collect(),
+        ),
+        Body::Multipart => json!({
+            "multipart/form-data": {
+                "schema": { "type": "object", "additionalProperties": binary }
+            }
+        }),
+    };
+    Some(content)
+}
+
+/// Helper: A query struct's fields, one query parameter each
+fn query_parameters(query: &Schema, schemas: &Map<String, Value>) -> Vec<Value> {
+    // `query` is a `$ref` to the struct's schema in `schemas`
+    let definition = query
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.rsplit('/').next())
+        .and_then(|name| schemas.get(name))
+        .unwrap_or(query.as_value());
+    let required = definition["required"].as_array();
+    let Some(properties) = definition["properties"].as_object() else {
+        return Vec::new();
+    };
+
+    properties
+        .iter()
+        .map(|(name, schema)| {
+            let mut schema = schema.clone();
+            let description = schema
+                .as_object_mut()
+                .and_then(|schema| schema.remove("description"));
+            let mut parameter = json!({
+                "name": name,
+                "in": "query",
+                "required": required.is_some_and(|required| required.contains(&json!(name))),
+                "schema": schema
+            });
+            if let Some(description) = description {
+                parameter["description"] = description;
+            }
+            parameter
+        })
+        .collect()
+}
+
+/// Helper: A reusable header parameter
+fn header_parameter(name: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "header",
+        "required": false,
+        "description": description,
+        "schema": schema
+    })
+}
@@ -0,0 +1,739 @@
+// src/routes.rs
+//! Route table - Every endpoint, described once
+//!
+//! For 1st Year CS Students:
+//! A list of endpoints tends to exist in many places: where routes are
+//! registered, in the API documentation, in the /anttp-0/command answer...
+//! and sooner or later one copy is out of date. Here there is ONE list:
+//! `configure` registers it with actix, `/openapi.json` is generated from
+//! it (with JSON Schemas of the `models.rs` types), and /anttp-0/command
+//! lists it. Add an endpoint here and all three know about it.
+//!
+//! The table also says what each route needs when `[auth]` is enabled:
+//! read or write access to its group (the data type of token scopes), or
+//! admin. `handlers/auth.rs` checks it.
+//!
+//! Order matters: actix tries routes in this order, so fixed paths like
+//! `/anttp-0/graph_entry/import` must come before their catch-alls.
+
+use actix_web::http::Method;
+use actix_web::web;
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+
+use crate::config::{Config, FeaturesConfig, LimitsConfig};
+use crate::handlers;
+use crate::models::{
+    ArchiveDiffResponse, ArchiveDownloadQuery, ArchiveManifestRequest, ArchivePatchRequest,
+    ArchiveResponse, ArchiveSyncPlan, ArchiveUpdateQuery, ArchiveUpdateResponse,
+    ArchiveUploadResponse, BucketRequest, ChunkData, ChunkRequest, ChunkResponse, GraphEntryData,
+    GraphEntryRequest, GraphExportQuery, GraphImportResponse, IssuedTokenResponse,
+    JsonGraphDocument, KeyValueBucket, KeyValueData, KeyValueRequest, KeyValueResponse,
+    ListBucketsResponse, ListObjectsQuery, ListObjectsResponse, ObjectVersionsResponse, PnrRequest,
+    PointerData, PointerRequest, PointerResponse, PublishRequest, PublishResponse, RegisterData,
+    RegisterHistoryEntry, RegisterRequest, RegisterResponse, ScratchpadData, ScratchpadRequest,
+    ScratchpadResponse, ScratchpadUpdateRequest, TokenInfo, TokenListResponse, TokenRequest,
+    VersionQuery, ZipUploadResponse,
+};
+use crate::services::Access;
+
+/// Builds the JSON Schema of a model (usually a `$ref` to it)
+pub type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+/// What a request or response body holds
+#[derive(Clone, Copy)]
+pub enum Body {
+    Empty,
+    /// JSON of a `models.rs` type
+    Json(SchemaFn),
+    /// JSON without a fixed model (built with `json!`)
+    Object,
+    /// Raw bytes, in one of these content types
+    Binary(&'static [&'static str]),
+    /// multipart/form-data file upload
+    Multipart,
+}
+
+/// One endpoint: method, path, what goes in and what comes out
+pub struct ApiRoute {
+    pub method: Method,
+    /// actix path pattern (`{object:.*}` also matches "/")
+    pub pattern: &'static str,
+    /// Name of the handler function (the OpenAPI operationId)
+    pub handler: &'static str,
+    pub summary: &'static str,
+    pub request: Body,
+    pub response: Body,
+    pub query: Option<SchemaFn>,
+    /// Reads the common headers (x-store-type, x-consistency, x-request-id)
+    pub context: bool,
+    /// Also reads x-owner-key
+    pub owner_key: bool,
+    /// What a token needs for it (read for GET/HEAD, write otherwise)
+    pub access: Access,
+    /// The group's name (bucket, PNR name) is in the JSON body, not the path
+    pub named_in_body: bool,
+    /// Adds the route to the app (`None` when its group registers itself)
+    register: Option<RegisterFn>,
+}
+
+/// Endpoints of one feature ("chunk", "archive", ...)
+pub struct ApiGroup {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub routes: Vec<ApiRoute>,
+    /// Open to everyone, even when `[auth]` is enabled
+    pub public: bool,
+    /// Path parameter that names the bucket (or PNR name) in a scope
+    /// like `write:key_value/notes`
+    pub name_param: Option<&'static str>,
+    /// Path prefix the group's `service` serves (e.g. "/webdav")
+    pub prefix: Option<&'static str>,
+    /// Registers the whole group at once (e.g. a scope) instead of route by route
+    service: Option<RegisterFn>,
+}
+
+/// Adds routes to the app (with the configured body limits at hand)
+type RegisterFn = fn(&mut web::ServiceConfig, &LimitsConfig);
+
+/// A route served by a handler function
+macro_rules! route {
+    ($method:ident $pattern:literal => $handler:path) => {
+        ApiRoute::new(
+            Method::$method,
+            $pattern,
+            stringify!($handler),
+            Some(|cfg: &mut web::ServiceConfig, _: &LimitsConfig| {
+                cfg.route($pattern, web::method(Method::$method).to($handler));
+            }),
+        )
+    };
+    // With its own request body limit (a `LimitsConfig` field)
+    ($method:ident $pattern:literal => $handler:path, limit $limit:ident) => {
+        ApiRoute::new(
+            Method::$method,
+            $pattern,
+            stringify!($handler),
+            Some(|cfg: &mut web::ServiceConfig, limits: &LimitsConfig| {
+                cfg.service(
+                    web::resource($pattern)
+                        .app_data(web::PayloadConfig::new(limits.$limit))
+                        .route(web::method(Method::$method).to($handler)),
+                );
+            }),
+        )
+    };
+    // Served by its group's `service` (documented here only)
+    // (any method name, also WebDAV's PROPFIND etc.)
+    ($method:ident $pattern:literal => $handler:path, in group) => {
+        ApiRoute::new(
+            Method::from_bytes(stringify!($method).as_bytes()).expect("valid method name"),
+            $pattern,
+            stringify!($handler),
+            None,
+        )
+    };
+}
+
+impl ApiRoute {
+    fn new(
+        method: Method,
+        pattern: &'static str,
+        handler: &'static str,
+        register: Option<RegisterFn>,
+    ) -> Self {
+        Self {
+            access: access_for(&method),
+            method,
+            pattern,
+            handler: handler.rsplit("::").next().unwrap_or(handler).trim(),
+            summary: "",
+            request: Body::Empty,
+            response: Body::Empty,
+            query: None,
+            context: true,
+            owner_key: false,
+            named_in_body: false,
+            register,
+        }
+    }
+
+    fn summary(mut self, summary: &'static str) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    fn json_body<T: JsonSchema>(mut self) -> Self {
+        self.request = Body::Json(schema_of::<T>);
+        self
+    }
+
+    fn body(mut self, body: Body) -> Self {
+        self.request = body;
+        self
+    }
+
+    fn json_response<T: JsonSchema>(mut self) -> Self {
+        self.response = Body::Json(schema_of::<T>);
+        self
+    }
+
+    fn response(mut self, body: Body) -> Self {
+        self.response = body;
+        self
+    }
+
+    fn query<T: JsonSchema>(mut self) -> Self {
+        self.query = Some(schema_of::<T>);
+        self
+    }
+
+    fn owner_key(mut self) -> Self {
+        self.owner_key = true;
+        self
+    }
+
+    fn without_context(mut self) -> Self {
+        self.context = false;
+        self
+    }
+
+    /// Needs other access than its method suggests (e.g. a POST that only reads)
+    fn access(mut self, access: Access) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// The handler checks the body's name against the token (see `authorize_name`)
+    fn named_in_body(mut self) -> Self {
+        self.named_in_body = true;
+        self
+    }
+
+    /// The path as people write it: `{object:.*}` → `{object}`
+    pub fn path(&self) -> String {
+        let mut path = String::with_capacity(self.pattern.len());
+        let (mut in_param, mut in_regex) = (false, false);
+        for c in self.pattern.chars() {
+            match c {
+                '{' => in_param = true,
+                '}' => (in_param, in_regex) = (false, false),
+                ':' if in_param => in_regex = true,
+                _ => {}
+            }
+            if !in_regex {
+                path.push(c);
+            }
+        }
+        path
+    }
+
+    /// Names of the `{...}` segments, in order
+    pub fn path_params(&self) -> Vec<&'static str> {
+        self.pattern
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split('}').next())
+            .map(|param| param.split(':').next().unwrap_or(param))
+            .collect()
+    }
+}
+
+impl ApiGroup {
+    fn new(name: &'static str, description: &'static str, routes: Vec<ApiRoute>) -> Self {
+        Self {
+            name,
+            description,
+            routes,
+            public: false,
+            name_param: None,
+            prefix: None,
+            service: None,
+        }
+    }
+
+    /// Serve everything under `prefix` with `service`
+    fn service(mut self, prefix: &'static str, service: RegisterFn) -> Self {
+        self.prefix = Some(prefix);
+        self.service = Some(service);
+        self
+    }
+
+    fn public(mut self) -> Self {
+        self.public = true;
+        self
+    }
+
+    fn named_by(mut self, param: &'static str) -> Self {
+        self.name_param = Some(param);
+        self
+    }
+
+    /// Every route needs the `admin` scope
+    fn admin_only(mut self) -> Self {
+        for route in &mut self.routes {
+            route.access = Access::Admin;
+        }
+        self
+    }
+}
+
+/// Read for methods that only look (GET, HEAD, OPTIONS, PROPFIND), write otherwise
+pub fn access_for(method: &Method) -> Access {
+    match method.as_str() {
+        "GET" | "HEAD" | "OPTIONS" | "PROPFIND" => Access::Read,
+        _ => Access::Write,
+    }
+}
+
+/// Helper: Schema of a model, as a fn pointer for the table
+fn schema_of<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<T>()
+}
+
+/// Add every enabled route in the table to the app (in table order)
+pub fn configure(cfg: &mut web::ServiceConfig, config: &Config) {
+    for group in enabled_groups(&config.features) {
+        if let Some(service) = group.service {
+            service(cfg, &config.limits);
+        }
+        for register in group.routes.iter().filter_map(|route| route.register) {
+            register(cfg, &config.limits);
+        }
+    }
+}
+
+/// The table without the groups switched off in `[features]`
+///
+/// For Students:
+/// A switched-off group is not registered at all, so its paths answer
+/// 404 - and it is missing from /openapi.json and /anttp-0/command too.
+pub fn enabled_groups(features: &FeaturesConfig) -> Vec<ApiGroup> {
+    api_groups()
+        .into_iter()
+        .filter(|group| features.enables(group.name))
+        .collect()
+}
+
+/// How many method + path pairs the API has
+pub fn endpoint_count(groups: &[ApiGroup]) -> usize {
+    groups.iter().map(|group| group.routes.len()).sum()
+}
+
+const OCTET_STREAM: &[&str] = &["application/octet-stream"];
+/// Archive files are sent with the type of their extension
+const ANY_TYPE: &[&str] = &["*/*"];
+
+/// The table itself
+pub fn api_groups() -> Vec<ApiGroup> {
+    vec![
+        // ========================================
+        // SYSTEM - health and commands
+        // ========================================
+        ApiGroup::new(
+            "system",
+            "Health check and command list",
+            vec![
+                route!(GET "/health" => crate::health_check)
+                    .summary("Health check")
+                    .response(Body::Object)
+                    .without_context(),
+                route!(GET "/anttp-0/command" => handlers::get_commands)
+                    .summary("List all commands (generated from the route table)")
+                    .response(Body::Object),
+            ],
+        )
+        .public(),
+        // ========================================
+        // DOCS - API description (features.api_docs)
+        // ========================================
+        ApiGroup::new(
+            "docs",
+            "This API description and a page to try it",
+            vec![
+                route!(GET "/openapi.json" => handlers::get_openapi)
+                    .summary("OpenAPI 3 description of this API")
+                    .response(Body::Object)
+                    .without_context(),
+                route!(GET "/docs" => handlers::get_api_docs)
+                    .summary("Interactive API viewer")
+                    .response(Body::Binary(&["text/html"]))
+                    .without_context(),
+            ],
+        )
+        .public(),
+        // ========================================
+        // CHUNK ENDPOINTS - /anttp-0/chunk
+        // ========================================
+        ApiGroup::new(
+            "chunk",
+            "Store and retrieve immutable data",
+            vec![
+                route!(POST "/anttp-0/chunk" => handlers::create_chunk)
+                    .summary("Create chunk (Base64 JSON)")
+                    .json_body::<ChunkRequest>()
+                    .json_response::<ChunkResponse>(),
+                route!(GET "/anttp-0/chunk/{address}" => handlers::get_chunk)
+                    .summary("Get chunk (Base64 JSON)")
+                    .json_response::<ChunkData>(),
+                route!(POST "/anttp-0/binary/chunk" => handlers::create_chunk_binary)
+                    .summary("Create chunk (raw bytes)")
+                    .body(Body::Binary(OCTET_STREAM))
+                    .json_response::<ChunkResponse>(),
+                route!(GET "/anttp-0/binary/chunk/{address}" => handlers::get_chunk_binary)
+                    .summary("Get chunk (raw bytes)")
+                    .response(Body::Binary(OCTET_STREAM)),
+            ],
+        ),
+        // ========================================
+        // REGISTER ENDPOINTS - /anttp-0/register
+        // ========================================
+        ApiGroup::new(
+            "register",
+            "Mutable key-value storage with history",
+            vec![
+                route!(POST "/anttp-0/register" => handlers::create_register)
+                    .summary("Create register")
+                    .json_body::<RegisterRequest>()
+                    .json_response::<RegisterResponse>(),
+                route!(GET "/anttp-0/register/{address}" => handlers::get_register)
+                    .summary("Get current value")
+                    .json_response::<RegisterData>(),
+                route!(PUT "/anttp-0/register/{address}" => handlers::update_register)
+                    .summary("Update register")
+                    .json_body::<RegisterRequest>()
+                    .response(Body::Object),
+                route!(GET "/anttp-0/register_history/{address}" => handlers::get_register_history)
+                    .summary("Get all values, oldest first")
+                    .json_response::<Vec<RegisterHistoryEntry>>(),
+            ],
+        ),
+        // ========================================
+        // POINTER ENDPOINTS - /anttp-0/pointer
+        // ========================================
+        ApiGroup::new(
+            "pointer",
+            "Mutable address references",
+            vec![
+                route!(POST "/anttp-0/pointer" => handlers::create_pointer)
+                    .summary("Create pointer")
+                    .json_body::<PointerRequest>()
+                    .json_response::<PointerResponse>(),
+                route!(GET "/anttp-0/pointer/{address}" => handlers::get_pointer)
+                    .summary("Get pointer target")
+                    .json_response::<PointerData>(),
+                route!(PUT "/anttp-0/pointer/{address}" => handlers::update_pointer)
+                    .summary("Update pointer target")
+                    .json_body::<PointerRequest>()
+                    .response(Body::Object),
+            ],
+        ),
+        // ========================================
+        // SCRATCHPADS - public and private
+        // ========================================
+        ApiGroup::new(
+            "scratchpad",
+            "Public and private mutable data",
+            vec![
+                route!(POST "/anttp-0/public_scratchpad" => handlers::create_public_scratchpad)
+                    .summary("Create public scratchpad")
+                    .json_body::<ScratchpadRequest>()
+                    .json_response::<ScratchpadResponse>(),
+                route!(PUT "/anttp-0/public_scratchpad/{address}/{name}"
+                    => handlers::update_public_scratchpad)
+                .summary("Update public scratchpad")
+                .json_body::<ScratchpadUpdateRequest>()
+                .response(Body::Object),
+                route!(GET "/anttp-0/public_scratchpad/{address}"
+                    => handlers::get_public_scratchpad)
+                .summary("Get public scratchpad")
+                .json_response::<ScratchpadData>(),
+                route!(POST "/anttp-0/private_scratchpad" => handlers::create_private_scratchpad)
+                    .summary("Create private scratchpad")
+                    .json_body::<ScratchpadRequest>()
+                    .json_response::<ScratchpadResponse>(),
+                route!(PUT "/anttp-0/private_scratchpad/{address}/{name}"
+                    => handlers::update_private_scratchpad)
+                .summary("Update private scratchpad")
+                .json_body::<ScratchpadUpdateRequest>()
+                .response(Body::Object),
+                route!(GET "/anttp-0/private_scratchpad/{address}/{name}"
+                    => handlers::get_private_scratchpad)
+                .summary("Get private scratchpad (the name decrypts it)")
+                .json_response::<ScratchpadData>(),
+            ],
+        ),
+        // ========================================
+        // ARCHIVE ENDPOINTS - /anttp-0/multipart/public_archive
+        // ========================================
+        ApiGroup::new(
+            "archive",
+            "File collections (multipart, zip upload; zip/tar download)",
+            vec![
+                route!(POST "/anttp-0/multipart/public_archive" => handlers::create_archive)
+                    .summary("Create archive (file paths from the part names)")
+                    .body(Body::Multipart)
+                    .json_response::<ArchiveUploadResponse>(),
+                route!(POST "/anttp-0/multipart/public_archive/{path:.*}"
+                    => handlers::create_archive_with_path)
+                .summary("Create archive with every file under a folder")
+                .body(Body::Multipart)
+                .json_response::<ArchiveUploadResponse>(),
+                route!(POST "/anttp-0/zip/public_archive"
+                    => handlers::create_archive_from_zip, limit zip_body_bytes)
+                .summary("Create archive from a zip file (request body)")
+                .body(Body::Binary(&["application/zip"]))
+                .json_response::<ZipUploadResponse>(),
+                route!(PUT "/anttp-0/multipart/public_archive/{address}"
+                    => handlers::update_archive_files)
+                .summary("Add or replace files (new version)")
+                .query::<ArchiveUpdateQuery>()
+                .body(Body::Multipart)
+                .json_response::<ArchiveUpdateResponse>(),
+                route!(GET "/anttp-0/public_archive/{address}" => handlers::get_archive_root)
+                    .summary("List files, or download as zip/tar/tar.gz with ?format=")
+                    .query::<ArchiveDownloadQuery>()
+                    .response(Body::Object),
+                route!(PATCH "/anttp-0/public_archive/{address}" => handlers::patch_archive)
+                    .summary("Delete or rename paths (new version)")
+                    .query::<ArchiveUpdateQuery>()
+                    .json_body::<ArchivePatchRequest>()
+                    .json_response::<ArchiveUpdateResponse>(),
+                route!(GET "/anttp-0/public_archive_diff/{from}/{to}"
+                    => handlers::get_archive_diff)
+                .summary("Compare two archives")
+                .json_response::<ArchiveDiffResponse>(),
+                route!(POST "/anttp-0/public_archive_diff/{address}"
+                    => handlers::diff_archive_with_manifest)
+                .summary("Compare an archive with a local file-hash list")
+                .json_body::<ArchiveManifestRequest>()
+                .json_response::<ArchiveDiffResponse>()
+                .access(Access::Read),
+                route!(POST "/anttp-0/public_archive_sync_plan/{address}"
+                    => handlers::get_archive_sync_plan)
+                .summary("What to upload and change to reach a local state")
+                .json_body::<ArchiveManifestRequest>()
+                .json_response::<ArchiveSyncPlan>()
+                .access(Access::Read),
+                route!(GET "/anttp-0/public_archive/{address}/{path:.*}"
+                    => handlers::get_archive_file)
+                .summary("Get a file (following the archive's app-conf.json)")
+                .response(Body::Binary(ANY_TYPE)),
+            ],
+        ),
+        // ========================================
+        // TARCHIVE ENDPOINT - /anttp-0/multipart/tarchive
+        // ========================================
+        ApiGroup::new(
+            "tarchive",
+            "Tar-based archives",
+            vec![
+                route!(POST "/anttp-0/multipart/tarchive" => handlers::create_tarchive)
+                    .summary("Create tarchive")
+                    .body(Body::Multipart)
+                    .json_response::<ArchiveResponse>(),
+            ],
+        ),
+        // ========================================
+        // GRAPH ENDPOINTS - /anttp-0/graph_entry
+        // ========================================
+        ApiGroup::new(
+            "graph",
+            "Graph data structures",
+            vec![
+                route!(POST "/anttp-0/graph_entry" => handlers::create_graph_entry)
+                    .summary("Create graph entry")
+                    .json_body::<GraphEntryRequest>()
+                    .response(Body::Object),
+                route!(GET "/anttp-0/graph_entry/{address}" => handlers::get_graph_entry)
+                    .summary("Get graph entry")
+                    .json_response::<GraphEntryData>(),
+                route!(POST "/anttp-0/graph_entry/import" => handlers::import_graph)
+                    .summary("Bulk import a JSON Graph Format document")
+                    .json_body::<JsonGraphDocument>()
+                    .json_response::<GraphImportResponse>(),
+                route!(GET "/anttp-0/graph_entry/{address}/export" => handlers::export_graph)
+                    .summary("Export the reachable graph as DOT, JSON or GraphML")
+                    .query::<GraphExportQuery>()
+                    .response(Body::Binary(&[
+                        "text/vnd.graphviz",
+                        "application/json",
+                        "application/graphml+xml",
+                    ])),
+            ],
+        ),
+        // ========================================
+        // PNR ENDPOINTS - /anttp-0/pnr
+        // ========================================
+        ApiGroup::new(
+            "pnr",
+            "Pointer Name Registry (DNS-like)",
+            vec![
+                route!(POST "/anttp-0/pnr" => handlers::create_pnr)
+                    .summary("Create PNR")
+                    .json_body::<PnrRequest>()
+                    .response(Body::Object)
+                    .owner_key()
+                    .named_in_body(),
+                route!(PUT "/anttp-0/pnr/{name}" => handlers::update_pnr)
+                    .summary("Replace PNR records")
+                    .json_body::<PnrRequest>()
+                    .response(Body::Object)
+                    .owner_key(),
+                route!(GET "/anttp-0/pnr/{name}" => handlers::get_pnr)
+                    .summary("Get PNR records")
+                    .response(Body::Object),
+                route!(PATCH "/anttp-0/pnr/{name}" => handlers::append_pnr)
+                    .summary("Append records to a PNR")
+                    .json_body::<PnrRequest>()
+                    .response(Body::Object)
+                    .owner_key(),
+                route!(DELETE "/anttp-0/pnr/{name}" => handlers::delete_pnr)
+                    .summary("Delete a name (or one record via sub.name)")
+                    .response(Body::Object)
+                    .owner_key(),
+            ],
+        )
+        .named_by("name"),
+        // ========================================
+        // KEY/VALUE ENDPOINTS - /anttp-0/key_value
+        // ========================================
+        ApiGroup::new(
+            "key_value",
+            "Object storage with buckets",
+            vec![
+                route!(POST "/anttp-0/key_value" => handlers::create_key_value)
+                    .summary("Create object (Base64 JSON)")
+                    .json_body::<KeyValueRequest>()
+                    .json_response::<KeyValueResponse>()
+                    .named_in_body(),
+                route!(GET "/anttp-0/key_value" => handlers::list_buckets)
+                    .summary("List buckets")
+                    .json_response::<ListBucketsResponse>(),
+                route!(GET "/anttp-0/key_value/{bucket}" => handlers::list_key_values)
+                    .summary("List objects (prefix, delimiter, pages)")
+                    .query::<ListObjectsQuery>()
+                    .json_response::<ListObjectsResponse>(),
+                route!(PUT "/anttp-0/key_value/{bucket}" => handlers::create_bucket)
+                    .summary("Create bucket (body optional)")
+                    .json_body::<BucketRequest>()
+                    .json_response::<KeyValueBucket>(),
+                route!(DELETE "/anttp-0/key_value/{bucket}" => handlers::delete_bucket)
+                    .summary("Delete an empty bucket")
+                    .response(Body::Object),
+                route!(GET "/anttp-0/key_value/{bucket}/{object:.*}" => handlers::get_key_value)
+                    .summary("Get object (Base64 JSON)")
+                    .query::<VersionQuery>()
+                    .json_response::<KeyValueData>(),
+                route!(HEAD "/anttp-0/key_value/{bucket}/{object:.*}"
+                    => handlers::head_key_value)
+                .summary("Object metadata (Content-Type, size, ETag, Last-Modified)")
+                .query::<VersionQuery>(),
+                route!(DELETE "/anttp-0/key_value/{bucket}/{object:.*}"
+                    => handlers::delete_key_value)
+                .summary("Delete object")
+                .query::<VersionQuery>()
+                .response(Body::Object),
+                route!(GET "/anttp-0/key_value_versions/{bucket}/{object:.*}"
+                    => handlers::list_key_value_versions)
+                .summary("List object versions")
+                .json_response::<ObjectVersionsResponse>(),
+                route!(PUT "/anttp-0/binary/key_value/{bucket}/{object:.*}"
+                    => handlers::create_key_value_binary)
+                .summary("Create object (raw bytes, Content-Type kept)")
+                .body(Body::Binary(OCTET_STREAM))
+                .json_response::<KeyValueResponse>(),
+                route!(GET "/anttp-0/binary/key_value/{bucket}/{object:.*}"
+                    => handlers::get_key_value_binary)
+                .summary("Get object (raw bytes)")
+                .query::<VersionQuery>()
+                .response(Body::Binary(OCTET_STREAM)),
+            ],
+        )
+        .named_by("bucket"),
+        // ========================================
+        // PUBLIC DATA ENDPOINTS - /anttp-0/binary/public_data
+        // ========================================
+        ApiGroup::new(
+            "public_data",
+            "Simple binary storage",
+            vec![
+                route!(POST "/anttp-0/binary/public_data" => handlers::create_public_data)
+                    .summary("Store public data")
+                    .body(Body::Binary(OCTET_STREAM))
+                    .response(Body::Object),
+                route!(GET "/anttp-0/binary/public_data/{address}" => handlers::get_public_data)
+                    .summary("Get public data")
+                    .response(Body::Binary(OCTET_STREAM)),
+            ],
+        ),
+        // ========================================
+        // ADMIN ENDPOINTS - /anttp-0/admin
+        // ========================================
+        ApiGroup::new(
+            "admin",
+            "Publish a server-side directory (needs PUBLISH_ROOT), show the configuration, \
+             manage API tokens",
+            vec![
+                route!(GET "/anttp-0/admin/config" => handlers::get_config)
+                    .summary("Effective server configuration (secrets hidden)")
+                    .json_response::<Config>()
+                    .without_context(),
+                route!(POST "/anttp-0/admin/publish_directory" => handlers::publish_directory)
+                    .summary("Publish a directory as an archive")
+                    .json_body::<PublishRequest>()
+                    .json_response::<PublishResponse>()
+                    .owner_key(),
+                route!(POST "/anttp-0/admin/token" => handlers::issue_token)
+                    .summary("Issue an API token (its secret is only shown here)")
+                    .json_body::<TokenRequest>()
+                    .json_response::<IssuedTokenResponse>()
+                    .without_context(),
+                route!(GET "/anttp-0/admin/token" => handlers::list_tokens)
+                    .summary("List API tokens (without secrets)")
+                    .json_response::<TokenListResponse>()
+                    .without_context(),
+                route!(DELETE "/anttp-0/admin/token/{id}" => handlers::revoke_token)
+                    .summary("Revoke an issued API token")
+                    .json_response::<TokenInfo>()
+                    .without_context(),
+            ],
+        )
+        .admin_only(),
+        // ========================================
+        // WEBDAV - /webdav (PROPFIND, MKCOL, MOVE, ...)
+        // ========================================
+        ApiGroup::new(
+            "webdav",
+            "Browse archives (read-only snapshots) and key/value buckets as a drive",
+            vec![
+                route!(OPTIONS "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("Supported methods and DAV class"),
+                route!(PROPFIND "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("List a folder (Depth: 0 or 1)")
+                    .response(Body::Binary(&["application/xml"])),
+                route!(GET "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("Download a file")
+                    .response(Body::Binary(OCTET_STREAM)),
+                route!(HEAD "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("File metadata"),
+                route!(PUT "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("Upload a file")
+                    .body(Body::Binary(OCTET_STREAM)),
+                route!(MKCOL "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("Create a folder"),
+                route!(DELETE "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("Delete a file or folder"),
+                route!(MOVE "/webdav/{path:.*}" => handlers::webdav_request, in group)
+                    .summary("Rename a file or folder (Destination header)"),
+            ],
+        )
+        .service("/webdav", |cfg, limits| {
+            cfg.service(
+                web::scope("/webdav")
+                    .app_data(web::PayloadConfig::new(limits.webdav_body_bytes))
+                    // Every path and method - webdav_request works out the rest
+                    .default_service(web::route().to(handlers::webdav_request)),
+            );
+        }),
+    ]
+}
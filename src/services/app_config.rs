@@ -0,0 +1,295 @@
+// src/services/app_config.rs
+//! App config - Website routing rules read from an archive
+//!
+//! For 1st Year CS Students:
+//! A plain archive only answers "here is the file at exactly this path".
+//! Real websites need more: `/old-page` should redirect, `/app/settings`
+//! in a single-page app should serve `index.html`, and a missing page
+//! should show a friendly 404 page. The site's author ships those rules
+//! in `app-conf.json`, and `AppRoutes` decides what to serve.
+//!
+//! Anyone can publish an archive, so its headers are limited to a short
+//! list (caching and browser security policies). A site can't change a
+//! file's `Content-Type`, set cookies or loosen CORS for the API, and its
+//! `Content-Security-Policy` only adds to the server's `sandbox`.
+
+use globset::{GlobBuilder, GlobMatcher};
+use std::collections::HashMap;
+
+use crate::models::AppConfig;
+
+/// Name of the config file at the root of an archive
+pub const APP_CONFIG_FILE: &str = "app-conf.json";
+
+/// Headers an app config may set (compared case-insensitively)
+pub const ALLOWED_HEADERS: &[&str] = &[
+    "cache-control",
+    "content-language",
+    "content-security-policy",
+    "cross-origin-embedder-policy",
+    "cross-origin-opener-policy",
+    "cross-origin-resource-policy",
+    "expires",
+    "permissions-policy",
+    "referrer-policy",
+    "x-frame-options",
+];
+
+/// Why an archive's app config can't be used
+#[derive(Debug, thiserror::Error)]
+pub enum AppConfigError {
+    #[error("Invalid {APP_CONFIG_FILE}: {0}")]
+    Parse(String),
+    #[error("Invalid path pattern '{0}': {1}")]
+    Pattern(String, String),
+    #[error("Invalid redirect status {0} (use 301, 302, 303, 307 or 308)")]
+    RedirectStatus(u16),
+    #[error("Invalid error page status '{0}'")]
+    ErrorStatus(String),
+    #[error("Invalid header '{0}'")]
+    Header(String),
+    #[error("Header '{0}' can't be set by an app config")]
+    HeaderNotAllowed(String),
+}
+
+/// What to answer for a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppRoute {
+    /// Redirect to `location` (an archive path starting with "/", or a URL)
+    Redirect { location: String, status: u16 },
+    /// Serve the archive file at `path` with this status
+    File { path: String, status: u16 },
+    /// Nothing matched and there is no 404 page
+    NotFound,
+}
+
+/// An app config, ready to match request paths against
+#[derive(Debug)]
+pub struct AppRoutes {
+    redirects: Vec<(GlobMatcher, String, u16)>,
+    rewrites: Vec<(GlobMatcher, String)>,
+    headers: Vec<(GlobMatcher, Vec<(String, String)>)>,
+    error_pages: HashMap<u16, String>,
+    clean_urls: bool,
+}
+
+impl AppRoutes {
+    /// Parse and check the content of an `app-conf.json`
+    pub fn parse(content: &[u8]) -> Result<Self, AppConfigError> {
+        let config: AppConfig =
+            serde_json::from_slice(content).map_err(|e| AppConfigError::Parse(e.to_string()))?;
+        Self::compile(config)
+    }
+
+    /// Compile the glob patterns of a config
+    pub fn compile(config: AppConfig) -> Result<Self, AppConfigError> {
+        let mut redirects = Vec::with_capacity(config.redirects.len());
+        for redirect in config.redirects {
+            let status = redirect.status.unwrap_or(301);
+            if ![301, 302, 303, 307, 308].contains(&status) {
+                return Err(AppConfigError::RedirectStatus(status));
+            }
+            redirects.push((compile_pattern(&redirect.from)?, redirect.to, status));
+        }
+
+        // routeMap is an object (no order), so its longest patterns win
+        let mut route_map: Vec<(String, String)> = config.route_map.into_iter().collect();
+        route_map.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        let mut rewrites = Vec::with_capacity(config.rewrites.len() + route_map.len());
+        for (from, to) in config
+            .rewrites
+            .into_iter()
+            .map(|rewrite| (rewrite.from, rewrite.to))
+            .chain(route_map)
+        {
+            rewrites.push((compile_pattern(&from)?, trim_path(&to).to_string()));
+        }
+
+        let mut headers = Vec::with_capacity(config.headers.len());
+        for rule in config.headers {
+            let mut values: Vec<(String, String)> = rule.headers.into_iter().collect();
+            values.sort();
+            for (name, value) in &values {
+                if !is_header_name(name) || value.chars().any(|c| c.is_control() && c != '\t') {
+                    return Err(AppConfigError::Header(name.clone()));
+                }
+                if !ALLOWED_HEADERS
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(name))
+                {
+                    return Err(AppConfigError::HeaderNotAllowed(name.clone()));
+                }
+            }
+            headers.push((compile_pattern(&rule.path)?, values));
+        }
+
+        let mut error_pages = HashMap::with_capacity(config.error_pages.len());
+        for (status, page) in config.error_pages {
+            let code = status
+                .parse::<u16>()
+                .ok()
+                .filter(|code| (400..600).contains(code))
+                .ok_or(AppConfigError::ErrorStatus(status))?;
+            error_pages.insert(code, trim_path(&page).to_string());
+        }
+
+        Ok(Self {
+            redirects,
+            rewrites,
+            headers,
+            error_pages,
+            clean_urls: config.clean_urls,
+        })
+    }
+
+    /// Decide what to serve for `path` ("" is the archive root)
+    ///
+    /// `exists` says whether the archive has a file at a path. Order:
+    /// redirects, the file itself (or its clean URL / index.html), rewrites,
+    /// then the 404 page.
+    pub fn resolve(&self, path: &str, exists: impl Fn(&str) -> bool) -> AppRoute {
+        let path = trim_path(path);
+        if let Some((_, to, status)) = self.redirects.iter().find(|(glob, ..)| glob.is_match(path))
+        {
+            let location = if to.contains("://") {
+                to.clone()
+            } else {
+                format!("/{}", trim_path(to))
+            };
+            return AppRoute::Redirect {
+                location,
+                status: *status,
+            };
+        }
+
+        let mut candidates = Vec::with_capacity(3);
+        if !path.is_empty() {
+            candidates.push(path.to_string());
+            if self.clean_urls && !path.ends_with(".html") {
+                candidates.push(format!("{}.html", path));
+            }
+        }
+        candidates.push(match path {
+            "" => "index.html".to_string(),
+            folder => format!("{}/index.html", folder),
+        });
+        if let Some(found) = candidates.into_iter().find(|candidate| exists(candidate)) {
+            return AppRoute::File {
+                path: found,
+                status: 200,
+            };
+        }
+
+        let rewritten = self
+            .rewrites
+            .iter()
+            .find(|(glob, to)| glob.is_match(path) && exists(to));
+        if let Some((_, to)) = rewritten {
+            return AppRoute::File {
+                path: to.clone(),
+                status: 200,
+            };
+        }
+
+        match self.error_page(404) {
+            Some(page) if exists(page) => AppRoute::File {
+                path: page.to_string(),
+                status: 404,
+            },
+            _ => AppRoute::NotFound,
+        }
+    }
+
+    /// The configured page for an error status, if any
+    pub fn error_page(&self, status: u16) -> Option<&str> {
+        self.error_pages.get(&status).map(String::as_str)
+    }
+
+    /// Extra headers for a file path (later rules override earlier ones)
+    pub fn headers_for(&self, path: &str) -> Vec<(&str, &str)> {
+        let path = trim_path(path);
+        let mut headers: Vec<(&str, &str)> = Vec::new();
+        for (_, values) in self.headers.iter().filter(|(glob, _)| glob.is_match(path)) {
+            for (name, value) in values {
+                headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+                headers.push((name, value));
+            }
+        }
+        headers
+    }
+}
+
+/// Helper: Compile a path glob ("/" at the start is optional)
+fn compile_pattern(pattern: &str) -> Result<GlobMatcher, AppConfigError> {
+    GlobBuilder::new(trim_path(pattern))
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| AppConfigError::Pattern(pattern.to_string(), e.to_string()))
+}
+
+/// Helper: Archive paths have no leading or trailing "/"
+fn trim_path(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+/// Helper: HTTP header names are "token" characters only
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper: an app config with one header rule for every path
+    fn with_header(name: &str, value: &str) -> Result<AppRoutes, AppConfigError> {
+        let config = serde_json::json!({
+            "headers": [{"path": "**", "headers": {name: value}}]
+        });
+        AppRoutes::parse(config.to_string().as_bytes())
+    }
+
+    #[test]
+    fn policy_headers_are_allowed() {
+        let routes = with_header("Content-Security-Policy", "default-src 'self'").unwrap();
+        assert_eq!(
+            routes.headers_for("/index.html"),
+            vec![("Content-Security-Policy", "default-src 'self'")]
+        );
+        assert!(with_header("cache-control", "max-age=60").is_ok());
+    }
+
+    #[test]
+    fn sensitive_headers_are_refused() {
+        for name in [
+            "Content-Type",
+            "Set-Cookie",
+            "Location",
+            "Access-Control-Allow-Origin",
+            "Access-Control-Allow-Credentials",
+            "X-Content-Type-Options",
+        ] {
+            assert!(
+                matches!(with_header(name, "x"), Err(AppConfigError::HeaderNotAllowed(n)) if n == name),
+                "{} should be refused",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_headers_are_refused() {
+        assert!(matches!(
+            with_header("Cache Control", "x"),
+            Err(AppConfigError::Header(_))
+        ));
+        assert!(matches!(
+            with_header("Cache-Control", "a\r\nSet-Cookie: x"),
+            Err(AppConfigError::Header(_))
+        ));
+    }
+}
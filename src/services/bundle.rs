@@ -0,0 +1,459 @@
+// src/services/bundle.rs
+//! Bundles - Stream an archive as a zip, tar or tar.gz file (and read zips)
+//!
+//! For 1st Year CS Students:
+//! A big archive could be gigabytes, so we never build the whole zip in
+//! memory. Instead we add one file at a time and send whatever bytes the
+//! zip/tar writer produced so far, then move on to the next file - like
+//! a conveyor belt instead of a warehouse.
+//!
+//! Going the other way, an uploaded zip can't be trusted: a "zip bomb" is
+//! a tiny file that expands to terabytes, and entry names like
+//! "../../etc/passwd" try to escape the folder. `read_zip` checks both.
+
+use bytes::Bytes;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::Stream;
+use std::io::{Cursor, Read, Write};
+use std::sync::{Arc, Mutex};
+use zip::write::{SimpleFileOptions, StreamWriter};
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::models::{ArchiveEntry, BundleFormat};
+use crate::services::NetworkService;
+
+/// Most entries an uploaded zip may have
+pub const ZIP_MAX_ENTRIES: usize = 10_000;
+/// Highest compression ratio allowed for a large entry
+pub const ZIP_MAX_RATIO: u64 = 200;
+/// Entries smaller than this skip the ratio check (tiny files compress well)
+const ZIP_RATIO_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Why an uploaded zip was refused as a whole
+#[derive(Debug, thiserror::Error)]
+pub enum ZipImportError {
+    #[error("Not a valid zip file: {0}")]
+    NotAZip(String),
+    #[error("Zip has {0} entries (limit {ZIP_MAX_ENTRIES})")]
+    TooManyEntries(usize),
+    #[error("Zip expands to more than {0} bytes (limits.zip_expanded_bytes)")]
+    TooLarge(u64),
+    #[error("Entry {0} is compressed {1}:1 (limit {ZIP_MAX_RATIO}:1) - possible zip bomb")]
+    SuspiciousRatio(String, u64),
+    #[error("Entry {0} doesn't match the size in its header")]
+    SizeMismatch(String),
+}
+
+/// One entry of an uploaded zip, as found
+#[derive(Debug)]
+pub struct ZipImportEntry {
+    /// Name exactly as stored in the zip (not yet checked as a path)
+    pub name: String,
+    pub outcome: ZipImportOutcome,
+}
+
+/// What reading a zip entry gave
+#[derive(Debug)]
+pub enum ZipImportOutcome {
+    /// A regular file with its content and modification time
+    File { content: Bytes, modified: Option<i64> },
+    /// Nothing to store (folders, symbolic links)
+    Skipped(String),
+    /// The entry couldn't be read (encrypted, unsupported, corrupt)
+    Rejected(String),
+}
+
+/// Read every entry of an uploaded zip, enforcing the zip bomb limits
+///
+/// Sizes in the zip's headers can lie, so each entry is read with a cap
+/// and the real size compared; `max_total` bounds the bytes actually read
+/// (everything stays in memory). This does CPU-heavy decompression - call
+/// it from `web::block`.
+pub fn read_zip(body: Bytes, max_total: u64) -> Result<Vec<ZipImportEntry>, ZipImportError> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(body)).map_err(|e| ZipImportError::NotAZip(e.to_string()))?;
+    if archive.len() > ZIP_MAX_ENTRIES {
+        return Err(ZipImportError::TooManyEntries(archive.len()));
+    }
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut total: u64 = 0;
+    for index in 0..archive.len() {
+        let fallback_name = archive
+            .name_for_index(index)
+            .map_or_else(|| format!("#{}", index), str::to_string);
+        let mut file = match archive.by_index(index) {
+            Ok(file) => file,
+            Err(e) => {
+                entries.push(ZipImportEntry {
+                    name: fallback_name,
+                    outcome: ZipImportOutcome::Rejected(e.to_string()),
+                });
+                continue;
+            }
+        };
+        let name = file.name().to_string();
+        if file.is_dir() {
+            entries.push(ZipImportEntry {
+                name,
+                outcome: ZipImportOutcome::Skipped("directory".to_string()),
+            });
+            continue;
+        }
+        if file.is_symlink() {
+            entries.push(ZipImportEntry {
+                name,
+                outcome: ZipImportOutcome::Skipped("symbolic link".to_string()),
+            });
+            continue;
+        }
+
+        let size = file.size();
+        if size >= ZIP_RATIO_MIN_BYTES {
+            let ratio = size / file.compressed_size().max(1);
+            if ratio > ZIP_MAX_RATIO {
+                return Err(ZipImportError::SuspiciousRatio(name, ratio));
+            }
+        }
+        let remaining = max_total - total;
+        if size > remaining {
+            return Err(ZipImportError::TooLarge(max_total));
+        }
+
+        // Never read more than the header promised (plus one byte to catch a lie)
+        let mut content = Vec::with_capacity(size.min(ZIP_RATIO_MIN_BYTES) as usize);
+        let read = (&mut file).take(size + 1).read_to_end(&mut content);
+        // Count what was really read, whatever the header said
+        total = total.saturating_add(content.len() as u64);
+        if total > max_total {
+            return Err(ZipImportError::TooLarge(max_total));
+        }
+        if let Err(e) = read {
+            entries.push(ZipImportEntry {
+                name,
+                outcome: ZipImportOutcome::Rejected(e.to_string()),
+            });
+            continue;
+        }
+        if content.len() as u64 != size {
+            return Err(ZipImportError::SizeMismatch(name));
+        }
+
+        let modified = file.last_modified().and_then(zip_timestamp);
+        entries.push(ZipImportEntry {
+            name,
+            outcome: ZipImportOutcome::File {
+                content: Bytes::from(content),
+                modified,
+            },
+        });
+    }
+    Ok(entries)
+}
+
+/// Stream `entries` of an archive as one bundle file
+///
+/// Files are read from the store one at a time, so memory use stays
+/// around the size of the largest file. If a file can't be read halfway
+/// through, the stream ends with an error (the client sees a cut-off
+/// download).
+pub fn stream_bundle(
+    network: Arc<NetworkService>,
+    entries: Vec<ArchiveEntry>,
+    format: BundleFormat,
+    use_network: bool,
+) -> impl Stream<Item = anyhow::Result<Bytes>> {
+    let buffer = SharedBuffer::default();
+    let writer = BundleWriter::new(format, buffer.clone());
+    let state = BundleState {
+        network,
+        entries: entries.into_iter(),
+        writer: Some(writer),
+        buffer,
+        use_network,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        match state.next_piece().await {
+            Ok(Some(piece)) => Some((Ok(piece), state)),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("❌ Archive download failed: {}", e);
+                // Stop after reporting the error once
+                state.writer = None;
+                Some((Err(e), state))
+            }
+        }
+    })
+}
+
+/// Where the stream is: files still to add and the half-written bundle
+struct BundleState {
+    network: Arc<NetworkService>,
+    entries: std::vec::IntoIter<ArchiveEntry>,
+    /// `None` once the bundle is finished (or failed)
+    writer: Option<BundleWriter>,
+    buffer: SharedBuffer,
+    use_network: bool,
+}
+
+impl BundleState {
+    /// Add files until the writer has produced some bytes to send
+    async fn next_piece(&mut self) -> anyhow::Result<Option<Bytes>> {
+        loop {
+            let Some(writer) = self.writer.as_mut() else {
+                return Ok(None);
+            };
+            match self.entries.next() {
+                Some(entry) => {
+                    let content = self
+                        .network
+                        .get_chunk(&entry.address, self.use_network)
+                        .await?;
+                    writer.add(&entry, &content)?;
+                }
+                None => {
+                    if let Some(writer) = self.writer.take() {
+                        writer.finish()?;
+                    }
+                }
+            }
+            let piece = self.buffer.take();
+            if !piece.is_empty() {
+                return Ok(Some(piece));
+            }
+        }
+    }
+}
+
+/// The zip/tar writer for one download
+enum BundleWriter {
+    Zip(ZipWriter<StreamWriter<SharedBuffer>>),
+    Tar(tar::Builder<SharedBuffer>),
+    TarGz(tar::Builder<GzEncoder<SharedBuffer>>),
+}
+
+impl BundleWriter {
+    fn new(format: BundleFormat, buffer: SharedBuffer) -> Self {
+        match format {
+            BundleFormat::Zip => BundleWriter::Zip(ZipWriter::new_stream(buffer)),
+            BundleFormat::Tar => BundleWriter::Tar(tar::Builder::new(buffer)),
+            BundleFormat::TarGz => BundleWriter::TarGz(tar::Builder::new(GzEncoder::new(
+                buffer,
+                Compression::default(),
+            ))),
+        }
+    }
+
+    /// Append one file (keeping its path and modification time)
+    fn add(&mut self, entry: &ArchiveEntry, content: &[u8]) -> anyhow::Result<()> {
+        match self {
+            BundleWriter::Zip(zip) => {
+                let options = SimpleFileOptions::default()
+                    .compression_method(CompressionMethod::Deflated)
+                    .last_modified_time(zip_time(entry.modified))
+                    .large_file(content.len() as u64 >= u32::MAX as u64)
+                    .unix_permissions(0o644);
+                zip.start_file(entry.path.as_str(), options)?;
+                zip.write_all(content)?;
+            }
+            BundleWriter::Tar(tar) => append_tar(tar, entry, content)?,
+            BundleWriter::TarGz(tar) => append_tar(tar, entry, content)?,
+        }
+        Ok(())
+    }
+
+    /// Write the trailer (zip central directory, tar end blocks, gzip footer)
+    fn finish(self) -> anyhow::Result<()> {
+        match self {
+            BundleWriter::Zip(zip) => {
+                zip.finish()?;
+            }
+            BundleWriter::Tar(tar) => {
+                tar.into_inner()?;
+            }
+            BundleWriter::TarGz(tar) => {
+                tar.into_inner()?.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Helper: Add one regular file to a tar
+fn append_tar<W: Write>(
+    tar: &mut tar::Builder<W>,
+    entry: &ArchiveEntry,
+    content: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(entry.modified.max(0) as u64);
+    tar.append_data(&mut header, &entry.path, content)
+}
+
+/// Helper: Zip timestamps are local date/time parts from 1980 onwards
+fn zip_time(modified: i64) -> zip::DateTime {
+    use chrono::{Datelike, Timelike};
+
+    chrono::DateTime::from_timestamp(modified, 0)
+        .and_then(|time| {
+            zip::DateTime::from_date_and_time(
+                u16::try_from(time.year()).ok()?,
+                time.month() as u8,
+                time.day() as u8,
+                time.hour() as u8,
+                time.minute() as u8,
+                time.second() as u8,
+            )
+            .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Helper: Zip date/time parts (no time zone - taken as UTC) to Unix time
+fn zip_timestamp(time: zip::DateTime) -> Option<i64> {
+    let date = chrono::NaiveDate::from_ymd_opt(
+        time.year().into(),
+        time.month().into(),
+        time.day().into(),
+    )?;
+    let time = date.and_hms_opt(
+        time.hour().into(),
+        time.minute().into(),
+        time.second().into(),
+    )?;
+    Some(time.and_utc().timestamp())
+}
+
+/// Bytes written by the zip/tar writer, waiting to be sent
+///
+/// The writer owns one handle and the stream keeps another to take the
+/// output out after each file.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Take everything written so far
+    fn take(&self) -> Bytes {
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        Bytes::from(std::mem::take(&mut *buffer))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expanded-size cap for tests that aren't about it
+    const TEST_MAX_TOTAL: u64 = 64 * 1024 * 1024;
+
+    /// Helper: build a zip in memory, `add` puts the entries in
+    fn zip_with(add: impl FnOnce(&mut ZipWriter<Cursor<Vec<u8>>>)) -> Bytes {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        add(&mut writer);
+        Bytes::from(writer.finish().unwrap().into_inner())
+    }
+
+    fn deflated() -> SimpleFileOptions {
+        SimpleFileOptions::default().compression_method(CompressionMethod::Deflated)
+    }
+
+    #[test]
+    fn files_folders_and_links_are_sorted_out() {
+        let body = zip_with(|zip| {
+            zip.add_directory("docs/", deflated()).unwrap();
+            zip.start_file("docs/readme.txt", deflated()).unwrap();
+            zip.write_all(b"hello").unwrap();
+            zip.add_symlink("docs/link", "readme.txt", deflated())
+                .unwrap();
+        });
+
+        let entries = read_zip(body, TEST_MAX_TOTAL).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(
+            matches!(&entries[0].outcome, ZipImportOutcome::Skipped(why) if why == "directory")
+        );
+        match &entries[1].outcome {
+            ZipImportOutcome::File { content, .. } => assert_eq!(content.as_ref(), b"hello"),
+            other => panic!("expected a file, got {:?}", other),
+        }
+        assert!(
+            matches!(&entries[2].outcome, ZipImportOutcome::Skipped(why) if why == "symbolic link")
+        );
+    }
+
+    #[test]
+    fn garbage_is_not_a_zip() {
+        let err = read_zip(
+            Bytes::from_static(b"definitely not a zip file"),
+            TEST_MAX_TOTAL,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ZipImportError::NotAZip(_)));
+    }
+
+    #[test]
+    fn too_many_entries_are_refused() {
+        let body = zip_with(|zip| {
+            for i in 0..=ZIP_MAX_ENTRIES {
+                zip.start_file(format!("f{}", i), SimpleFileOptions::default())
+                    .unwrap();
+            }
+        });
+        let err = read_zip(body, TEST_MAX_TOTAL).unwrap_err();
+        assert!(matches!(err, ZipImportError::TooManyEntries(n) if n == ZIP_MAX_ENTRIES + 1));
+    }
+
+    #[test]
+    fn zip_bombs_are_refused() {
+        // 8 MiB of zeros deflates to a few KB - far past the ratio limit
+        let body = zip_with(|zip| {
+            zip.start_file("bomb.bin", deflated()).unwrap();
+            zip.write_all(&vec![0u8; 8 * 1024 * 1024]).unwrap();
+        });
+        let err = read_zip(body, TEST_MAX_TOTAL).unwrap_err();
+        assert!(matches!(err, ZipImportError::SuspiciousRatio(name, ratio)
+            if name == "bomb.bin" && ratio > ZIP_MAX_RATIO));
+    }
+
+    #[test]
+    fn small_files_skip_the_ratio_check() {
+        // Compresses just as well, but is under the 1 MiB threshold
+        let body = zip_with(|zip| {
+            zip.start_file("zeros.bin", deflated()).unwrap();
+            zip.write_all(&vec![0u8; 512 * 1024]).unwrap();
+        });
+        let entries = read_zip(body, TEST_MAX_TOTAL).unwrap();
+        assert!(matches!(&entries[0].outcome,
+            ZipImportOutcome::File { content, .. } if content.len() == 512 * 1024));
+    }
+
+    #[test]
+    fn expanded_size_is_capped() {
+        // Three 1 KiB files against a 2.5 KiB cap: the third one goes over
+        let body = zip_with(|zip| {
+            for name in ["a.bin", "b.bin", "c.bin"] {
+                zip.start_file(name, deflated()).unwrap();
+                zip.write_all(&[1u8; 1024]).unwrap();
+            }
+        });
+        let err = read_zip(body.clone(), 2560).unwrap_err();
+        assert!(matches!(err, ZipImportError::TooLarge(2560)));
+        assert_eq!(read_zip(body, 3072).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,353 @@
+//! DNS listener - Answers DNS queries from PNR records
+//!
+//! For 1st Year CS Students:
+//! PNR is "DNS for the Autonomi network", so here we speak real DNS!
+//! Point a resolver at this port and `dig @127.0.0.1 -p 18853 TXT my-site.ant`
+//! answers with the address stored in the `my-site` PNR record.
+//! - `my-site.ant`      → the apex ("@") record of PNR `my-site`
+//! - `blog.my-site.ant` → the "blog" record of PNR `my-site`
+//!
+//! TXT queries get `address=...` / `type=...` strings (TXT records get
+//! their text as-is).
+//!
+//! A and AAAA queries get the gateway's own IP (`dns.gateway_addresses`),
+//! because an Autonomi address is not an IP address - the best a browser
+//! can do is reach this server. The URL path still has to name the data
+//! (`http://my-site.ant:8080/anttp-0/public_archive/my-site/`): the HTTP
+//! API does not pick an archive from the Host header. Without a gateway
+//! address of the right family the answer is empty (NODATA) with the TXT
+//! record attached, so resolvers learn the name exists.
+
+use hickory_proto::op::{Message, MessageType, ResponseCode};
+use hickory_proto::rr::rdata::{A, AAAA, TXT};
+use hickory_proto::rr::{Name, RData, Record, RecordType};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+use crate::config::{Config, ConfigError};
+use crate::models::{PnrRecord, PnrRecordType, StoreType};
+use crate::services::{split_pnr_name, NetworkService, ServiceError};
+
+/// Largest UDP query read (EDNS lets clients send more than 512 bytes)
+const UDP_BUFFER_BYTES: usize = 65_535;
+
+/// A TCP connection with no complete query for this long is closed
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// DNS listener settings (the `[dns]` config section)
+#[derive(Debug, Clone)]
+pub struct DnsConfig {
+    pub bind_address: SocketAddr,
+    /// Pseudo top-level domain served from PNR, without dots (e.g. "ant")
+    pub tld: String,
+    pub store_type: StoreType,
+    /// What A/AAAA queries answer (this gateway's own IPs)
+    pub gateway_addresses: Vec<IpAddr>,
+}
+
+impl DnsConfig {
+    /// Listener settings from the server configuration
+    ///
+    /// Returns `Ok(None)` unless `dns.enabled` is true.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, ConfigError> {
+        if !config.dns.enabled {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            bind_address: config.dns_address()?,
+            tld: config.dns.tld.trim_matches('.').to_lowercase(),
+            store_type: config.dns.store_type,
+            gateway_addresses: config.dns.gateway_addresses.clone(),
+        }))
+    }
+}
+
+/// Answers DNS queries for `*.<tld>` from the PNR store
+pub struct DnsServer {
+    config: DnsConfig,
+    network: Arc<NetworkService>,
+}
+
+impl DnsServer {
+    pub fn new(config: DnsConfig, network: Arc<NetworkService>) -> Self {
+        Self { config, network }
+    }
+
+    /// Listen on UDP and TCP until either listener fails
+    pub async fn run(self) -> std::io::Result<()> {
+        let server = Arc::new(self);
+        let udp = UdpSocket::bind(server.config.bind_address).await?;
+        let tcp = TcpListener::bind(server.config.bind_address).await?;
+        log::info!(
+            "🌍 DNS listening on {} (udp+tcp) for *.{}",
+            server.config.bind_address,
+            server.config.tld
+        );
+
+        tokio::try_join!(server.clone().serve_udp(udp), server.serve_tcp(tcp))?;
+        Ok(())
+    }
+
+    /// One task per packet, so a slow PNR lookup doesn't hold up other clients
+    async fn serve_udp(self: Arc<Self>, socket: UdpSocket) -> std::io::Result<()> {
+        let socket = Arc::new(socket);
+        let mut buffer = vec![0u8; UDP_BUFFER_BYTES];
+        loop {
+            let (len, peer) = socket.recv_from(&mut buffer).await?;
+            let request = buffer[..len].to_vec();
+            let (server, socket) = (self.clone(), socket.clone());
+            tokio::spawn(async move {
+                if let Some(response) = server.answer(&request).await {
+                    if let Err(e) = socket.send_to(&response, peer).await {
+                        log::warn!("⚠️  DNS reply to {} failed: {}", peer, e);
+                    }
+                }
+            });
+        }
+    }
+
+    async fn serve_tcp(self: Arc<Self>, listener: TcpListener) -> std::io::Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_tcp(stream).await {
+                    log::warn!("⚠️  DNS TCP connection from {} failed: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    /// TCP messages are prefixed with a 2-byte length
+    ///
+    /// Idle clients are dropped after `TCP_IDLE_TIMEOUT`, so they can't
+    /// keep connections (and tasks) open forever.
+    async fn handle_tcp(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        loop {
+            let read = async {
+                let len = stream.read_u16().await?;
+                let mut request = vec![0u8; usize::from(len)];
+                stream.read_exact(&mut request).await?;
+                Ok::<_, std::io::Error>(request)
+            };
+            let request = match tokio::time::timeout(TCP_IDLE_TIMEOUT, read).await {
+                Ok(Ok(request)) => request,
+                Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Ok(()),
+            };
+
+            if let Some(response) = self.answer(&request).await {
+                let len = u16::try_from(response.len())
+                    .map_err(|_| std::io::Error::other("DNS response too large"))?;
+                stream.write_u16(len).await?;
+                stream.write_all(&response).await?;
+            }
+        }
+    }
+
+    /// Build the wire-format reply for one wire-format query
+    async fn answer(&self, request: &[u8]) -> Option<Vec<u8>> {
+        let request = match Message::from_vec(request) {
+            Ok(request) => request,
+            Err(e) => {
+                log::warn!("⚠️  Ignoring malformed DNS query: {}", e);
+                return None;
+            }
+        };
+
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(request.op_code())
+            .set_recursion_desired(request.recursion_desired())
+            .add_queries(request.queries().to_vec());
+
+        match request.queries().first() {
+            Some(query) => {
+                let lookup = self.lookup(query.name(), query.query_type()).await;
+                response
+                    .set_authoritative(lookup.code != ResponseCode::Refused)
+                    .set_response_code(lookup.code)
+                    .add_answers(lookup.answers)
+                    .add_additionals(lookup.additionals);
+            }
+            None => {
+                response.set_response_code(ResponseCode::FormErr);
+            }
+        }
+
+        response.to_vec().ok()
+    }
+
+    async fn lookup(&self, name: &Name, query_type: RecordType) -> Lookup {
+        let query_name = name.to_ascii().trim_end_matches('.').to_lowercase();
+        let Some((pnr_name, sub_name)) = split_query_name(&query_name, &self.config.tld) else {
+            return Lookup::empty(ResponseCode::Refused);
+        };
+
+        log::info!("🌍 DNS {} {}", query_type, query_name);
+        let use_network = self.config.store_type == StoreType::Network;
+        let record = match self
+            .network
+            .lookup_pnr_record(pnr_name, sub_name, use_network)
+            .await
+        {
+            Ok(record) => record,
+            // Only a missing record is NXDOMAIN - resolvers cache that answer
+            Err(e) => match ServiceError::from(e) {
+                ServiceError::NotFound(message) => {
+                    log::info!("🌍 DNS NXDOMAIN {}: {}", query_name, message);
+                    return Lookup::empty(ResponseCode::NXDomain);
+                }
+                e => {
+                    log::warn!("⚠️  DNS SERVFAIL {}: {}", query_name, e);
+                    return Lookup::empty(ResponseCode::ServFail);
+                }
+            },
+        };
+
+        let txt = Record::from_rdata(name.clone(), record.ttl, txt_rdata(&record));
+        let answers = match query_type {
+            RecordType::TXT | RecordType::ANY => vec![txt.clone()],
+            RecordType::A | RecordType::AAAA if record.record_type != PnrRecordType::Txt => self
+                .config
+                .gateway_addresses
+                .iter()
+                .filter_map(|address| match (query_type, address) {
+                    (RecordType::A, IpAddr::V4(ip)) => Some(RData::A(A(*ip))),
+                    (RecordType::AAAA, IpAddr::V6(ip)) => Some(RData::AAAA(AAAA(*ip))),
+                    _ => None,
+                })
+                .map(|rdata| Record::from_rdata(name.clone(), record.ttl, rdata))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Name exists but has no data of this type (NODATA) - the TXT says what it holds
+        let additionals = match answers.is_empty() {
+            true => vec![txt],
+            false => Vec::new(),
+        };
+        Lookup {
+            code: ResponseCode::NoError,
+            answers,
+            additionals,
+        }
+    }
+}
+
+/// The answer to one query
+struct Lookup {
+    code: ResponseCode,
+    answers: Vec<Record>,
+    additionals: Vec<Record>,
+}
+
+impl Lookup {
+    fn empty(code: ResponseCode) -> Self {
+        Self {
+            code,
+            answers: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+}
+
+/// Split `blog.my-site.ant` into (`my-site`, Some(`blog`))
+fn split_query_name<'a>(query_name: &'a str, tld: &str) -> Option<(&'a str, Option<&'a str>)> {
+    let labels = query_name.strip_suffix(tld)?.strip_suffix('.')?;
+    if labels.is_empty() {
+        return None;
+    }
+    Some(split_pnr_name(labels))
+}
+
+fn txt_rdata(record: &PnrRecord) -> RData {
+    let strings = match record.record_type {
+        PnrRecordType::Txt => vec![record.address.clone()],
+        _ => vec![
+            format!("address={}", record.address),
+            format!("type={}", record.record_type),
+        ],
+    };
+    RData::TXT(TXT::new(strings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_proto::op::Query;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    async fn server(gateway_addresses: Vec<IpAddr>) -> DnsServer {
+        let network = NetworkService::new().await.unwrap();
+        let record = PnrRecord {
+            address: "a".repeat(64),
+            record_type: PnrRecordType::A,
+            ttl: 60,
+        };
+        let records = HashMap::from([("@".to_string(), record)]);
+        network
+            .store_pnr("my-site", &records, "owner", false)
+            .await
+            .unwrap();
+        let config = DnsConfig {
+            bind_address: "127.0.0.1:0".parse().unwrap(),
+            tld: "ant".to_string(),
+            store_type: StoreType::Memory,
+            gateway_addresses,
+        };
+        DnsServer::new(config, Arc::new(network))
+    }
+
+    async fn ask(server: &DnsServer, name: &str, query_type: RecordType) -> Message {
+        let mut request = Message::new();
+        request.add_query(Query::query(Name::from_str(name).unwrap(), query_type));
+        let response = server.answer(&request.to_vec().unwrap()).await.unwrap();
+        Message::from_vec(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_queries_answer_with_the_gateway_address() {
+        let server = server(vec!["192.168.1.10".parse().unwrap()]).await;
+
+        let response = ask(&server, "my-site.ant.", RecordType::A).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        let answers: Vec<_> = response.answers().iter().map(Record::data).collect();
+        assert_eq!(answers, [Some(&RData::A(A::new(192, 168, 1, 10)))]);
+
+        // No IPv6 gateway address: NODATA, with the TXT record attached
+        let response = ask(&server, "my-site.ant.", RecordType::AAAA).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(response.answers().is_empty());
+        assert_eq!(response.additionals()[0].record_type(), RecordType::TXT);
+    }
+
+    #[tokio::test]
+    async fn unknown_and_foreign_names() {
+        let server = server(Vec::new()).await;
+
+        let response = ask(&server, "nobody.ant.", RecordType::TXT).await;
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        let response = ask(&server, "example.com.", RecordType::A).await;
+        assert_eq!(response.response_code(), ResponseCode::Refused);
+        let response = ask(&server, "my-site.ant.", RecordType::A).await;
+        assert!(response.answers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_lookups_are_not_nxdomain() {
+        // The network store isn't there yet: that says nothing about the name
+        let mut server = server(Vec::new()).await;
+        server.config.store_type = StoreType::Network;
+
+        let response = ask(&server, "my-site.ant.", RecordType::TXT).await;
+        assert_eq!(response.response_code(), ResponseCode::ServFail);
+    }
+}
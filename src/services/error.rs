@@ -0,0 +1,204 @@
+// src/services/error.rs
+//! Service errors - What went wrong, and which HTTP status that means
+//!
+//! For 1st Year CS Students:
+//! "Something failed" isn't enough for a client. A missing archive (404),
+//! a name owned by someone else (403) and a network that is down (503)
+//! need different reactions. `ServiceError` names the kind of failure once,
+//! and every handler turns it into the same status and `code`.
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+
+use crate::models::{ErrorCode, ErrorResponse};
+use crate::services::app_config::AppConfigError;
+use crate::services::bundle::ZipImportError;
+use crate::services::publisher::PublishError;
+use crate::services::tokens::TokenError;
+use crate::services::{ArchiveError, KeyValueError, PnrAccessError};
+
+/// A failed operation, by kind
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceError {
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    TypeMismatch(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    #[error("{0}")]
+    BackendUnavailable(String),
+    #[error("{0}")]
+    Corrupt(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl ServiceError {
+    /// The stable code sent in `ErrorResponse.code`
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ServiceError::InvalidInput(_) => ErrorCode::InvalidInput,
+            ServiceError::NotFound(_) => ErrorCode::NotFound,
+            ServiceError::TypeMismatch(_) => ErrorCode::TypeMismatch,
+            ServiceError::Conflict(_) => ErrorCode::Conflict,
+            ServiceError::Unauthorized(_) => ErrorCode::Unauthorized,
+            ServiceError::Forbidden(_) => ErrorCode::Forbidden,
+            ServiceError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            ServiceError::BackendUnavailable(_) => ErrorCode::BackendUnavailable,
+            ServiceError::Corrupt(_) => ErrorCode::Corrupt,
+            ServiceError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// Error response with a headline (`error`) and this error as `details`
+    ///
+    /// For Students:
+    /// `ServiceError::from(e).response("Failed to get archive")` gives
+    /// `{"error": "Failed to get archive", "code": "not_found", "details": "..."}`
+    pub fn response(&self, error: impl Into<String>) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorResponse::with_details(
+            self.code(),
+            error,
+            self.to_string(),
+        ))
+    }
+}
+
+impl ResponseError for ServiceError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ServiceError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            ServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+            ServiceError::TypeMismatch(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::Conflict(_) => StatusCode::CONFLICT,
+            ServiceError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ServiceError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ServiceError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ServiceError::BackendUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ServiceError::Corrupt(_) | ServiceError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .json(ErrorResponse::new(self.code(), self.to_string()))
+    }
+}
+
+/// Service methods return `anyhow::Result`; find the typed error inside
+impl From<anyhow::Error> for ServiceError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<ServiceError>() {
+            Ok(service_error) => return service_error,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<KeyValueError>() {
+            Ok(key_value_error) => return key_value_error.into(),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<PnrAccessError>() {
+            Ok(access_error) => return access_error.into(),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<ArchiveError>() {
+            Ok(archive_error) => return archive_error.into(),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<PublishError>() {
+            Ok(publish_error) => return publish_error.into(),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<AppConfigError>() {
+            Ok(config_error) => return config_error.into(),
+            Err(e) => e,
+        };
+        if e.downcast_ref::<serde_json::Error>().is_some() {
+            return ServiceError::Corrupt(format!("Stored data could not be decoded: {}", e));
+        }
+        ServiceError::Internal(e.to_string())
+    }
+}
+
+impl From<KeyValueError> for ServiceError {
+    fn from(e: KeyValueError) -> Self {
+        match e {
+            KeyValueError::BucketNotEmpty => ServiceError::Conflict(e.to_string()),
+            _ => ServiceError::NotFound(e.to_string()),
+        }
+    }
+}
+
+impl From<PnrAccessError> for ServiceError {
+    fn from(e: PnrAccessError) -> Self {
+        match e {
+            PnrAccessError::OwnerKeyRequired(_) => ServiceError::Unauthorized(e.to_string()),
+            PnrAccessError::NotOwner(_) => ServiceError::Forbidden(e.to_string()),
+            PnrAccessError::AlreadyExists(_) => ServiceError::Conflict(e.to_string()),
+        }
+    }
+}
+
+impl From<ArchiveError> for ServiceError {
+    fn from(e: ArchiveError) -> Self {
+        match e {
+            ArchiveError::PathNotFound(_) => ServiceError::NotFound(e.to_string()),
+            _ => ServiceError::Conflict(e.to_string()),
+        }
+    }
+}
+
+impl From<PublishError> for ServiceError {
+    fn from(e: PublishError) -> Self {
+        match e {
+            PublishError::OutsideRoot(_) => ServiceError::Forbidden(e.to_string()),
+            PublishError::NotADirectory(_) | PublishError::PointerNotFound(_) => {
+                ServiceError::NotFound(e.to_string())
+            }
+            PublishError::Io(..) => ServiceError::Internal(e.to_string()),
+            PublishError::InvalidPattern(..)
+            | PublishError::SymlinkRefused(_)
+            | PublishError::Empty(_)
+            | PublishError::OwnerKeyRequired(_) => ServiceError::InvalidInput(e.to_string()),
+        }
+    }
+}
+
+impl From<AppConfigError> for ServiceError {
+    /// A broken app-conf.json is the archive's fault, not the request's
+    fn from(e: AppConfigError) -> Self {
+        ServiceError::Corrupt(e.to_string())
+    }
+}
+
+impl From<ZipImportError> for ServiceError {
+    fn from(e: ZipImportError) -> Self {
+        match e {
+            ZipImportError::NotAZip(_) | ZipImportError::SizeMismatch(_) => {
+                ServiceError::InvalidInput(e.to_string())
+            }
+            _ => ServiceError::PayloadTooLarge(e.to_string()),
+        }
+    }
+}
+
+impl From<TokenError> for ServiceError {
+    fn from(e: TokenError) -> Self {
+        match e {
+            TokenError::Invalid(_) => ServiceError::InvalidInput(e.to_string()),
+            TokenError::NotFound(_) => ServiceError::NotFound(e.to_string()),
+            TokenError::Configured(_) => ServiceError::Conflict(e.to_string()),
+            TokenError::Io(_) => ServiceError::Internal(e.to_string()),
+        }
+    }
+}
@@ -1,6 +1,21 @@
 // src/services/mod.rs
 //! Service layer modules
 
+pub mod app_config;
+pub mod bundle;
+pub mod dns;
+pub mod error;
 pub mod network;
+pub mod publisher;
+pub mod s3;
+pub mod tokens;
 
-pub use network::NetworkService;
+pub use dns::{DnsConfig, DnsServer};
+pub use error::ServiceError;
+pub use network::{
+    diff_archive, is_network_address, plan_archive_sync, split_pnr_name, ArchiveChange,
+    ArchiveError, KeyValueError, NetworkService, PnrAccessError,
+};
+pub use publisher::PublishConfig;
+pub use s3::{S3Config, S3Gateway, S3UploadError, SignedRequest};
+pub use tokens::{Access, Scope, TokenError, TokenStore};
@@ -6,16 +6,35 @@
 //! Later, we can add real network storage!
 
 use anyhow::Result;
+use base64::Engine as _;
 use bytes::Bytes;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+use crate::models::{
+    ArchiveDiffResponse, ArchiveEncoding, ArchiveEntry, ArchivePathChange, ArchiveSyncPlan,
+    Consistency, KeyValueBucket, KeyValueMetadata, KeyValueResponse, KeyValueVersion,
+    ListObjectsQuery, ListObjectsResponse, PnrRecord, PnrRecordType,
+};
+use crate::services::ServiceError;
+
 /// Network client wrapper
 pub struct NetworkService {
     /// Local cache for memory storage
     memory_cache: Arc<RwLock<HashMap<String, Bytes>>>,
+    /// Store brotli/gzip copies of text files in archives (ARCHIVE_PRECOMPRESS)
+    precompress: bool,
+    /// PNR name → resolved address, kept for the record's TTL
+    resolved_names: Arc<RwLock<HashMap<String, ResolvedName>>>,
+}
+
+/// A cached PNR lookup result
+struct ResolvedName {
+    address: String,
+    expires_at: Instant,
 }
 
 impl NetworkService {
@@ -39,9 +58,20 @@ impl NetworkService {
 
         Ok(Self {
             memory_cache: Arc::new(RwLock::new(HashMap::new())),
+            precompress: false,
+            resolved_names: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
+    /// Also store brotli/gzip copies of archive files (`storage.archive_precompress`)
+    pub fn with_precompress(mut self, precompress: bool) -> Self {
+        if precompress {
+            log::info!("🗜️ Archive files will be stored precompressed (br, gzip)");
+        }
+        self.precompress = precompress;
+        self
+    }
+
     /// Store a chunk on the network
     ///
     /// For Students:
@@ -82,19 +112,79 @@ impl NetworkService {
         // Memory retrieval
         log::info!("💾 Fetching chunk from memory: {}", address);
         let cache = self.memory_cache.read().await;
-        cache
-            .get(address)
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Chunk not found in memory: {}", address))
+        cache.get(address).cloned().ok_or_else(|| {
+            ServiceError::NotFound(format!("Chunk not found in memory: {}", address)).into()
+        })
     }
 
     /// Store an archive (directory of files)
     ///
     /// For Students:
     /// Archives are like ZIP files - multiple files stored together!
+    /// Each file becomes a chunk, and the archive itself is a small
+    /// "manifest" listing path → chunk address (like a table of contents).
+    /// Each file's modification time (Unix seconds) defaults to now.
     pub async fn store_archive(
         &self,
-        files: Vec<(PathBuf, Bytes)>,
+        files: Vec<(PathBuf, Bytes, Option<i64>)>,
+        use_network: bool,
+    ) -> Result<String> {
+        log::info!("💾 Storing archive with {} files", files.len());
+        let mut entries = Vec::with_capacity(files.len());
+        for (path, content, modified) in files {
+            let path = path.to_string_lossy().replace('\\', "/");
+            entries.push(self.store_archive_file(&path, content, modified, use_network).await?);
+        }
+        self.store_archive_manifest(entries, use_network).await
+    }
+
+    /// Store one file's content and describe it as a manifest entry
+    ///
+    /// `modified` defaults to now. With precompression on, text files also
+    /// get brotli and gzip copies (when those are noticeably smaller).
+    pub async fn store_archive_file(
+        &self,
+        path: &str,
+        content: Bytes,
+        modified: Option<i64>,
+        use_network: bool,
+    ) -> Result<ArchiveEntry> {
+        let size = content.len() as u64;
+        let sha256 = hex::encode(sha256_hash(&content));
+
+        let mut encodings = Vec::new();
+        if self.precompress && is_compressible(path, size) {
+            let original = content.clone();
+            let variants = tokio::task::spawn_blocking(move || compress_variants(&original))
+                .await
+                .map_err(|e| anyhow::anyhow!("Compression failed: {}", e))??;
+            for (encoding, compressed) in variants {
+                let compressed_size = compressed.len() as u64;
+                encodings.push(ArchiveEncoding {
+                    encoding: encoding.to_string(),
+                    address: self.store_chunk(Bytes::from(compressed), use_network).await?,
+                    size: compressed_size,
+                });
+            }
+        }
+
+        let address = self.store_chunk(content, use_network).await?;
+        Ok(ArchiveEntry {
+            path: path.to_string(),
+            address,
+            size,
+            sha256,
+            modified: modified.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+            encodings,
+        })
+    }
+
+    /// Store a manifest of already-stored files as a new archive
+    ///
+    /// Entries are sorted by path; two entries with the same path are an error.
+    pub async fn store_archive_manifest(
+        &self,
+        mut entries: Vec<ArchiveEntry>,
         use_network: bool,
     ) -> Result<String> {
         if use_network {
@@ -102,59 +192,145 @@ impl NetworkService {
             log::info!("💾 Falling back to memory storage");
             // Fall through to memory storage
         }
-        
-        // Memory storage - serialize files
-        log::info!("💾 Storing archive with {} files in memory", files.len());
-        
-        // Convert Bytes to Vec<u8> for serialization
-        let files_serializable: Vec<(PathBuf, Vec<u8>)> = files
-            .into_iter()
-            .map(|(path, bytes)| (path, bytes.to_vec()))
-            .collect();
-        
-        let serialized = serde_json::to_vec(&files_serializable)?;
-        let hash = sha256_hash(&serialized);
-        let hex_address = hex::encode(&hash);
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].path == pair[1].path) {
+            return Err(ArchiveError::DuplicatePath(pair[0].path.clone()).into());
+        }
+
+        let manifest = serde_json::json!({
+            "type": "archive",
+            "files": entries,
+        });
+        let serialized = serde_json::to_vec(&manifest)?;
+        let hex_address = hex::encode(sha256_hash(&serialized));
 
         self.memory_cache
             .write()
             .await
             .insert(hex_address.clone(), Bytes::from(serialized));
 
-        log::info!("💾 Archive cached in memory: {}", hex_address);
+        log::info!("💾 Archive cached in memory: {} ({} files)", hex_address, entries.len());
         Ok(hex_address)
     }
 
-    /// Retrieve an archive from the network
-    pub async fn get_archive(
+    /// Read an archive's manifest (paths, sizes, hashes) without any content
+    pub async fn get_archive_manifest(
         &self,
         address: &str,
         use_network: bool,
-    ) -> Result<Vec<(PathBuf, Bytes)>> {
+    ) -> Result<Vec<ArchiveEntry>> {
         if use_network {
             log::warn!("⚠️  Network retrieval requested but not available (compile with --features network)");
             log::info!("💾 Falling back to memory retrieval");
             // Fall through to memory retrieval
         }
-        
-        // Memory retrieval
+
         log::info!("💾 Fetching archive from memory: {}", address);
         let cache = self.memory_cache.read().await;
         let serialized = cache
             .get(address)
-            .ok_or_else(|| anyhow::anyhow!("Archive not found: {}", address))?;
+            .ok_or_else(|| ServiceError::NotFound(format!("Archive not found: {}", address)))?;
 
-        let files_serializable: Vec<(PathBuf, Vec<u8>)> = serde_json::from_slice(serialized)?;
-        
-        // Convert Vec<u8> back to Bytes
-        let files: Vec<(PathBuf, Bytes)> = files_serializable
-            .into_iter()
-            .map(|(path, vec)| (path, Bytes::from(vec)))
-            .collect();
-        
+        let manifest: serde_json::Value = serde_json::from_slice(serialized)
+            .map_err(|_| ServiceError::TypeMismatch(format!("Not an archive: {}", address)))?;
+        if manifest["type"] != "archive" {
+            return Err(ServiceError::TypeMismatch(format!("Not an archive: {}", address)).into());
+        }
+        Ok(serde_json::from_value(manifest["files"].clone())?)
+    }
+
+    /// Retrieve an archive with every file's content
+    pub async fn get_archive(
+        &self,
+        address: &str,
+        use_network: bool,
+    ) -> Result<Vec<(PathBuf, Bytes)>> {
+        let mut files = Vec::new();
+        for entry in self.get_archive_manifest(address, use_network).await? {
+            let content = self.get_chunk(&entry.address, use_network).await?;
+            files.push((PathBuf::from(entry.path), content));
+        }
         Ok(files)
     }
 
+    /// Apply changes to an archive, producing a new archive
+    ///
+    /// For Students:
+    /// Archives never change - like chunks, they're addressed by their
+    /// content. "Changing" one means writing a new manifest. Files that
+    /// didn't change keep pointing at the same chunks, so only new
+    /// content is stored. A path in Delete/Rename may also name a
+    /// directory, which removes or moves everything under it.
+    ///
+    /// Returns the new archive address and its manifest.
+    pub async fn update_archive(
+        &self,
+        address: &str,
+        changes: Vec<ArchiveChange>,
+        use_network: bool,
+    ) -> Result<(String, Vec<ArchiveEntry>)> {
+        let mut entries = self.get_archive_manifest(address, use_network).await?;
+
+        for change in changes {
+            match change {
+                ArchiveChange::Put {
+                    path,
+                    content,
+                    modified,
+                } => {
+                    // A file can't replace a directory, or live inside a file
+                    let is_directory = entries
+                        .iter()
+                        .any(|entry| entry.path.starts_with(&format!("{}/", path)));
+                    if is_directory || conflicts_with(&entries, &path) {
+                        return Err(ArchiveError::PathConflict(path).into());
+                    }
+                    let entry = self
+                        .store_archive_file(&path, content, modified, use_network)
+                        .await?;
+                    entries.retain(|existing| existing.path != path);
+                    entries.push(entry);
+                }
+                ArchiveChange::Delete { path } => {
+                    let before = entries.len();
+                    entries.retain(|entry| !is_at_or_under(&entry.path, &path));
+                    if entries.len() == before {
+                        return Err(ArchiveError::PathNotFound(path).into());
+                    }
+                }
+                ArchiveChange::Rename { from, to } => {
+                    let from = from.trim_end_matches('/').to_string();
+                    let to = to.trim_end_matches('/').to_string();
+                    if entries.iter().any(|entry| is_at_or_under(&entry.path, &to)) {
+                        return Err(ArchiveError::PathExists(to).into());
+                    }
+                    if conflicts_with(&entries, &to) || is_at_or_under(&to, &from) {
+                        return Err(ArchiveError::PathConflict(to).into());
+                    }
+                    let mut renamed = false;
+                    for entry in entries
+                        .iter_mut()
+                        .filter(|entry| is_at_or_under(&entry.path, &from))
+                    {
+                        entry.path = format!("{}{}", to, &entry.path[from.len()..]);
+                        renamed = true;
+                    }
+                    if !renamed {
+                        return Err(ArchiveError::PathNotFound(from).into());
+                    }
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let new_address = self
+            .store_archive_manifest(entries.clone(), use_network)
+            .await?;
+        log::info!("💾 Archive {} updated → {}", address, new_address);
+        Ok((new_address, entries))
+    }
+
     // ========================================================================
     // REGISTERS - Mutable key-value with history
     // ========================================================================
@@ -234,10 +410,10 @@ impl NetworkService {
                     .unwrap_or("")
                     .to_string())
             } else {
-                Err(anyhow::anyhow!("Register not found"))
+                Err(ServiceError::NotFound("Register not found".to_string()).into())
             }
         } else {
-            Err(anyhow::anyhow!("Network registers not yet implemented"))
+            Err(network_unavailable("registers"))
         }
     }
 
@@ -260,10 +436,10 @@ impl NetworkService {
                     .collect();
                 Ok(entries)
             } else {
-                Err(anyhow::anyhow!("Register not found"))
+                Err(ServiceError::NotFound("Register not found".to_string()).into())
             }
         } else {
-            Err(anyhow::anyhow!("Network registers not yet implemented"))
+            Err(network_unavailable("registers"))
         }
     }
 
@@ -317,6 +493,37 @@ impl NetworkService {
         Ok(())
     }
 
+    /// Point an existing pointer at a new target, keeping its name
+    pub async fn set_pointer_target(
+        &self,
+        address: &str,
+        target: &str,
+        use_network: bool,
+    ) -> Result<()> {
+        if use_network {
+            return Err(network_unavailable("pointers"));
+        }
+
+        let mut cache = self.memory_cache.write().await;
+        let mut data: serde_json::Value = match cache.get(address) {
+            Some(data) => serde_json::from_slice(data)?,
+            None => {
+                return Err(
+                    ServiceError::NotFound(format!("Pointer not found: {}", address)).into(),
+                )
+            }
+        };
+        if data.get("target").is_none() {
+            return Err(ServiceError::TypeMismatch(format!("Not a pointer: {}", address)).into());
+        }
+        data["target"] = serde_json::Value::from(target);
+        cache.insert(address.to_string(), Bytes::from(serde_json::to_vec(&data)?));
+        drop(cache);
+
+        log::info!("👉 Pointer {} → {}", address, target);
+        Ok(())
+    }
+
     pub async fn get_pointer(&self, address: &str, use_network: bool) -> Result<String> {
         if !use_network {
             let cache = self.memory_cache.read().await;
@@ -324,10 +531,10 @@ impl NetworkService {
                 let value: serde_json::Value = serde_json::from_slice(data)?;
                 Ok(value["target"].as_str().unwrap_or("").to_string())
             } else {
-                Err(anyhow::anyhow!("Pointer not found"))
+                Err(ServiceError::NotFound("Pointer not found".to_string()).into())
             }
         } else {
-            Err(anyhow::anyhow!("Network pointers not yet implemented"))
+            Err(network_unavailable("pointers"))
         }
     }
 
@@ -393,10 +600,10 @@ impl NetworkService {
                 let value: serde_json::Value = serde_json::from_slice(data)?;
                 Ok(value["content"].as_str().unwrap_or("").to_string())
             } else {
-                Err(anyhow::anyhow!("Scratchpad not found"))
+                Err(ServiceError::NotFound("Scratchpad not found".to_string()).into())
             }
         } else {
-            Err(anyhow::anyhow!("Network scratchpads not yet implemented"))
+            Err(network_unavailable("scratchpads"))
         }
     }
 
@@ -461,10 +668,10 @@ impl NetworkService {
                 let value: serde_json::Value = serde_json::from_slice(data)?;
                 Ok(value["content"].as_str().unwrap_or("").to_string())
             } else {
-                Err(anyhow::anyhow!("Private scratchpad not found"))
+                Err(ServiceError::NotFound("Private scratchpad not found".to_string()).into())
             }
         } else {
-            Err(anyhow::anyhow!("Network scratchpads not yet implemented"))
+            Err(network_unavailable("scratchpads"))
         }
     }
 
@@ -516,11 +723,13 @@ impl NetworkService {
         &self,
         name: &str,
         content: &str,
+        descendants: &[String],
         use_network: bool,
     ) -> Result<String> {
         let data = serde_json::json!({
             "name": name,
             "content": content,
+            "descendants": descendants,
             "type": "graph_entry"
         });
         let serialized = serde_json::to_vec(&data)?;
@@ -544,70 +753,140 @@ impl NetworkService {
         &self,
         address: &str,
         use_network: bool,
-    ) -> Result<(String, String)> {
+    ) -> Result<crate::models::GraphEntryData> {
         if !use_network {
             let cache = self.memory_cache.read().await;
             if let Some(data) = cache.get(address) {
                 let value: serde_json::Value = serde_json::from_slice(data)?;
-                let name = value["name"].as_str().unwrap_or("").to_string();
-                let content = value["content"].as_str().unwrap_or("").to_string();
-                Ok((name, content))
+                let descendants = match value.get("descendants") {
+                    Some(list) => serde_json::from_value(list.clone())?,
+                    None => Vec::new(),
+                };
+                Ok(crate::models::GraphEntryData {
+                    name: value["name"].as_str().unwrap_or("").to_string(),
+                    content: value["content"].as_str().unwrap_or("").to_string(),
+                    descendants,
+                })
             } else {
-                Err(anyhow::anyhow!("Graph entry not found"))
+                Err(ServiceError::NotFound("Graph entry not found".to_string()).into())
             }
         } else {
-            Err(anyhow::anyhow!("Network graph not yet implemented"))
+            Err(network_unavailable("graph"))
         }
     }
 
+    /// Walk the graph breadth-first starting at `root`
+    ///
+    /// For Students:
+    /// We follow each entry's descendants like following links between
+    /// web pages, remembering where we've been so loops don't trap us.
+    /// Links to entries we can't find are left in the results as-is.
+    pub async fn walk_graph(
+        &self,
+        root: &str,
+        max_depth: Option<usize>,
+        use_network: bool,
+    ) -> Result<Vec<(String, crate::models::GraphEntryData)>> {
+        let mut visited = std::collections::HashSet::new();
+        let mut queue = std::collections::VecDeque::new();
+        let mut entries = Vec::new();
+
+        // The root itself must exist, everything below it is best-effort
+        let root_entry = self.get_graph_entry(root, use_network).await?;
+        visited.insert(root.to_string());
+        queue.push_back((root.to_string(), root_entry, 0usize));
+
+        while let Some((address, entry, depth)) = queue.pop_front() {
+            if entries.len() >= MAX_GRAPH_WALK_NODES {
+                return Err(ServiceError::PayloadTooLarge(format!(
+                    "Graph has more than {} entries",
+                    MAX_GRAPH_WALK_NODES
+                ))
+                .into());
+            }
+
+            if max_depth.is_none_or(|max| depth < max) {
+                for child in &entry.descendants {
+                    if !visited.insert(child.clone()) {
+                        continue;
+                    }
+                    match self.get_graph_entry(child, use_network).await {
+                        Ok(child_entry) => queue.push_back((child.clone(), child_entry, depth + 1)),
+                        Err(e) => log::warn!("⚠️  Skipping graph entry {}: {}", child, e),
+                    }
+                }
+            }
+
+            entries.push((address, entry));
+        }
+
+        Ok(entries)
+    }
+
     // ========================================================================
     // PNR - Pointer Name Registry (DNS-like)
     // ========================================================================
 
+    /// Register a new name, owned by `owner_key`
+    ///
+    /// For Students:
+    /// Like registering a domain: the first one to ask gets it, and a
+    /// name that is already taken is refused (409 Conflict) instead of
+    /// being quietly replaced.
     pub async fn store_pnr(
         &self,
         name: &str,
         records: &std::collections::HashMap<String, crate::models::PnrRecord>,
+        owner_key: &str,
         use_network: bool,
     ) -> Result<String> {
-        let data = serde_json::json!({
-            "name": name,
-            "records": records,
-            "type": "pnr"
-        });
-        let serialized = serde_json::to_vec(&data)?;
-
         if use_network {
             log::info!("🌐 Storing PNR on network: {}", name);
+            let data = serde_json::json!({
+                "name": name,
+                "records": records,
+                "type": "pnr"
+            });
             // TODO: Real network implementation
-            self.store_chunk(Bytes::from(serialized), false).await
-        } else {
-            let hash = sha256_hash(&serialized);
-            let hex_address = hex::encode(&hash);
-            self.memory_cache
-                .write()
+            self.store_chunk(Bytes::from(serde_json::to_vec(&data)?), false)
                 .await
-                .insert(format!("pnr:{}", name), Bytes::from(serialized));
+        } else {
+            let key = format!("pnr:{}", name);
+            let mut cache = self.memory_cache.write().await;
+            if cache.contains_key(&key) {
+                return Err(PnrAccessError::AlreadyExists(name.to_string()).into());
+            }
+
+            let owner = hex::encode(sha256_hash(owner_key.as_bytes()));
+            let serialized = pnr_document(name, records, &owner)?;
+            let hex_address = hex::encode(sha256_hash(&serialized));
+            cache.insert(key, serialized);
+            drop(cache);
+
+            self.forget_resolved_name(name).await;
             Ok(hex_address)
         }
     }
 
+    /// Replace all records of an existing name (owner only)
     pub async fn update_pnr(
         &self,
         name: &str,
         records: &std::collections::HashMap<String, crate::models::PnrRecord>,
+        owner_key: Option<&str>,
         use_network: bool,
     ) -> Result<()> {
         if !use_network {
-            let data = serde_json::json!({
-                "name": name,
-                "records": records,
-                "type": "pnr"
-            });
-            self.memory_cache.write().await.insert(
-                format!("pnr:{}", name),
-                Bytes::from(serde_json::to_vec(&data)?),
-            );
+            let mut cache = self.memory_cache.write().await;
+            let key = format!("pnr:{}", name);
+            let data = cache
+                .get(&key)
+                .ok_or_else(|| ServiceError::NotFound(format!("PNR not found: {}", name)))?;
+            let owner = pnr_owner_for_write(name, data, owner_key)?;
+
+            cache.insert(key, pnr_document(name, records, &owner)?);
+            drop(cache);
+            self.forget_resolved_name(name).await;
         }
         Ok(())
     }
@@ -625,10 +904,10 @@ impl NetworkService {
                 let records = serde_json::from_value(value["records"].clone())?;
                 Ok(records)
             } else {
-                Err(anyhow::anyhow!("PNR not found"))
+                Err(ServiceError::NotFound("PNR not found".to_string()).into())
             }
         } else {
-            Err(anyhow::anyhow!("Network PNR not yet implemented"))
+            Err(network_unavailable("PNR"))
         }
     }
 
@@ -636,34 +915,182 @@ impl NetworkService {
         &self,
         name: &str,
         new_records: &std::collections::HashMap<String, crate::models::PnrRecord>,
+        owner_key: Option<&str>,
         use_network: bool,
     ) -> Result<()> {
         if !use_network {
             let mut cache = self.memory_cache.write().await;
             let key = format!("pnr:{}", name);
+            let data = cache
+                .get(&key)
+                .ok_or_else(|| ServiceError::NotFound(format!("PNR not found: {}", name)))?;
+            let owner = pnr_owner_for_write(name, data, owner_key)?;
 
             // Get existing records
-            let mut all_records = if let Some(data) = cache.get(&key) {
-                let value: serde_json::Value = serde_json::from_slice(data)?;
-                serde_json::from_value(value["records"].clone())?
-            } else {
-                std::collections::HashMap::new()
-            };
+            let value: serde_json::Value = serde_json::from_slice(data)?;
+            let mut all_records: HashMap<String, PnrRecord> =
+                serde_json::from_value(value["records"].clone())?;
 
             // Merge with new records
             all_records.extend(new_records.clone());
 
             // Store back
-            let data = serde_json::json!({
-                "name": name,
-                "records": all_records,
-                "type": "pnr"
-            });
-            cache.insert(key, Bytes::from(serde_json::to_vec(&data)?));
+            cache.insert(key, pnr_document(name, &all_records, &owner)?);
+            drop(cache);
+            self.forget_resolved_name(name).await;
         }
         Ok(())
     }
 
+    /// Delete a whole PNR name
+    pub async fn delete_pnr(&self, name: &str, owner_key: Option<&str>, use_network: bool) -> Result<()> {
+        if use_network {
+            return Err(network_unavailable("PNR"));
+        }
+
+        let mut cache = self.memory_cache.write().await;
+        let key = format!("pnr:{}", name);
+        let data = cache
+            .get(&key)
+            .ok_or_else(|| ServiceError::NotFound("PNR not found".to_string()))?;
+        pnr_owner_for_write(name, data, owner_key)?;
+        cache.remove(&key);
+        drop(cache);
+
+        self.forget_resolved_name(name).await;
+        Ok(())
+    }
+
+    /// Delete one record (sub-name) from a PNR name
+    pub async fn delete_pnr_record(
+        &self,
+        name: &str,
+        sub_name: &str,
+        owner_key: Option<&str>,
+        use_network: bool,
+    ) -> Result<()> {
+        if use_network {
+            return Err(network_unavailable("PNR"));
+        }
+
+        let mut cache = self.memory_cache.write().await;
+        let key = format!("pnr:{}", name);
+        let data = cache
+            .get(&key)
+            .ok_or_else(|| ServiceError::NotFound("PNR not found".to_string()))?;
+        let owner = pnr_owner_for_write(name, data, owner_key)?;
+
+        let value: serde_json::Value = serde_json::from_slice(data)?;
+        let mut records: HashMap<String, PnrRecord> = serde_json::from_value(value["records"].clone())?;
+        if records.remove(sub_name).is_none() {
+            return Err(ServiceError::NotFound(format!(
+                "PNR '{}' has no '{}' record",
+                name, sub_name
+            ))
+            .into());
+        }
+        cache.insert(key, pnr_document(name, &records, &owner)?);
+        drop(cache);
+
+        self.forget_resolved_name(name).await;
+        Ok(())
+    }
+
+    // ========================================================================
+    // PNR RESOLUTION - Names usable wherever addresses are accepted
+    // ========================================================================
+
+    /// Turn a PNR name into an address (addresses pass straight through)
+    ///
+    /// For Students:
+    /// This is the "DNS lookup" step. "my-site" → its PNR records →
+    /// the default record → (maybe through a pointer) → an address.
+    /// Answers are remembered for the record's `ttl` seconds, just
+    /// like a DNS resolver caches answers (`Consistency::Strong` looks
+    /// the name up again, and caches the fresh answer).
+    pub async fn resolve_address(
+        &self,
+        name_or_address: &str,
+        use_network: bool,
+        consistency: Consistency,
+    ) -> Result<String> {
+        if is_network_address(name_or_address) {
+            return Ok(name_or_address.to_string());
+        }
+
+        let cache_key = format!("{}:{}", use_network, name_or_address);
+        if consistency == Consistency::Cached {
+            if let Some(resolved) = self.resolved_names.read().await.get(&cache_key) {
+                if resolved.expires_at > Instant::now() {
+                    log::info!("🌐 Resolved {} from cache", name_or_address);
+                    return Ok(resolved.address.clone());
+                }
+            }
+        }
+
+        let (pnr_name, sub_name) = split_pnr_name(name_or_address);
+        let record = self.lookup_pnr_record(pnr_name, sub_name, use_network).await?;
+
+        let address = match record.record_type {
+            // A = the address of the data itself
+            PnrRecordType::A => record.address.clone(),
+            // X = the address of a pointer, follow it to the data
+            PnrRecordType::X => self.get_pointer(&record.address, use_network).await?,
+            PnrRecordType::Txt => {
+                return Err(ServiceError::TypeMismatch(format!(
+                    "PNR '{}' is a TXT record and does not resolve to an address",
+                    name_or_address
+                ))
+                .into())
+            }
+        };
+
+        log::info!("🌐 Resolved {} → {}", name_or_address, address);
+        if record.ttl > 0 {
+            self.resolved_names.write().await.insert(
+                cache_key,
+                ResolvedName {
+                    address: address.clone(),
+                    expires_at: Instant::now() + Duration::from_secs(u64::from(record.ttl)),
+                },
+            );
+        }
+        Ok(address)
+    }
+
+    /// Find the record for `sub_name` under a PNR name (`None` = the apex)
+    pub async fn lookup_pnr_record(
+        &self,
+        name: &str,
+        sub_name: Option<&str>,
+        use_network: bool,
+    ) -> Result<PnrRecord> {
+        let records = self.get_pnr(name, use_network).await?;
+        let record = match sub_name {
+            Some(sub_name) => records.get(sub_name),
+            None => default_pnr_record(&records),
+        };
+        record.cloned().ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "PNR '{}' has no '{}' record",
+                name,
+                sub_name.unwrap_or("@")
+            ))
+            .into()
+        })
+    }
+
+    /// Drop cached resolutions for a name after its records change
+    async fn forget_resolved_name(&self, name: &str) {
+        self.resolved_names
+            .write()
+            .await
+            .retain(|key, _| match key.split_once(':') {
+                Some((_, cached)) => split_pnr_name(cached).0 != name,
+                None => true,
+            });
+    }
+
     // ========================================================================
     // KEY/VALUE - Object storage with buckets
     // ========================================================================
@@ -673,49 +1100,473 @@ impl NetworkService {
         bucket: &str,
         object: &str,
         content: &str,
+        content_type: Option<&str>,
         use_network: bool,
-    ) -> Result<String> {
-        let data = serde_json::json!({
+    ) -> Result<KeyValueResponse> {
+        let decoded = base64::engine::general_purpose::STANDARD.decode(content)?;
+        self.store_key_value_bytes(bucket, object, Bytes::from(decoded), content_type, use_network)
+            .await
+    }
+
+    /// Store raw object bytes (the JSON API sends base64, S3 sends bytes)
+    ///
+    /// For Students:
+    /// In a versioned bucket every write also gets a version ID and is
+    /// added to the object's history (like register history), so older
+    /// content can still be read - or brought back - later.
+    pub async fn store_key_value_bytes(
+        &self,
+        bucket: &str,
+        object: &str,
+        content: Bytes,
+        content_type: Option<&str>,
+        use_network: bool,
+    ) -> Result<KeyValueResponse> {
+        let mut data = serde_json::json!({
             "bucket": bucket,
             "object": object,
-            "content": content
+            "content": base64::engine::general_purpose::STANDARD.encode(&content),
+            "content_type": content_type.unwrap_or(DEFAULT_CONTENT_TYPE),
+            "size": content.len(),
+            "last_modified": chrono::Utc::now().timestamp(),
+            "etag": hex::encode(sha256_hash(&content)),
         });
-        let serialized = serde_json::to_vec(&data)?;
 
         if use_network {
             log::info!("🗄️ Storing key/value on network: {}/{}", bucket, object);
             // TODO: Real network implementation
-            self.store_chunk(Bytes::from(serialized), false).await
-        } else {
-            let hash = sha256_hash(&serialized);
-            let hex_address = hex::encode(&hash);
-            let key = format!("kv:{}:{}", bucket, object);
-            self.memory_cache
-                .write()
-                .await
-                .insert(key, Bytes::from(serialized));
-            Ok(hex_address)
+            let address = self
+                .store_chunk(Bytes::from(serde_json::to_vec(&data)?), false)
+                .await?;
+            return Ok(KeyValueResponse {
+                address,
+                bucket: bucket.to_string(),
+                object: object.to_string(),
+                version_id: None,
+            });
         }
+
+        let key = format!("kv:{}:{}", bucket, object);
+        let mut cache = self.memory_cache.write().await;
+        let version_id = if ensure_bucket(&mut cache, bucket)?.versioning {
+            // An object written before versioning was turned on becomes version "null"
+            if let Some(current) = cache.get(&key) {
+                let mut current: serde_json::Value = serde_json::from_slice(current)?;
+                if current["version_id"].is_null() {
+                    current["version_id"] = serde_json::json!(NULL_VERSION_ID);
+                    push_key_value_version(&mut cache, bucket, object, current)?;
+                }
+            }
+            let version_id = uuid::Uuid::new_v4().simple().to_string();
+            data["version_id"] = serde_json::json!(version_id);
+            push_key_value_version(&mut cache, bucket, object, data.clone())?;
+            Some(version_id)
+        } else {
+            // Versioning suspended: this write *is* the "null" version, replacing any older one
+            let history_key = format!("kvh:{}:{}", bucket, object);
+            if cache.contains_key(&history_key) {
+                let mut history = key_value_history(&cache, bucket, object)?;
+                history.retain(|entry| entry["version_id"] != NULL_VERSION_ID);
+                cache.insert(history_key, Bytes::from(serde_json::to_vec(&history)?));
+            }
+            None
+        };
+
+        let serialized = serde_json::to_vec(&data)?;
+        let address = hex::encode(sha256_hash(&serialized));
+        cache.insert(key, Bytes::from(serialized));
+        Ok(KeyValueResponse {
+            address,
+            bucket: bucket.to_string(),
+            object: object.to_string(),
+            version_id,
+        })
     }
 
+    /// Base64 content of the current object, or of one version
     pub async fn get_key_value(
         &self,
         bucket: &str,
         object: &str,
+        version_id: Option<&str>,
         use_network: bool,
     ) -> Result<String> {
         if !use_network {
             let cache = self.memory_cache.read().await;
-            let key = format!("kv:{}:{}", bucket, object);
-            if let Some(data) = cache.get(&key) {
-                let value: serde_json::Value = serde_json::from_slice(data)?;
-                Ok(value["content"].as_str().unwrap_or("").to_string())
-            } else {
-                Err(anyhow::anyhow!("Key/value not found"))
+            let value = find_key_value(&cache, bucket, object, version_id)?;
+            Ok(value["content"].as_str().unwrap_or("").to_string())
+        } else {
+            Err(network_unavailable("key/value"))
+        }
+    }
+
+    /// Object bytes plus metadata in one read
+    pub async fn get_key_value_bytes(
+        &self,
+        bucket: &str,
+        object: &str,
+        version_id: Option<&str>,
+        use_network: bool,
+    ) -> Result<(Bytes, KeyValueMetadata)> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        let cache = self.memory_cache.read().await;
+        let value = find_key_value(&cache, bucket, object, version_id)?;
+        let content = base64::engine::general_purpose::STANDARD
+            .decode(value["content"].as_str().unwrap_or(""))?;
+        Ok((Bytes::from(content), key_value_metadata(&value)))
+    }
+
+    /// Object metadata without the content (for HEAD and listings)
+    pub async fn head_key_value(
+        &self,
+        bucket: &str,
+        object: &str,
+        version_id: Option<&str>,
+        use_network: bool,
+    ) -> Result<KeyValueMetadata> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        let cache = self.memory_cache.read().await;
+        Ok(key_value_metadata(&find_key_value(&cache, bucket, object, version_id)?))
+    }
+
+    /// Delete an object, or permanently delete one of its versions
+    ///
+    /// For Students:
+    /// In a versioned bucket a plain delete doesn't destroy anything - it
+    /// adds a "delete marker" version that hides the object. Deleting that
+    /// marker (by its version ID) is the undo button. With versioning
+    /// suspended the marker takes the "null" version's place. Returns the
+    /// new delete marker's version ID, if one was added.
+    pub async fn delete_key_value(
+        &self,
+        bucket: &str,
+        object: &str,
+        version_id: Option<&str>,
+        use_network: bool,
+    ) -> Result<Option<String>> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        let key = format!("kv:{}:{}", bucket, object);
+        let history_key = format!("kvh:{}:{}", bucket, object);
+        let mut cache = self.memory_cache.write().await;
+
+        let Some(version_id) = version_id else {
+            log::info!("🗄️ Deleting key/value: {}/{}", bucket, object);
+            let versioning = cache
+                .get(&format!("kvb:{}", bucket))
+                .map(|data| serde_json::from_slice::<KeyValueBucket>(data))
+                .transpose()?
+                .is_some_and(|record| record.versioning);
+            let current = cache.remove(&key);
+            if !versioning && !cache.contains_key(&history_key) {
+                return if current.is_some() {
+                    Ok(None)
+                } else {
+                    Err(KeyValueError::ObjectNotFound.into())
+                };
+            }
+            if !versioning {
+                // Versioning suspended: the "null" version becomes a delete marker, older
+                // versions stay (same as S3)
+                let mut history = key_value_history(&cache, bucket, object)?;
+                history.retain(|entry| entry["version_id"] != NULL_VERSION_ID);
+                history.push(serde_json::json!({
+                    "bucket": bucket,
+                    "object": object,
+                    "version_id": NULL_VERSION_ID,
+                    "delete_marker": true,
+                    "last_modified": chrono::Utc::now().timestamp(),
+                }));
+                cache.insert(history_key, Bytes::from(serde_json::to_vec(&history)?));
+                return Ok(Some(NULL_VERSION_ID.to_string()));
+            }
+            match current {
+                // An object written before versioning was turned on is kept as version "null"
+                Some(current) => {
+                    let mut current: serde_json::Value = serde_json::from_slice(&current)?;
+                    if current["version_id"].is_null() {
+                        current["version_id"] = serde_json::json!(NULL_VERSION_ID);
+                        push_key_value_version(&mut cache, bucket, object, current)?;
+                    }
+                }
+                None if !cache.contains_key(&history_key) => {
+                    return Err(KeyValueError::ObjectNotFound.into());
+                }
+                None => {}
+            }
+
+            let marker_id = uuid::Uuid::new_v4().simple().to_string();
+            let marker = serde_json::json!({
+                "bucket": bucket,
+                "object": object,
+                "version_id": marker_id,
+                "delete_marker": true,
+                "last_modified": chrono::Utc::now().timestamp(),
+            });
+            push_key_value_version(&mut cache, bucket, object, marker)?;
+            return Ok(Some(marker_id));
+        };
+
+        log::info!("🗄️ Deleting key/value version: {}/{} @ {}", bucket, object, version_id);
+        let mut history = key_value_history(&cache, bucket, object)?;
+        let current_version = match cache.get(&key) {
+            Some(data) => serde_json::from_slice::<serde_json::Value>(data)?["version_id"]
+                .as_str()
+                .unwrap_or(NULL_VERSION_ID)
+                .to_string(),
+            None => String::new(),
+        };
+
+        match history.iter().position(|entry| entry["version_id"] == version_id) {
+            Some(index) => {
+                history.remove(index);
+            }
+            // The unversioned current object is version "null"
+            None if version_id == NULL_VERSION_ID && current_version == NULL_VERSION_ID => {}
+            None => return Err(KeyValueError::VersionNotFound(version_id.to_string()).into()),
+        }
+
+        // Deleting the current version (or a marker hiding it) brings the newest remaining one back
+        if current_version.is_empty() || current_version == version_id {
+            match history.last() {
+                Some(latest) if latest["delete_marker"] != true => {
+                    cache.insert(key, Bytes::from(serde_json::to_vec(latest)?));
+                }
+                _ => {
+                    cache.remove(&key);
+                }
             }
+        }
+        if history.is_empty() {
+            cache.remove(&history_key);
         } else {
-            Err(anyhow::anyhow!("Network key/value not yet implemented"))
+            cache.insert(history_key, Bytes::from(serde_json::to_vec(&history)?));
+        }
+        Ok(None)
+    }
+
+    /// Every version of an object, newest first (delete markers included)
+    pub async fn list_key_value_versions(
+        &self,
+        bucket: &str,
+        object: &str,
+        use_network: bool,
+    ) -> Result<Vec<KeyValueVersion>> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        let cache = self.memory_cache.read().await;
+        if !cache.contains_key(&format!("kvb:{}", bucket)) {
+            return Err(KeyValueError::BucketNotFound.into());
+        }
+        let history = key_value_history(&cache, bucket, object)?;
+        let current = cache
+            .get(&format!("kv:{}:{}", bucket, object))
+            .map(|data| serde_json::from_slice::<serde_json::Value>(data))
+            .transpose()?;
+
+        let mut versions = Vec::new();
+        // Written while versioning was off (or suspended): newer than all of history
+        let unversioned_current = current.as_ref().filter(|current| current["version_id"].is_null());
+        if let Some(current) = unversioned_current {
+            versions.push(key_value_version(current, true));
+        }
+        for (index, entry) in history.iter().enumerate().rev() {
+            let is_latest = unversioned_current.is_none() && index + 1 == history.len();
+            versions.push(key_value_version(entry, is_latest));
+        }
+
+        if versions.is_empty() {
+            return Err(KeyValueError::ObjectNotFound.into());
+        }
+        Ok(versions)
+    }
+
+    /// List objects in a bucket, S3 ListObjectsV2-style
+    ///
+    /// For Students:
+    /// Keys are sorted, then filtered by `prefix`. With a `delimiter`
+    /// (usually "/"), everything below the next "/" is rolled up into
+    /// one "common prefix" - that's how flat keys look like folders.
+    pub async fn list_key_values(
+        &self,
+        bucket: &str,
+        query: &ListObjectsQuery,
+        use_network: bool,
+    ) -> Result<ListObjectsResponse> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        let prefix = query.prefix.clone().unwrap_or_default();
+        let delimiter = query.delimiter.clone().filter(|d| !d.is_empty());
+        let max_keys = query.max_keys.unwrap_or(MAX_LIST_KEYS).clamp(1, MAX_LIST_KEYS);
+        let resume = match &query.continuation_token {
+            Some(token) => Some(ListPosition::from_token(token)?),
+            None => query.start_after.clone().map(ListPosition::After),
+        };
+
+        let cache = self.memory_cache.read().await;
+        if !cache.contains_key(&format!("kvb:{}", bucket)) {
+            return Err(KeyValueError::BucketNotFound.into());
+        }
+
+        let object_prefix = format!("kv:{}:", bucket);
+        let mut keys: Vec<&str> = cache
+            .keys()
+            .filter_map(|key| key.strip_prefix(&object_prefix))
+            .filter(|object| object.starts_with(&prefix))
+            .filter(|object| resume.as_ref().is_none_or(|position| position.is_before(object)))
+            .collect();
+        keys.sort_unstable();
+
+        let mut contents = Vec::new();
+        let mut common_prefixes: Vec<String> = Vec::new();
+        let mut last_position = None;
+        let mut is_truncated = false;
+
+        for object in keys {
+            let rolled_up = delimiter.as_ref().and_then(|delimiter| {
+                object[prefix.len()..]
+                    .find(delimiter.as_str())
+                    .map(|at| object[..prefix.len() + at + delimiter.len()].to_string())
+            });
+            if let Some(common) = &rolled_up {
+                if common_prefixes.last() == Some(common) {
+                    continue;
+                }
+                // start_after="docs/" (S3's v1 marker) already covers everything under docs/
+                if let Some(ListPosition::After(key)) = &resume {
+                    if common.as_str() <= key.as_str() {
+                        continue;
+                    }
+                }
+            }
+
+            if contents.len() + common_prefixes.len() == max_keys {
+                is_truncated = true;
+                break;
+            }
+
+            match rolled_up {
+                Some(common) => {
+                    last_position = Some(ListPosition::Below(common.clone()));
+                    common_prefixes.push(common);
+                }
+                None => {
+                    let data = &cache[&format!("{}{}", object_prefix, object)];
+                    contents.push(key_value_metadata(&serde_json::from_slice(data)?));
+                    last_position = Some(ListPosition::After(object.to_string()));
+                }
+            }
+        }
+
+        Ok(ListObjectsResponse {
+            bucket: bucket.to_string(),
+            prefix,
+            delimiter,
+            key_count: contents.len() + common_prefixes.len(),
+            contents,
+            common_prefixes,
+            max_keys,
+            is_truncated,
+            next_continuation_token: if is_truncated {
+                last_position.map(|position| position.to_token())
+            } else {
+                None
+            },
+        })
+    }
+
+    pub async fn list_buckets(&self, use_network: bool) -> Result<Vec<KeyValueBucket>> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        let cache = self.memory_cache.read().await;
+        let mut buckets = cache
+            .iter()
+            .filter(|(key, _)| key.starts_with("kvb:"))
+            .map(|(_, data)| Ok(serde_json::from_slice(data)?))
+            .collect::<Result<Vec<KeyValueBucket>>>()?;
+        buckets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(buckets)
+    }
+
+    /// Create a bucket (does nothing if it already exists)
+    ///
+    /// `versioning: Some(true/false)` turns versioning on or suspends it;
+    /// suspending keeps existing versions but stops adding new ones.
+    pub async fn create_bucket(
+        &self,
+        bucket: &str,
+        versioning: Option<bool>,
+        use_network: bool,
+    ) -> Result<KeyValueBucket> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        log::info!("🗄️ Creating bucket: {}", bucket);
+        let mut cache = self.memory_cache.write().await;
+        let mut record = ensure_bucket(&mut cache, bucket)?;
+        if let Some(versioning) = versioning.filter(|v| *v != record.versioning) {
+            log::info!("🗄️ Bucket {} versioning: {}", bucket, if versioning { "on" } else { "suspended" });
+            record.versioning = versioning;
+            cache.insert(format!("kvb:{}", bucket), Bytes::from(serde_json::to_vec(&record)?));
+        }
+        Ok(record)
+    }
+
+    /// Does the bucket keep versions? (versioning on, or old versions left from before)
+    ///
+    /// Such a bucket can't become empty by deleting its objects - every
+    /// delete only adds a marker.
+    pub async fn bucket_keeps_versions(&self, bucket: &str, use_network: bool) -> Result<bool> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
+        }
+
+        let cache = self.memory_cache.read().await;
+        let versioning = cache
+            .get(&format!("kvb:{}", bucket))
+            .map(|data| serde_json::from_slice::<KeyValueBucket>(data))
+            .transpose()?
+            .is_some_and(|record| record.versioning);
+        let history_prefix = format!("kvh:{}:", bucket);
+        Ok(versioning || cache.keys().any(|key| key.starts_with(&history_prefix)))
+    }
+
+    /// Delete an empty bucket
+    pub async fn delete_bucket(&self, bucket: &str, use_network: bool) -> Result<()> {
+        if use_network {
+            return Err(network_unavailable("key/value"));
         }
+
+        log::info!("🗄️ Deleting bucket: {}", bucket);
+        let mut cache = self.memory_cache.write().await;
+        // Old versions and delete markers count too
+        let object_prefix = format!("kv:{}:", bucket);
+        let history_prefix = format!("kvh:{}:", bucket);
+        if cache
+            .keys()
+            .any(|key| key.starts_with(&object_prefix) || key.starts_with(&history_prefix))
+        {
+            return Err(KeyValueError::BucketNotEmpty.into());
+        }
+        cache
+            .remove(&format!("kvb:{}", bucket))
+            .map(|_| ())
+            .ok_or_else(|| KeyValueError::BucketNotFound.into())
     }
 
     // ========================================================================
@@ -749,10 +1600,432 @@ impl NetworkService {
         cache
             .get(address)
             .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Public data not found"))
+            .ok_or_else(|| ServiceError::NotFound("Public data not found".to_string()).into())
     }
 }
 
+/// Is this a raw network address (64 hex chars) rather than a PNR name?
+pub fn is_network_address(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Split `blog.my-site` into the PNR name (`my-site`) and sub-name (`blog`)
+pub fn split_pnr_name(full_name: &str) -> (&str, Option<&str>) {
+    match full_name.rsplit_once('.') {
+        Some((sub_name, name)) => (name, Some(sub_name)),
+        None => (full_name, None),
+    }
+}
+
+/// Why a PNR change was refused
+#[derive(Debug, thiserror::Error)]
+pub enum PnrAccessError {
+    #[error("PNR '{0}' is owned - send its x-owner-key to change it")]
+    OwnerKeyRequired(String),
+    #[error("x-owner-key does not own PNR '{0}'")]
+    NotOwner(String),
+    #[error("PNR '{0}' is already registered - change it with PUT or PATCH")]
+    AlreadyExists(String),
+}
+
+/// Check the x-owner-key of a write to an existing name, returning its owner
+///
+/// Every name stores a hash of the key it was registered with, and only
+/// that key can change or delete it. (A stored name without an owner
+/// matches no key, so it can't be taken over either.)
+fn pnr_owner_for_write(name: &str, existing: &Bytes, owner_key: Option<&str>) -> Result<String> {
+    let Some(owner_key) = owner_key else {
+        return Err(PnrAccessError::OwnerKeyRequired(name.to_string()).into());
+    };
+    let key_hash = hex::encode(sha256_hash(owner_key.as_bytes()));
+    let value: serde_json::Value = serde_json::from_slice(existing)?;
+    match value["owner"].as_str() {
+        Some(owner) if owner == key_hash => Ok(key_hash),
+        _ => Err(PnrAccessError::NotOwner(name.to_string()).into()),
+    }
+}
+
+/// Serialize a PNR name as it is kept in memory
+fn pnr_document(name: &str, records: &HashMap<String, PnrRecord>, owner: &str) -> Result<Bytes> {
+    let data = serde_json::json!({
+        "name": name,
+        "records": records,
+        "owner": owner,
+        "type": "pnr"
+    });
+    Ok(Bytes::from(serde_json::to_vec(&data)?))
+}
+
+/// Pick the record a bare name resolves to
+///
+/// The apex record is keyed "@" (like a DNS zone file). A name with
+/// only one record resolves to that record.
+fn default_pnr_record(records: &HashMap<String, PnrRecord>) -> Option<&PnrRecord> {
+    records.get("@").or_else(|| match records.len() {
+        1 => records.values().next(),
+        _ => None,
+    })
+}
+
+/// One change to apply with `update_archive`
+#[derive(Debug, Clone)]
+pub enum ArchiveChange {
+    /// Add a file, or replace the file at this path
+    Put {
+        path: String,
+        content: Bytes,
+        modified: Option<i64>,
+    },
+    /// Remove a file, or every file under a directory
+    Delete { path: String },
+    /// Move a file or directory; the destination must not exist
+    Rename { from: String, to: String },
+}
+
+/// Why an archive change was refused
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("Path not found in archive: {0}")]
+    PathNotFound(String),
+    #[error("Path already exists in archive: {0}")]
+    PathExists(String),
+    #[error("Path appears more than once in archive: {0}")]
+    DuplicatePath(String),
+    #[error("Path clashes with a file or directory in archive: {0}")]
+    PathConflict(String),
+}
+
+/// Would `path` have to sit inside something that is already a file?
+fn conflicts_with(entries: &[ArchiveEntry], path: &str) -> bool {
+    entries
+        .iter()
+        .any(|entry| path.starts_with(&format!("{}/", entry.path)))
+}
+
+/// Compare an archive's files with a desired state of (path, sha256) pairs
+///
+/// `to` is left empty - the caller knows what it compared against.
+pub fn diff_archive(
+    from: &str,
+    current: &[ArchiveEntry],
+    desired: &[(String, String)],
+) -> ArchiveDiffResponse {
+    let current_hashes: HashMap<&str, &str> = current
+        .iter()
+        .map(|entry| (entry.path.as_str(), entry.sha256.as_str()))
+        .collect();
+    let desired_paths: std::collections::HashSet<&str> =
+        desired.iter().map(|(path, _)| path.as_str()).collect();
+
+    let mut diff = ArchiveDiffResponse {
+        from: from.to_string(),
+        ..Default::default()
+    };
+    for (path, sha256) in desired {
+        match current_hashes.get(path.as_str()) {
+            None => diff.added.push(path.clone()),
+            Some(current) if current.eq_ignore_ascii_case(sha256) => {
+                diff.unchanged.push(path.clone())
+            }
+            Some(_) => diff.modified.push(path.clone()),
+        }
+    }
+    diff.removed = current
+        .iter()
+        .filter(|entry| !desired_paths.contains(entry.path.as_str()))
+        .map(|entry| entry.path.clone())
+        .collect();
+
+    for paths in [&mut diff.added, &mut diff.removed, &mut diff.modified, &mut diff.unchanged] {
+        paths.sort();
+    }
+    diff
+}
+
+/// Work out which uploads and changes turn an archive into a desired state
+///
+/// For Students:
+/// A file that only moved (same content, new path, old path gone) becomes
+/// a rename, so it isn't uploaded again. Everything added or modified
+/// whose content isn't being moved has to be uploaded.
+pub fn plan_archive_sync(
+    address: &str,
+    current: &[ArchiveEntry],
+    desired: &[(String, String)],
+) -> ArchiveSyncPlan {
+    let diff = diff_archive(address, current, desired);
+    let desired_hashes: HashMap<&str, String> = desired
+        .iter()
+        .map(|(path, sha256)| (path.as_str(), sha256.to_lowercase()))
+        .collect();
+    let current_hashes: HashMap<&str, &str> = current
+        .iter()
+        .map(|entry| (entry.path.as_str(), entry.sha256.as_str()))
+        .collect();
+
+    // Removed files, by content, that an added path can be renamed from
+    let mut movable: HashMap<&str, Vec<&str>> = HashMap::new();
+    for path in &diff.removed {
+        if let Some(sha256) = current_hashes.get(path.as_str()) {
+            movable.entry(*sha256).or_default().push(path.as_str());
+        }
+    }
+
+    let mut renames = Vec::new();
+    let mut upload = Vec::new();
+    for path in &diff.added {
+        let source = movable
+            .get_mut(desired_hashes[path.as_str()].as_str())
+            .and_then(|sources| (!sources.is_empty()).then(|| sources.remove(0)));
+        match source {
+            Some(from) => renames.push(ArchivePathChange::Rename {
+                from: from.to_string(),
+                to: path.clone(),
+            }),
+            None => upload.push(path.clone()),
+        }
+    }
+    upload.extend(diff.modified.iter().cloned());
+    upload.sort();
+
+    // Deletes first, so a rename never lands on a path that is going away
+    let mut deletes: Vec<&str> = movable.into_values().flatten().collect();
+    deletes.sort_unstable();
+    let mut changes: Vec<ArchivePathChange> = deletes
+        .into_iter()
+        .map(|path| ArchivePathChange::Delete {
+            path: path.to_string(),
+        })
+        .collect();
+    changes.extend(renames);
+
+    ArchiveSyncPlan {
+        address: address.to_string(),
+        upload,
+        changes,
+        unchanged: diff.unchanged.len(),
+    }
+}
+
+/// Is `path` the file `target`, or inside the directory `target`?
+fn is_at_or_under(path: &str, target: &str) -> bool {
+    let target = target.trim_end_matches('/');
+    path == target
+        || path
+            .strip_prefix(target)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Helper: Error for storage the network build can't do yet
+fn network_unavailable(what: &str) -> anyhow::Error {
+    ServiceError::BackendUnavailable(format!("Network {} not yet implemented", what)).into()
+}
+
+/// Smallest file worth precompressing
+const PRECOMPRESS_MIN_BYTES: u64 = 1024;
+
+/// Helper: Text-like files (by extension) that compress well
+fn is_compressible(path: &str, size: u64) -> bool {
+    const EXTENSIONS: &[&str] = &[
+        "html", "htm", "css", "js", "mjs", "json", "map", "svg", "txt", "xml", "md", "csv",
+        "wasm", "webmanifest", "yaml", "yml", "toml",
+    ];
+    size >= PRECOMPRESS_MIN_BYTES
+        && path
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Helper: Brotli and gzip copies of `content`, keeping only those that
+/// save at least 10% (best first)
+///
+/// Files are compressed once when stored, so the slowest, smallest
+/// settings are worth it.
+fn compress_variants(content: &[u8]) -> std::io::Result<Vec<(&'static str, Vec<u8>)>> {
+    use std::io::Write;
+
+    let mut brotli = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut brotli, 4096, 11, 22);
+        writer.write_all(content)?;
+    }
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    gzip.write_all(content)?;
+    let gzip = gzip.finish()?;
+
+    let worth_it = |compressed: &Vec<u8>| compressed.len() * 10 <= content.len() * 9;
+    Ok([("br", brotli), ("gzip", gzip)]
+        .into_iter()
+        .filter(|(_, compressed)| worth_it(compressed))
+        .collect())
+}
+
+/// Why a key/value operation failed
+#[derive(Debug, thiserror::Error)]
+pub enum KeyValueError {
+    #[error("Bucket not found")]
+    BucketNotFound,
+    #[error("Bucket is not empty")]
+    BucketNotEmpty,
+    #[error("Key/value not found")]
+    ObjectNotFound,
+    #[error("Version '{0}' not found")]
+    VersionNotFound(String),
+}
+
+/// Where a paged object listing picks up again
+enum ListPosition {
+    /// Continue with keys after this one
+    After(String),
+    /// Continue after every key under this common prefix
+    Below(String),
+}
+
+impl ListPosition {
+    fn is_before(&self, object: &str) -> bool {
+        match self {
+            Self::After(key) => object > key.as_str(),
+            Self::Below(common) => object > common.as_str() && !object.starts_with(common.as_str()),
+        }
+    }
+
+    /// Continuation tokens are opaque to clients - base64 of "k:"/"p:" + key
+    fn to_token(&self) -> String {
+        let raw = match self {
+            Self::After(key) => format!("k:{}", key),
+            Self::Below(common) => format!("p:{}", common),
+        };
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    fn from_token(token: &str) -> Result<Self> {
+        let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| ServiceError::InvalidInput("Invalid continuation token".to_string()))?;
+        match raw.split_at_checked(2) {
+            Some(("k:", key)) => Ok(Self::After(key.to_string())),
+            Some(("p:", common)) => Ok(Self::Below(common.to_string())),
+            _ => Err(ServiceError::InvalidInput("Invalid continuation token".to_string()).into()),
+        }
+    }
+}
+
+/// Create the bucket record if it's missing, returning the bucket
+fn ensure_bucket(cache: &mut HashMap<String, Bytes>, bucket: &str) -> Result<KeyValueBucket> {
+    let key = format!("kvb:{}", bucket);
+    if let Some(data) = cache.get(&key) {
+        return Ok(serde_json::from_slice(data)?);
+    }
+
+    let created = KeyValueBucket {
+        name: bucket.to_string(),
+        created: chrono::Utc::now().timestamp(),
+        versioning: false,
+    };
+    cache.insert(key, Bytes::from(serde_json::to_vec(&created)?));
+    Ok(created)
+}
+
+/// Read the metadata out of a stored key/value object
+fn key_value_metadata(value: &serde_json::Value) -> KeyValueMetadata {
+    KeyValueMetadata {
+        bucket: value["bucket"].as_str().unwrap_or("").to_string(),
+        object: value["object"].as_str().unwrap_or("").to_string(),
+        content_type: value["content_type"]
+            .as_str()
+            .unwrap_or(DEFAULT_CONTENT_TYPE)
+            .to_string(),
+        size: value["size"].as_u64().unwrap_or(0),
+        last_modified: value["last_modified"].as_i64().unwrap_or(0),
+        etag: value["etag"].as_str().unwrap_or("").to_string(),
+        version_id: value["version_id"].as_str().map(str::to_string),
+    }
+}
+
+/// Summarise one stored version for a version listing
+fn key_value_version(value: &serde_json::Value, is_latest: bool) -> KeyValueVersion {
+    let is_delete_marker = value["delete_marker"] == true;
+    KeyValueVersion {
+        version_id: value["version_id"]
+            .as_str()
+            .unwrap_or(NULL_VERSION_ID)
+            .to_string(),
+        is_latest,
+        is_delete_marker,
+        last_modified: value["last_modified"].as_i64().unwrap_or(0),
+        size: value["size"].as_u64().filter(|_| !is_delete_marker),
+        etag: value["etag"].as_str().map(str::to_string),
+        content_type: value["content_type"].as_str().map(str::to_string),
+    }
+}
+
+/// The current object, or a specific version of it
+fn find_key_value(
+    cache: &HashMap<String, Bytes>,
+    bucket: &str,
+    object: &str,
+    version_id: Option<&str>,
+) -> Result<serde_json::Value> {
+    let current = cache
+        .get(&format!("kv:{}:{}", bucket, object))
+        .map(|data| serde_json::from_slice::<serde_json::Value>(data))
+        .transpose()?;
+    let Some(version_id) = version_id else {
+        return current.ok_or_else(|| KeyValueError::ObjectNotFound.into());
+    };
+
+    let found = key_value_history(cache, bucket, object)?
+        .into_iter()
+        .find(|entry| entry["version_id"] == version_id)
+        .or_else(|| current.filter(|c| version_id == NULL_VERSION_ID && c["version_id"].is_null()))
+        .ok_or_else(|| KeyValueError::VersionNotFound(version_id.to_string()))?;
+    if found["delete_marker"] == true {
+        return Err(KeyValueError::ObjectNotFound.into());
+    }
+    Ok(found)
+}
+
+/// All stored versions of an object, oldest first
+fn key_value_history(
+    cache: &HashMap<String, Bytes>,
+    bucket: &str,
+    object: &str,
+) -> Result<Vec<serde_json::Value>> {
+    match cache.get(&format!("kvh:{}:{}", bucket, object)) {
+        Some(data) => Ok(serde_json::from_slice(data)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn push_key_value_version(
+    cache: &mut HashMap<String, Bytes>,
+    bucket: &str,
+    object: &str,
+    version: serde_json::Value,
+) -> Result<()> {
+    let mut history = key_value_history(cache, bucket, object)?;
+    history.push(version);
+    cache.insert(
+        format!("kvh:{}:{}", bucket, object),
+        Bytes::from(serde_json::to_vec(&history)?),
+    );
+    Ok(())
+}
+
+/// Version ID of an object written while its bucket wasn't versioned (same as S3)
+const NULL_VERSION_ID: &str = "null";
+
+/// Content type for objects stored without one
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Most objects a single listing page returns (same as S3)
+const MAX_LIST_KEYS: usize = 1000;
+
+/// Upper bound on how many entries a single graph walk will visit
+const MAX_GRAPH_WALK_NODES: usize = 10_000;
+
 /// Simple SHA256 hash for memory storage
 fn sha256_hash(data: &[u8]) -> Vec<u8> {
     use sha2::{Digest, Sha256};
@@ -0,0 +1,363 @@
+// src/services/publisher.rs
+//! Publisher - Turn a directory on the server's disk into an archive
+//!
+//! For 1st Year CS Students:
+//! Uploading a website file by file is tedious. If the files are already
+//! on the machine running the server, we can walk the folder ourselves
+//! (like `find`), read every file and store them as one archive - then
+//! optionally move a pointer or PNR name to it so the site goes live.
+//!
+//! Reading the server's disk is powerful, so it is off unless PUBLISH_ROOT
+//! is set, and only directories inside PUBLISH_ROOT can be published.
+
+use bytes::Bytes;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config::{Config, ConfigError};
+use crate::models::{
+    PnrRecord, PnrRecordType, PublishFormat, PublishRequest, PublishResponse, SkippedPath,
+    SymlinkPolicy,
+};
+use crate::services::{split_pnr_name, NetworkService};
+
+/// TTL for PNR records created by publishing
+const PUBLISH_TTL: u32 = 60;
+
+/// Publishing settings (`publish.root` in the config)
+#[derive(Debug, Clone)]
+pub struct PublishConfig {
+    /// Only directories inside this one can be published (canonical path)
+    pub root: PathBuf,
+}
+
+impl PublishConfig {
+    /// Publishing settings from the server configuration
+    ///
+    /// Returns `Ok(None)` unless `publish.root` is set.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, ConfigError> {
+        let Some(root) = &config.publish.root else {
+            return Ok(None);
+        };
+        let root = std::fs::canonicalize(root).map_err(|e| {
+            ConfigError::invalid("publish.root", format!("{}: {}", root.display(), e))
+        })?;
+        if !root.is_dir() {
+            return Err(ConfigError::invalid(
+                "publish.root",
+                format!("{} is not a directory", root.display()),
+            ));
+        }
+        Ok(Some(Self { root }))
+    }
+}
+
+/// Why a directory couldn't be published
+#[derive(Debug, thiserror::Error)]
+pub enum PublishError {
+    #[error("{0} is outside PUBLISH_ROOT")]
+    OutsideRoot(String),
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
+    #[error("Invalid ignore pattern '{0}': {1}")]
+    InvalidPattern(String, String),
+    #[error("Symbolic link found (symlinks policy is \"error\"): {0}")]
+    SymlinkRefused(String),
+    #[error("Nothing to publish in {0}")]
+    Empty(String),
+    #[error("Failed to read {0}: {1}")]
+    Io(String, String),
+    #[error("Pointer not found: {0}")]
+    PointerNotFound(String),
+    #[error("Registering the new PNR name '{0}' needs an x-owner-key")]
+    OwnerKeyRequired(String),
+}
+
+/// One file found by the walk, with its path relative to the directory
+#[derive(Debug, Clone)]
+pub struct CollectedFile {
+    /// "/"-separated relative path (e.g. "css/site.css")
+    pub path: String,
+    pub content: Bytes,
+    /// Unix timestamp of the file's last modification
+    pub modified: Option<i64>,
+}
+
+/// Everything the walk found (and left out)
+#[derive(Debug, Default)]
+pub struct CollectedDirectory {
+    pub files: Vec<CollectedFile>,
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// Walk `path` (inside the publish root) and read every file to publish
+///
+/// This does blocking file I/O - call it from `web::block`.
+pub fn collect_directory(
+    config: &PublishConfig,
+    path: &str,
+    ignore: &[String],
+    symlinks: SymlinkPolicy,
+) -> Result<CollectedDirectory, PublishError> {
+    let requested = config.root.join(path);
+    let directory = std::fs::canonicalize(&requested)
+        .map_err(|_| PublishError::NotADirectory(path.to_string()))?;
+    if !directory.starts_with(&config.root) {
+        return Err(PublishError::OutsideRoot(path.to_string()));
+    }
+    if !directory.is_dir() {
+        return Err(PublishError::NotADirectory(path.to_string()));
+    }
+    let ignore = build_ignore_set(ignore)?;
+
+    log::info!("📤 Walking {}", directory.display());
+    let mut collected = CollectedDirectory::default();
+    let mut walker = WalkDir::new(&directory)
+        .follow_links(symlinks == SymlinkPolicy::Follow)
+        .sort_by_file_name()
+        .into_iter();
+
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.loop_ancestor().is_some() => {
+                collected.skipped.push(SkippedPath {
+                    path: relative_path(&directory, e.path().unwrap_or(&directory)),
+                    reason: "symbolic link loop".to_string(),
+                });
+                continue;
+            }
+            Err(e) => {
+                let failed_path = e.path().unwrap_or(&directory).display().to_string();
+                return Err(PublishError::Io(failed_path, e.to_string()));
+            }
+        };
+        if entry.depth() == 0 {
+            continue;
+        }
+
+        let relative = relative_path(&directory, entry.path());
+        let is_dir = entry.file_type().is_dir();
+        if ignore.is_match(&relative) {
+            if is_dir {
+                walker.skip_current_dir();
+            }
+            collected.skipped.push(SkippedPath {
+                path: relative,
+                reason: "ignored".to_string(),
+            });
+            continue;
+        }
+
+        if entry.path_is_symlink() {
+            match symlinks {
+                SymlinkPolicy::Skip => {
+                    collected.skipped.push(SkippedPath {
+                        path: relative,
+                        reason: "symbolic link".to_string(),
+                    });
+                    continue;
+                }
+                SymlinkPolicy::Error => return Err(PublishError::SymlinkRefused(relative)),
+                SymlinkPolicy::Follow => {
+                    // Following a link must not become a way out of PUBLISH_ROOT
+                    let inside = std::fs::canonicalize(entry.path())
+                        .is_ok_and(|target| target.starts_with(&config.root));
+                    if !inside {
+                        if is_dir {
+                            walker.skip_current_dir();
+                        }
+                        collected.skipped.push(SkippedPath {
+                            path: relative,
+                            reason: "symbolic link points outside PUBLISH_ROOT".to_string(),
+                        });
+                        continue;
+                    }
+                }
+            }
+        }
+
+        if is_dir {
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            collected.skipped.push(SkippedPath {
+                path: relative,
+                reason: "not a regular file".to_string(),
+            });
+            continue;
+        }
+
+        let content = std::fs::read(entry.path())
+            .map_err(|e| PublishError::Io(relative.clone(), e.to_string()))?;
+        let modified = entry
+            .metadata()
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).timestamp());
+        collected.files.push(CollectedFile {
+            path: relative,
+            content: Bytes::from(content),
+            modified,
+        });
+    }
+
+    if collected.files.is_empty() {
+        return Err(PublishError::Empty(path.to_string()));
+    }
+    Ok(collected)
+}
+
+/// Store the collected files, then move the pointer/PNR name if asked
+///
+/// `owner_key` is needed to change an owned PNR name.
+pub async fn publish_collected(
+    network: &NetworkService,
+    request: &PublishRequest,
+    collected: CollectedDirectory,
+    owner_key: Option<&str>,
+    use_network: bool,
+) -> anyhow::Result<PublishResponse> {
+    // Check the pointer first, so a typo doesn't leave an unannounced archive
+    if let Some(pointer) = &request.pointer {
+        if network.get_pointer(pointer, use_network).await.is_err() {
+            return Err(PublishError::PointerNotFound(pointer.clone()).into());
+        }
+    }
+
+    let files = collected.files.len();
+    let bytes = collected
+        .files
+        .iter()
+        .map(|file| file.content.len() as u64)
+        .sum();
+
+    let address = match request.format {
+        PublishFormat::Archive => {
+            // Archive manifests keep each file's modification time
+            let mut entries = Vec::with_capacity(files);
+            for file in collected.files {
+                entries.push(
+                    network
+                        .store_archive_file(&file.path, file.content, file.modified, use_network)
+                        .await?,
+                );
+            }
+            network.store_archive_manifest(entries, use_network).await?
+        }
+        PublishFormat::Tarchive => {
+            let files = collected
+                .files
+                .into_iter()
+                .map(|file| (PathBuf::from(file.path), file.content))
+                .collect();
+            network.store_tarchive(files, use_network).await?
+        }
+    };
+    log::info!("📤 Published {} ({} files) → {}", request.path, files, address);
+
+    if let Some(pointer) = &request.pointer {
+        network.set_pointer_target(pointer, &address, use_network).await?;
+    }
+    if let Some(pnr) = &request.pnr {
+        point_pnr_at(network, pnr, &address, owner_key, use_network).await?;
+    }
+
+    Ok(PublishResponse {
+        address,
+        format: request.format,
+        files,
+        bytes,
+        skipped: collected.skipped,
+        pointer: request.pointer.clone(),
+        pnr: request.pnr.clone(),
+    })
+}
+
+/// Make a PNR record (`name` or `sub.name`) resolve to `address`
+///
+/// An `X` record is followed and its pointer moved instead, so every
+/// other name sharing that pointer moves too. A name that doesn't exist
+/// yet is created, owned by `owner_key` (which it then needs).
+async fn point_pnr_at(
+    network: &NetworkService,
+    full_name: &str,
+    address: &str,
+    owner_key: Option<&str>,
+    use_network: bool,
+) -> anyhow::Result<()> {
+    let (name, sub_name) = split_pnr_name(full_name);
+    let record_name = sub_name.unwrap_or("@");
+
+    // A name that can't be read yet is created below
+    let mut records = network
+        .get_pnr(name, use_network)
+        .await
+        .unwrap_or_default();
+    let created = records.is_empty();
+    match records.get(record_name) {
+        Some(record) if record.record_type == PnrRecordType::X => {
+            let pointer = record.address.clone();
+            return network.set_pointer_target(&pointer, address, use_network).await;
+        }
+        existing => {
+            let ttl = existing.map_or(PUBLISH_TTL, |record| record.ttl);
+            records.insert(
+                record_name.to_string(),
+                PnrRecord {
+                    address: address.to_string(),
+                    record_type: PnrRecordType::A,
+                    ttl,
+                },
+            );
+        }
+    }
+
+    if created {
+        let owner_key =
+            owner_key.ok_or_else(|| PublishError::OwnerKeyRequired(name.to_string()))?;
+        network.store_pnr(name, &records, owner_key, use_network).await?;
+    } else {
+        network.update_pnr(name, &records, owner_key, use_network).await?;
+    }
+    log::info!("📛 PNR {} → {}", full_name, address);
+    Ok(())
+}
+
+/// Helper: Compile ignore patterns
+///
+/// Patterns without a "/" match at any depth (".git" skips every .git
+/// folder); patterns with one are matched from the published directory.
+fn build_ignore_set(patterns: &[String]) -> Result<GlobSet, PublishError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let trimmed = pattern.trim().trim_end_matches('/');
+        if trimmed.is_empty() {
+            continue;
+        }
+        let glob = match trimmed.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if !trimmed.contains('/') => format!("**/{}", trimmed),
+            None => trimmed.to_string(),
+        };
+        let glob = GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| PublishError::InvalidPattern(pattern.clone(), e.to_string()))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| PublishError::InvalidPattern(patterns.join(", "), e.to_string()))
+}
+
+/// Helper: "/"-separated path of `path` relative to `directory`
+fn relative_path(directory: &Path, path: &Path) -> String {
+    path.strip_prefix(directory)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,498 @@
+// src/services/tokens.rs
+//! API tokens - Who may read or change what
+//!
+//! For 1st Year CS Students:
+//! A token is a long random password a client sends with every request
+//! (`Authorization: Bearer <token>`). Each token carries *scopes*, the
+//! things it is allowed to do:
+//!
+//!   read:archive             read archives
+//!   write:key_value/notes    read and change the "notes" bucket only
+//!   read:*                   read everything
+//!   admin                    everything, including issuing tokens
+//!
+//! Tokens come from the `[auth]` config or are issued through
+//! /anttp-0/admin/token. We never keep an issued token itself - only its
+//! SHA-256 hash, like a password database - so a leaked `tokens.json`
+//! doesn't give anyone access.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::config::{AuthConfig, ConfigError};
+use crate::models::{IssuedTokenResponse, TokenInfo, TokenRequest, TokenSource};
+use crate::routes::api_groups;
+
+/// File in `storage.data_dir` keeping issued tokens across restarts
+const TOKENS_FILE: &str = "tokens.json";
+
+/// Groups that aren't data types (scopes can't name them)
+const UNSCOPED_GROUPS: &[&str] = &["system", "docs", "admin"];
+
+// ============================================================================
+// SCOPES - "read:archive", "write:key_value/notes", "admin"
+// ============================================================================
+
+/// What a request does (and what a scope allows)
+///
+/// Ordered: `write` includes `read`, `admin` includes both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+    Read,
+    Write,
+    Admin,
+}
+
+impl Access {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// One thing a token may do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    pub access: Access,
+    /// Route group ("archive", "key_value", ...); `None` = every data type
+    pub data_type: Option<String>,
+    /// Bucket or PNR name; `None` = all of them
+    pub name: Option<String>,
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        if scope == "admin" {
+            return Ok(Self {
+                access: Access::Admin,
+                data_type: None,
+                name: None,
+            });
+        }
+        let usage = || {
+            format!(
+                "'{}' should look like read:archive, write:key_value/notes, read:* or admin",
+                scope
+            )
+        };
+        let (access, target) = scope.split_once(':').ok_or_else(usage)?;
+        let access = match access {
+            "read" => Access::Read,
+            "write" => Access::Write,
+            _ => return Err(usage()),
+        };
+        let (data_type, name) = match target.split_once('/') {
+            Some((data_type, name)) if !name.is_empty() => (data_type, Some(name.to_string())),
+            Some(_) => return Err(usage()),
+            None => (target, None),
+        };
+        let data_type = match data_type {
+            "*" if name.is_none() => None,
+            "*" => return Err(usage()),
+            data_type if is_data_type(data_type) => Some(data_type.to_string()),
+            data_type => {
+                return Err(format!(
+                    "unknown data type '{}' in '{}' (one of: {})",
+                    data_type,
+                    scope,
+                    data_types().join(", ")
+                ))
+            }
+        };
+        Ok(Self {
+            access,
+            data_type,
+            name,
+        })
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.access == Access::Admin {
+            return write!(f, "admin");
+        }
+        write!(
+            f,
+            "{}:{}",
+            self.access.as_str(),
+            self.data_type.as_deref().unwrap_or("*")
+        )?;
+        match &self.name {
+            Some(name) => write!(f, "/{}", name),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Scope {
+    /// May a request with this scope do `access` on `data_type` (bucket/name `name`)?
+    pub fn allows(&self, access: Access, data_type: &str, name: Option<&str>) -> bool {
+        if self.access == Access::Admin {
+            return true;
+        }
+        access <= self.access
+            && access != Access::Admin
+            && self.data_type.as_deref().is_none_or(|own| own == data_type)
+            && self.name.as_deref().is_none_or(|own| Some(own) == name)
+    }
+
+    /// Could this scope allow `access` on `data_type` for at least one name?
+    pub fn allows_some_name(&self, access: Access, data_type: &str) -> bool {
+        self.allows(access, data_type, self.name.as_deref())
+    }
+}
+
+/// Parse a list of scopes (the first bad one is the error)
+pub fn parse_scopes(scopes: &[String]) -> Result<Vec<Scope>, String> {
+    scopes.iter().map(|scope| scope.parse()).collect()
+}
+
+/// Helper: Route groups a scope can name
+fn data_types() -> Vec<&'static str> {
+    api_groups()
+        .iter()
+        .map(|group| group.name)
+        .filter(|name| !UNSCOPED_GROUPS.contains(name))
+        .collect()
+}
+
+fn is_data_type(name: &str) -> bool {
+    data_types().contains(&name)
+}
+
+// ============================================================================
+// TOKEN STORE - Configured and issued tokens
+// ============================================================================
+
+/// Why a token operation failed
+#[derive(Debug, thiserror::Error)]
+pub enum TokenError {
+    #[error("Invalid token request: {0}")]
+    Invalid(String),
+    #[error("Token not found: {0}")]
+    NotFound(String),
+    #[error("Token '{0}' is defined in the config - remove it there")]
+    Configured(String),
+    #[error("Failed to save tokens: {0}")]
+    Io(String),
+}
+
+/// A token as kept in memory and in `tokens.json` (hash, never the secret)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    id: String,
+    name: String,
+    scopes: Vec<String>,
+    /// SHA-256 of the secret (hex)
+    sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+    #[serde(skip)]
+    source: Option<TokenSource>,
+    #[serde(skip)]
+    parsed: Vec<Scope>,
+}
+
+impl StoredToken {
+    fn info(&self) -> TokenInfo {
+        TokenInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            scopes: self.scopes.clone(),
+            source: self.source.unwrap_or(TokenSource::Issued),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+        }
+    }
+}
+
+/// Every token the server accepts
+pub struct TokenStore {
+    /// From `[auth]` (fixed while the server runs)
+    configured: Vec<StoredToken>,
+    issued: RwLock<Vec<StoredToken>>,
+    /// `<data_dir>/tokens.json` (`None` = issued tokens are lost on restart)
+    file: Option<PathBuf>,
+}
+
+impl TokenStore {
+    /// Configured tokens, plus the ones issued before the last restart
+    pub fn load(auth: &AuthConfig, data_dir: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut configured = Vec::new();
+        if let Some(admin_token) = &auth.admin_token {
+            configured.push(configured_token(
+                "admin",
+                admin_token,
+                &["admin".to_string()],
+            )?);
+        }
+        for token in &auth.tokens {
+            configured.push(configured_token(&token.name, &token.token, &token.scopes)?);
+        }
+
+        let file = data_dir.map(|dir| dir.join(TOKENS_FILE));
+        let issued = match &file {
+            Some(file) if file.exists() => read_tokens(file)?,
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            configured,
+            issued: RwLock::new(issued),
+            file,
+        })
+    }
+
+    /// How many tokens can be used (configured and issued)
+    pub fn len(&self) -> usize {
+        self.configured.len() + self.issued.read().map(|issued| issued.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The scopes of a token, if it exists and hasn't expired
+    pub fn scopes_of(&self, secret: &str) -> Option<Vec<Scope>> {
+        let hash = hash_secret(secret);
+        let now = chrono::Utc::now().timestamp();
+        let valid = |token: &&StoredToken| {
+            token.sha256 == hash && token.expires_at.is_none_or(|expires| now < expires)
+        };
+
+        if let Some(token) = self.configured.iter().find(valid) {
+            return Some(token.parsed.clone());
+        }
+        let issued = self.issued.read().ok()?;
+        issued.iter().find(valid).map(|token| token.parsed.clone())
+    }
+
+    /// Create a token; the response is the only place its secret appears
+    pub fn issue(&self, request: TokenRequest) -> Result<IssuedTokenResponse, TokenError> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(TokenError::Invalid("name must not be empty".to_string()));
+        }
+        if request.scopes.is_empty() {
+            return Err(TokenError::Invalid(
+                "at least one scope is needed".to_string(),
+            ));
+        }
+        let parsed = parse_scopes(&request.scopes).map_err(TokenError::Invalid)?;
+
+        let secret = new_secret();
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = request
+            .expires_in_seconds
+            .map(|seconds| now.saturating_add(i64::try_from(seconds).unwrap_or(i64::MAX)));
+        let token = StoredToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            scopes: parsed.iter().map(Scope::to_string).collect(),
+            sha256: hash_secret(&secret),
+            created_at: Some(now),
+            expires_at,
+            source: Some(TokenSource::Issued),
+            parsed,
+        };
+        let info = token.info();
+
+        let mut issued = self
+            .issued
+            .write()
+            .map_err(|e| TokenError::Io(e.to_string()))?;
+        issued.push(token);
+        self.save(&issued)?;
+        Ok(IssuedTokenResponse {
+            token: secret,
+            info,
+        })
+    }
+
+    /// Every token, without secrets (configured first)
+    pub fn list(&self) -> Vec<TokenInfo> {
+        let issued = self
+            .issued
+            .read()
+            .map(|issued| issued.clone())
+            .unwrap_or_default();
+        self.configured
+            .iter()
+            .chain(issued.iter())
+            .map(StoredToken::info)
+            .collect()
+    }
+
+    /// Delete an issued token (requests with it fail from now on)
+    pub fn revoke(&self, id: &str) -> Result<TokenInfo, TokenError> {
+        if let Some(token) = self.configured.iter().find(|token| token.id == id) {
+            return Err(TokenError::Configured(token.name.clone()));
+        }
+        let mut issued = self
+            .issued
+            .write()
+            .map_err(|e| TokenError::Io(e.to_string()))?;
+        let index = issued
+            .iter()
+            .position(|token| token.id == id)
+            .ok_or_else(|| TokenError::NotFound(id.to_string()))?;
+        let token = issued.remove(index);
+        self.save(&issued)?;
+        Ok(token.info())
+    }
+
+    /// Helper: Write the issued tokens to `tokens.json` (if there is a data dir)
+    ///
+    /// For Students:
+    /// Written to a temporary file first, then renamed over the old one -
+    /// a crash halfway leaves the old file intact instead of half a file.
+    fn save(&self, issued: &[StoredToken]) -> Result<(), TokenError> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(issued).map_err(|e| TokenError::Io(e.to_string()))?;
+        let temporary = file.with_extension("json.tmp");
+        std::fs::write(&temporary, json)
+            .and_then(|_| std::fs::rename(&temporary, file))
+            .map_err(|e| TokenError::Io(format!("{}: {}", file.display(), e)))
+    }
+}
+
+/// Helper: A token from `[auth]` (its id is derived from its name)
+fn configured_token(
+    name: &str,
+    secret: &str,
+    scopes: &[String],
+) -> Result<StoredToken, ConfigError> {
+    let parsed = parse_scopes(scopes).map_err(|e| ConfigError::invalid("auth.tokens", e))?;
+    Ok(StoredToken {
+        id: format!("config:{}", name),
+        name: name.to_string(),
+        scopes: scopes.to_vec(),
+        sha256: hash_secret(secret),
+        created_at: None,
+        expires_at: None,
+        source: Some(TokenSource::Config),
+        parsed,
+    })
+}
+
+/// Helper: Issued tokens from `tokens.json`
+fn read_tokens(file: &Path) -> Result<Vec<StoredToken>, ConfigError> {
+    let invalid = |message: String| {
+        ConfigError::invalid(
+            "storage.data_dir",
+            format!("{}: {}", file.display(), message),
+        )
+    };
+    let text = std::fs::read_to_string(file).map_err(|e| invalid(e.to_string()))?;
+    let mut tokens: Vec<StoredToken> =
+        serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+    for token in &mut tokens {
+        token.parsed = parse_scopes(&token.scopes).map_err(invalid)?;
+        token.source = Some(TokenSource::Issued);
+    }
+    Ok(tokens)
+}
+
+/// Helper: 256 random bits, hex, with a prefix that makes leaked tokens easy to grep for
+fn new_secret() -> String {
+    format!("anttp_{}", hex::encode(rand::random::<[u8; 32]>()))
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(text: &str) -> Scope {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn scopes_parse_and_print_back() {
+        for text in [
+            "admin",
+            "read:archive",
+            "write:key_value/notes",
+            "read:*",
+            "write:pnr/my-site",
+        ] {
+            assert_eq!(scope(text).to_string(), text);
+        }
+        assert_eq!(
+            scope("write:key_value/notes"),
+            Scope {
+                access: Access::Write,
+                data_type: Some("key_value".to_string()),
+                name: Some("notes".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn bad_scopes_are_refused() {
+        for bad in [
+            "",
+            "archive",
+            "read",
+            "read:",
+            "Read:archive",
+            "delete:archive",
+            "admin:archive",
+            "read:archive/",
+            "read:*/notes",
+            "read:nothing",
+            "read:admin",
+            "read:system",
+            "write:docs",
+        ] {
+            assert!(bad.parse::<Scope>().is_err(), "'{}' should be refused", bad);
+        }
+        let error = parse_scopes(&["read:archive".to_string(), "read:nope".to_string()]);
+        assert!(error.unwrap_err().contains("unknown data type 'nope'"));
+    }
+
+    #[test]
+    fn scopes_allow_what_they_name() {
+        let notes = scope("write:key_value/notes");
+        assert!(notes.allows(Access::Read, "key_value", Some("notes")));
+        assert!(notes.allows(Access::Write, "key_value", Some("notes")));
+        assert!(!notes.allows(Access::Write, "key_value", Some("photos")));
+        assert!(!notes.allows(Access::Write, "key_value", None));
+        assert!(!notes.allows(Access::Write, "archive", None));
+        assert!(!notes.allows(Access::Admin, "key_value", Some("notes")));
+        assert!(notes.allows_some_name(Access::Write, "key_value"));
+        assert!(!notes.allows_some_name(Access::Write, "pnr"));
+
+        let reader = scope("read:*");
+        assert!(reader.allows(Access::Read, "chunk", None));
+        assert!(!reader.allows(Access::Write, "chunk", None));
+        assert!(!reader.allows_some_name(Access::Write, "key_value"));
+        assert!(scope("admin").allows(Access::Admin, "admin", None));
+    }
+
+    #[test]
+    fn secrets_are_256_random_bits() {
+        let secret = new_secret();
+        let hex = secret.strip_prefix("anttp_").unwrap();
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(secret, new_secret());
+    }
+}